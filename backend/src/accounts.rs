@@ -0,0 +1,147 @@
+use crate::{failure, failure::Failure, parse_json_body, repository::Token, State};
+use argon2::{
+    password_hash::{rand_core::OsRng, rand_core::RngCore, SaltString},
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use async_trait::async_trait;
+use common::{ApiError, Credentials, ErrorCode, Session};
+use rocket::{
+    data::{FromData, Outcome, ToByteUnit},
+    request::FromRequest,
+    Data, Request,
+};
+use std::sync::OnceLock;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Authenticated user, extracted from the bearer token of the `Authorization` header.
+pub struct User {
+    pub name: String,
+    /// Value of the bearer token used to authenticate the request.
+    pub token: String,
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for User {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
+        let state = match request.rocket().state::<State>() {
+            Some(state) => state,
+            None => {
+                return failure::reject(
+                    request,
+                    ApiError::new(ErrorCode::Internal, "missing server state"),
+                )
+            }
+        };
+        match authenticate(request, state) {
+            Ok(user) => rocket::outcome::Outcome::Success(user),
+            Err(error) => failure::reject(request, error),
+        }
+    }
+}
+
+/// User authenticated by the bearer token of `request`, the error telling the client why the
+/// request could not be authenticated.
+pub fn authenticate(request: &Request<'_>, state: &State) -> Result<User, ApiError> {
+    let authorization = request
+        .headers()
+        .get_one("Authorization")
+        .ok_or(ApiError::new(ErrorCode::MissingToken, "you need to log in"))?;
+    let parts: Vec<&str> = authorization.trim().split(' ').collect();
+    if parts.len() != 2 || parts[0] != "Bearer" {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "invalid authorization header",
+        ));
+    }
+    match state.tokens.get(parts[1]) {
+        Ok(Some(token)) => {
+            if token.revoked_at.is_some() {
+                Err(ApiError::new(
+                    ErrorCode::RevokedToken,
+                    "you logged out, please log in again",
+                ))
+            } else if token.expires_at <= state.clock.now() {
+                Err(ApiError::new(
+                    ErrorCode::ExpiredToken,
+                    "your session expired, please log in again",
+                ))
+            } else {
+                Ok(User {
+                    name: token.username,
+                    token: token.value,
+                })
+            }
+        }
+        Ok(None) => Err(ApiError::new(
+            ErrorCode::InvalidToken,
+            "invalid session, please log in again",
+        )),
+        Err(error) => Err(Failure::from(error).error),
+    }
+}
+
+pub struct CredentialsData {
+    pub credentials: Credentials,
+}
+
+#[async_trait]
+impl<'r> FromData<'r> for CredentialsData {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        parse_json_body(request, data, 1.kibibytes())
+            .await
+            .map(|credentials| CredentialsData { credentials })
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, Failure> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(Failure::internal)
+}
+
+/// Hash of a password nobody knows, verified when logging in with an unknown username so that
+/// it takes as long as with a known one.
+pub fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    // hashing only fails on invalid parameters, the empty hash then matches no password
+    HASH.get_or_init(|| hash_password(&generate_token()).unwrap_or_default())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Issue a new token for `username`, valid for the configured token lifetime.
+pub fn issue_token(state: &State, username: String) -> Result<Session, Failure> {
+    let token = Token {
+        value: generate_token(),
+        username,
+        expires_at: state.clock.now() + state.token_lifetime,
+        revoked_at: None,
+    };
+    state.tokens.insert(token.clone())?;
+    Ok(Session {
+        username: token.username,
+        token: token.value,
+        expires_at: token.expires_at,
+    })
+}
+
+/// Generate a random opaque token, 256 bits encoded in hexadecimal.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
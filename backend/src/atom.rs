@@ -0,0 +1,93 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use common::Event;
+
+const FEED_TITLE: &str = "join my ride";
+
+/// Atom (RFC 4287) feed of `events`, identified by `id`, `now` being the time the feed is
+/// generated at.
+///
+/// The entries link to the pages of the events on the frontend served at `public_url`, which
+/// also identify them. The links are absolute as not every feed reader resolves relative ones.
+pub fn feed(events: &[Event], id: &str, public_url: &str, now: DateTime<Utc>) -> String {
+    let updated = events
+        .iter()
+        .filter_map(|event| event.published_at)
+        .max()
+        .unwrap_or(now);
+    let mut xml = String::new();
+    xml.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    xml.push_str(&format!("<id>{}</id>", escape(id)));
+    xml.push_str(&format!("<title>{}</title>", FEED_TITLE));
+    xml.push_str(&format!("<updated>{}</updated>", timestamp(&updated)));
+    xml.push_str(&format!("<author><name>{}</name></author>", FEED_TITLE));
+    xml.push_str(&format!(r#"<link href="{}/"/>"#, escape(public_url)));
+    for event in events {
+        entry(event, public_url, &mut xml);
+    }
+    xml.push_str("</feed>");
+    xml
+}
+
+fn entry(event: &Event, public_url: &str, xml: &mut String) {
+    // the events published before the date was recorded are older than any other
+    let published_at = event.published_at.unwrap_or(DateTime::UNIX_EPOCH);
+    xml.push_str("<entry>");
+    let url = escape(&format!("{}/event/{}", public_url, event.id));
+    xml.push_str(&format!("<id>{}</id>", url));
+    xml.push_str(&format!("<title>{}</title>", escape(&event.name)));
+    xml.push_str(&format!("<updated>{}</updated>", timestamp(&published_at)));
+    xml.push_str(&format!(r#"<link href="{}"/>"#, url));
+    if let Some(organizer) = &event.organizer {
+        xml.push_str(&format!(
+            "<author><name>{}</name></author>",
+            escape(organizer)
+        ));
+    }
+    xml.push_str(&format!("<summary>{}</summary>", escape(&summary(event))));
+    xml.push_str("</entry>");
+}
+
+/// When, where and what kind of ride, in the timezone of the organizer.
+fn summary(event: &Event) -> String {
+    let mut parts = vec![];
+    if let Some(start) = event.start {
+        parts.push(format!("starts {}", start.format("%A %-d %B %Y, %H:%M")));
+    }
+    if let Some(meeting_point) = &event.meeting_point {
+        parts.push(format!("meeting point: {}", meeting_point.address));
+    }
+    if let Some(discipline) = event.discipline {
+        parts.push(discipline.label().to_owned());
+    }
+    if let Some(pace) = event.pace {
+        parts.push(format!("pace: {}", pace.label()));
+    }
+    if let Some(difficulty) = event.difficulty {
+        parts.push(format!("difficulty: {}", difficulty));
+    }
+    if event.no_drop {
+        parts.push("no-drop".to_owned());
+    }
+    parts.join(", ")
+}
+
+fn timestamp(datetime: &DateTime<Utc>) -> String {
+    datetime.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Escape the characters having a meaning in XML text and attributes.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
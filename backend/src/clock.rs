@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+
+/// Source of the current time, injected so that tests can control it.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[cfg(test)]
+pub use fake::FakeClock;
+
+#[cfg(test)]
+mod fake {
+    use super::Clock;
+    use chrono::{DateTime, Duration, Utc};
+    use std::sync::Mutex;
+
+    /// Clock that only moves forward when told to.
+    pub struct FakeClock {
+        now: Mutex<DateTime<Utc>>,
+    }
+
+    impl FakeClock {
+        pub fn new(now: DateTime<Utc>) -> FakeClock {
+            FakeClock {
+                now: Mutex::new(now),
+            }
+        }
+
+        pub fn advance(&self, duration: Duration) {
+            let mut now = self.now.lock().unwrap();
+            *now += duration;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.now.lock().unwrap()
+        }
+    }
+}
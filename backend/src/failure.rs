@@ -0,0 +1,82 @@
+use crate::repository;
+use common::{ApiError, ErrorCode};
+use rocket::{
+    http::{ContentType, Status},
+    response::{self, Responder},
+    Request, Response,
+};
+use std::io::Cursor;
+
+/// Failed response of the API, the body is the serialized `ApiError`.
+#[derive(Debug)]
+pub struct Failure {
+    pub status: Status,
+    pub error: ApiError,
+    /// Seconds after which the request can be sent again, told in the `Retry-After` header.
+    pub retry_after: Option<u64>,
+}
+
+impl Failure {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Failure {
+        Failure::from(ApiError::new(code, message))
+    }
+
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Failure {
+        Failure::from(ApiError::invalid_field(field, message))
+    }
+
+    pub fn internal(error: impl ToString) -> Failure {
+        Failure::new(ErrorCode::Internal, error.to_string())
+    }
+}
+
+impl From<ApiError> for Failure {
+    fn from(error: ApiError) -> Failure {
+        Failure {
+            status: Status::from_code(error.code.status()).unwrap_or(Status::InternalServerError),
+            error,
+            retry_after: None,
+        }
+    }
+}
+
+impl From<repository::Error> for Failure {
+    fn from(error: repository::Error) -> Failure {
+        let code = match error {
+            repository::Error::NotFound => ErrorCode::NotFound,
+            repository::Error::AlreadyExists => ErrorCode::Conflict,
+            repository::Error::Storage(_) => ErrorCode::Internal,
+        };
+        Failure::new(code, error.to_string())
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Failure {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let body = serde_json::to_string(&self.error).map_err(|_| Status::InternalServerError)?;
+        let mut response = Response::build();
+        response
+            .status(self.status)
+            .header(ContentType::JSON)
+            .sized_body(body.len(), Cursor::new(body));
+        if let Some(retry_after) = self.retry_after {
+            response.raw_header("Retry-After", retry_after.to_string());
+        }
+        response.ok()
+    }
+}
+
+/// Fail a request guard, the default catcher sends `error` as the body of the response.
+pub fn reject<T, F>(
+    request: &Request<'_>,
+    error: ApiError,
+) -> rocket::outcome::Outcome<T, (Status, ApiError), F> {
+    let status = Failure::from(error.clone()).status;
+    request.local_cache(|| Some(error.clone()));
+    rocket::outcome::Outcome::Failure((status, error))
+}
+
+/// Error sent by a failed request guard, if any.
+pub fn rejection(request: &Request<'_>) -> Option<ApiError> {
+    request.local_cache(|| None::<ApiError>).clone()
+}
@@ -0,0 +1,107 @@
+use common::{ApiError, ErrorCode, FieldError, Route, RoutePoint};
+use roxmltree::{Document, Node};
+use std::str::FromStr;
+
+/// Beyond this number of invalid points, the other errors of the file are not reported.
+const MAX_FIELD_ERRORS: usize = 20;
+
+/// Read the track segments and the routes of a GPX 1.0 or 1.1 file.
+///
+/// The invalid points are reported as field errors named after their position in the file, e.g.
+/// `trk[0].trkseg[2].trkpt[5].lat`.
+pub fn parse(gpx: &str) -> Result<Route, ApiError> {
+    let document = Document::parse(gpx)
+        .map_err(|error| ApiError::invalid_field("gpx", format!("malformed XML: {}", error)))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "gpx" {
+        return Err(ApiError::invalid_field(
+            "gpx",
+            "the root element must be gpx",
+        ));
+    }
+    let mut route = Route::default();
+    let mut errors = vec![];
+    for (track_index, track) in children(root, "trk").enumerate() {
+        for (segment_index, segment) in children(track, "trkseg").enumerate() {
+            let path = format!("trk[{}].trkseg[{}]", track_index, segment_index);
+            route
+                .segments
+                .push(points(segment, "trkpt", &path, &mut errors));
+        }
+    }
+    for (route_index, gpx_route) in children(root, "rte").enumerate() {
+        let path = format!("rte[{}]", route_index);
+        route
+            .segments
+            .push(points(gpx_route, "rtept", &path, &mut errors));
+    }
+    if !errors.is_empty() {
+        errors.truncate(MAX_FIELD_ERRORS);
+        return Err(ApiError {
+            code: ErrorCode::Validation,
+            message: "invalid GPX file".to_owned(),
+            fields: errors,
+        });
+    }
+    route.segments.retain(|segment| !segment.is_empty());
+    if route.segments.is_empty() {
+        return Err(ApiError::invalid_field(
+            "gpx",
+            "the file has no track or route point",
+        ));
+    }
+    Ok(route)
+}
+
+/// Child elements of `node` named `name`, whatever their namespace.
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn points(
+    parent: Node,
+    name: &'static str,
+    path: &str,
+    errors: &mut Vec<FieldError>,
+) -> Vec<RoutePoint> {
+    children(parent, name)
+        .enumerate()
+        .filter_map(|(index, node)| point(node, &format!("{}.{}[{}]", path, name, index), errors))
+        .collect()
+}
+
+fn point(node: Node, path: &str, errors: &mut Vec<FieldError>) -> Option<RoutePoint> {
+    let mut number = |field: &str, value: Option<&str>, range: std::ops::RangeInclusive<f64>| {
+        let number = value
+            .and_then(|value| f64::from_str(value.trim()).ok())
+            .filter(|number| range.contains(number));
+        if number.is_none() {
+            errors.push(FieldError {
+                field: format!("{}.{}", path, field),
+                message: format!(
+                    "the {} must be a number between {} and {}",
+                    field,
+                    range.start(),
+                    range.end()
+                ),
+            });
+        }
+        number
+    };
+    let latitude = number("lat", node.attribute("lat"), -90.0..=90.0);
+    let longitude = number("lon", node.attribute("lon"), -180.0..=180.0);
+    let elevation = match children(node, "ele").next() {
+        // from the Dead Sea to the summit of the Everest, with some margin
+        Some(ele) => Some(number("ele", ele.text(), -1_000.0..=10_000.0)?),
+        None => None,
+    };
+    Some(RoutePoint {
+        latitude: latitude?,
+        longitude: longitude?,
+        elevation,
+    })
+}
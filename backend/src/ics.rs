@@ -0,0 +1,112 @@
+use chrono::{DateTime, TimeZone, Utc};
+use common::{Event, EventStatus};
+
+const PRODUCT_ID: &str = "-//join my ride//EN";
+/// Lines longer than this number of octets are folded, as required by RFC 5545.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// iCalendar (RFC 5545) calendar with a VEVENT per event, `now` being the time the calendar is
+/// generated at. Events without a start cannot be scheduled and are left out.
+///
+/// The UIDs are derived from the IDs of the events, so that calendar applications update the
+/// rides they already know when the calendar is fetched again. The sequence number is the
+/// revision of the event, which tells them whether their copy is outdated.
+pub fn calendar(events: &[Event], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_owned(),
+    ];
+    for event in events {
+        vevent(event, now, &mut lines);
+    }
+    lines.push("END:VCALENDAR".to_owned());
+    lines
+        .iter()
+        .map(|line| fold(line) + "\r\n")
+        .collect::<String>()
+}
+
+fn vevent(event: &Event, now: DateTime<Utc>, lines: &mut Vec<String>) {
+    let start = match event.start {
+        Some(start) => start,
+        None => return,
+    };
+    lines.push("BEGIN:VEVENT".to_owned());
+    lines.push(format!("UID:{}@join-my-ride", event.id));
+    lines.push(format!("DTSTAMP:{}", utc(&now)));
+    lines.push(format!("SEQUENCE:{}", event.revision));
+    lines.push(format!("DTSTART:{}", utc(&start)));
+    if let Some(end) = event.end {
+        lines.push(format!("DTEND:{}", utc(&end)));
+    }
+    lines.push(format!("SUMMARY:{}", escape(&event.name)));
+    if let Some(meeting_point) = &event.meeting_point {
+        lines.push(format!("LOCATION:{}", escape(&meeting_point.address)));
+        lines.push(format!(
+            "GEO:{};{}",
+            meeting_point.latitude, meeting_point.longitude
+        ));
+    }
+    let mut description = vec![];
+    if let Some(organizer) = &event.organizer {
+        description.push(format!("organized by {}", organizer));
+    }
+    if let Some(reason) = &event.cancellation_reason {
+        description.push(format!("cancelled: {}", reason));
+    }
+    if !description.is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape(&description.join("\n"))));
+    }
+    lines.push(format!(
+        "STATUS:{}",
+        match event.status {
+            EventStatus::Draft => "TENTATIVE",
+            EventStatus::Published | EventStatus::Completed => "CONFIRMED",
+            EventStatus::Cancelled => "CANCELLED",
+        }
+    ));
+    lines.push("END:VEVENT".to_owned());
+}
+
+/// Date and time in UTC in the basic format of iCalendar, e.g. `20300601T070000Z`.
+fn utc<Tz: TimeZone>(datetime: &DateTime<Tz>) -> String {
+    datetime
+        .with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Escape the characters having a meaning in the TEXT values of iCalendar.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Split `line` in lines of at most 75 octets, the continuation lines starting with a space.
+/// Characters are never split.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for character in line.chars() {
+        if octets + character.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // the leading space counts in the length of the continuation line
+            octets = 1;
+        }
+        folded.push(character);
+        octets += character.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,125 @@
+use crate::{
+    notification::{self, Notification},
+    repository::{self, Reminder},
+    State,
+};
+use chrono::{DateTime, Duration, Utc};
+use common::{Event, EventStatus, EventUpdate};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    tokio::{select, task, time},
+    Orbit, Rocket,
+};
+use std::sync::Arc;
+
+/// How long before the start of a ride its participants are reminded of it.
+const REMINDER_ADVANCE_HOURS: i64 = 24;
+
+/// Run the jobs which are due at the time of the clock of `state`, then send the emails queued
+/// since the last run. Running them again does nothing more, what they did is stored along with
+/// the events.
+pub fn run(state: &State) {
+    let now = state.clock.now();
+    match state.events.all() {
+        Ok(events) => {
+            for event in &events {
+                send_reminders(state, event, now);
+                complete(state, event, now);
+            }
+        }
+        Err(error) => error!("cannot run the jobs: {}", error),
+    }
+    notification::send_queued_emails(state);
+}
+
+/// Remind the participants of `event` if it starts within a day. Each reminder is recorded before
+/// it is sent, so that it is sent at most once even if the server stops in between.
+fn send_reminders(state: &State, event: &Event, now: DateTime<Utc>) {
+    let start = match event.start {
+        Some(start) if event.status == EventStatus::Published => start.with_timezone(&Utc),
+        _ => return,
+    };
+    if start <= now || start - now > Duration::hours(REMINDER_ADVANCE_HOURS) {
+        return;
+    }
+    for username in &event.participants {
+        let reminder = Reminder {
+            event_id: event.id,
+            username: username.clone(),
+            start,
+        };
+        match state.reminders.insert(reminder) {
+            Ok(()) => notification::notify(state, [username], Notification::Reminder(event)),
+            Err(repository::Error::AlreadyExists) => {}
+            Err(error) => error!("cannot remind {} of {}: {}", username, event.id, error),
+        }
+    }
+}
+
+/// Mark `event` completed once it ended, or once it started if its end is not known.
+fn complete(state: &State, event: &Event, now: DateTime<Utc>) {
+    let ended = event
+        .end
+        .or(event.start)
+        .is_some_and(|end| end.with_timezone(&Utc) <= now);
+    if event.status != EventStatus::Published || !ended {
+        return;
+    }
+    let mut completed = false;
+    let result = state.events.update(event.id, &mut |event| {
+        // the event may have changed since it was read
+        if event.status == EventStatus::Published {
+            event.status = EventStatus::Completed;
+            event.revision += 1;
+            completed = true;
+        }
+    });
+    match result {
+        Ok(event) if completed => state.updates.send(EventUpdate::Updated { event }),
+        Ok(_) => {}
+        Err(error) => error!("cannot complete {}: {}", event.id, error),
+    }
+}
+
+/// Runs the jobs every `interval` while the server is running, starting when it launches so that
+/// the jobs missed while it was stopped are caught up.
+pub struct Scheduler {
+    pub interval: std::time::Duration,
+}
+
+#[rocket::async_trait]
+impl Fairing for Scheduler {
+    fn info(&self) -> Info {
+        Info {
+            name: "Scheduler",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let state = match rocket.state::<State>() {
+            Some(state) => Arc::new(state.clone()),
+            None => {
+                error!("the scheduler cannot run without the state of the server");
+                return;
+            }
+        };
+        let mut interval = time::interval(self.interval);
+        interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        let mut shutdown = rocket.shutdown();
+        rocket::tokio::spawn(async move {
+            loop {
+                select! {
+                    _ = interval.tick() => {
+                        // the repositories and the mailer block
+                        let state = state.clone();
+                        if let Err(error) = task::spawn_blocking(move || run(&state)).await {
+                            error!("the jobs failed: {}", error);
+                        }
+                    }
+                    _ = &mut shutdown => break,
+                }
+            }
+        });
+    }
+}
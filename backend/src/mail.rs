@@ -0,0 +1,201 @@
+use chrono::Utc;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    Message, SmtpTransport, Transport,
+};
+use rocket::serde::Deserialize;
+use std::{fmt::Display, fs::OpenOptions, io::Write, path::PathBuf, sync::Mutex};
+
+/// Sender of the emails unless the `mail.from` setting says otherwise.
+pub const DEFAULT_FROM: &str = "join my ride <noreply@localhost>";
+
+/// Plain text email.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Error(String);
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot send email: {}", self.0)
+    }
+}
+
+fn mail_error(error: impl Display) -> Error {
+    Error(error.to_string())
+}
+
+/// Delivers the emails sent by the server.
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), Error>;
+}
+
+/// Settings of the SMTP server relaying the emails, read from the `mail.smtp` setting.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct SmtpSettings {
+    pub host: String,
+    /// Defaults to 587 with STARTTLS, 25 without TLS.
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Only disable it for a relay on the same machine.
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+}
+
+fn default_tls() -> bool {
+    true
+}
+
+/// Sends the emails through an SMTP relay.
+pub struct SmtpMailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(settings: &SmtpSettings, from: &str) -> Result<SmtpMailer, Error> {
+        let mut builder = if settings.tls {
+            SmtpTransport::starttls_relay(&settings.host).map_err(mail_error)?
+        } else {
+            SmtpTransport::builder_dangerous(&settings.host)
+        };
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(SmtpMailer {
+            transport: builder.build(),
+            from: from.parse().map_err(mail_error)?,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &Email) -> Result<(), Error> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse().map_err(mail_error)?)
+            .subject(&email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body.clone())
+            .map_err(mail_error)?;
+        self.transport.send(&message).map_err(mail_error)?;
+        Ok(())
+    }
+}
+
+/// Drops the emails, for the tests not reading them.
+#[cfg(test)]
+pub struct NullMailer;
+
+#[cfg(test)]
+impl Mailer for NullMailer {
+    fn send(&self, _email: &Email) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Appends the emails to a local mbox file instead of sending them, so that they can be read
+/// with a mail client during development and checked by the tests.
+pub struct MboxMailer {
+    path: PathBuf,
+    from: String,
+    /// Keeps the emails sent concurrently from interleaving.
+    file: Mutex<()>,
+}
+
+impl MboxMailer {
+    pub fn new(path: impl Into<PathBuf>, from: &str) -> MboxMailer {
+        MboxMailer {
+            path: path.into(),
+            from: from.to_owned(),
+            file: Mutex::new(()),
+        }
+    }
+
+    /// Emails appended to the mbox file so far, oldest first.
+    #[cfg(test)]
+    pub fn emails(&self) -> Result<Vec<Email>, Error> {
+        let mbox = match std::fs::read_to_string(&self.path) {
+            Ok(mbox) => mbox,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(mail_error(error)),
+        };
+        let mut emails = vec![];
+        for message in mbox
+            .split("\n\nFrom ")
+            .filter(|message| !message.is_empty())
+        {
+            let (headers, body) = message.split_once("\n\n").unwrap_or((message, ""));
+            let header = |name: &str| {
+                headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+                    .unwrap_or_default()
+                    .to_owned()
+            };
+            let body: Vec<&str> = body
+                .trim_end_matches('\n')
+                .lines()
+                .map(|line| match line.strip_prefix('>') {
+                    Some(unquoted) if unquoted.trim_start_matches('>').starts_with("From ") => {
+                        unquoted
+                    }
+                    _ => line,
+                })
+                .collect();
+            emails.push(Email {
+                to: header("To"),
+                subject: header("Subject"),
+                body: body.join("\n"),
+            });
+        }
+        Ok(emails)
+    }
+}
+
+impl Mailer for MboxMailer {
+    fn send(&self, email: &Email) -> Result<(), Error> {
+        let now = Utc::now();
+        let sender = self
+            .from
+            .parse::<Mailbox>()
+            .map(|mailbox| mailbox.email.to_string())
+            .unwrap_or_else(|_| self.from.clone());
+        let mut message = format!(
+            "From {} {}\nFrom: {}\nTo: {}\nSubject: {}\nDate: {}\nContent-Type: text/plain; charset=utf-8\n\n",
+            sender,
+            now.format("%a %b %e %H:%M:%S %Y"),
+            self.from,
+            email.to,
+            email.subject,
+            now.to_rfc2822(),
+        );
+        // mboxrd quoting, the lines looking like the separator of the messages get one more `>`
+        for line in email.body.lines() {
+            if line.trim_start_matches('>').starts_with("From ") {
+                message.push('>');
+            }
+            message.push_str(line);
+            message.push('\n');
+        }
+        message.push('\n');
+        let _file = self.file.lock().map_err(mail_error)?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(message.as_bytes()))
+            .map_err(mail_error)
+    }
+}
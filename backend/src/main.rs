@@ -1,42 +1,216 @@
+use accounts::User;
 use async_trait::async_trait;
-use common::Event;
+use chrono::{DateTime, Duration, FixedOffset, Offset, Utc};
+use clock::Clock;
+use common::{
+    ApiError, CalendarFeed, Cancellation, Comment, CommentText, EmailAddress, ErrorCode, Event,
+    EventPatch, EventSort, EventStatus, EventUpdate, Id, MeetingPoint, NewEvent, Recurrence,
+    Series, Tz,
+};
+use failure::Failure;
+use mail::Mailer;
+use notification::Notification;
+use rate_limit::RateLimit;
+use repository::{
+    Account, CommentRepository, EventRepository, FeedTokenRepository, NotificationRepository,
+    OutboxRepository, Recipient, RecipientRepository, ReminderRepository, RouteRepository,
+    TokenRepository, UserRepository,
+};
 use rocket::{
-    data::Outcome,
-    data::{FromData, ToByteUnit},
+    data::{ByteUnit, FromData, Outcome, ToByteUnit},
+    figment::Figment,
     fs::NamedFile,
-    http::Status,
-    request::FromRequest,
-    response::status::NotFound,
-    Data, Request,
+    http::{ContentType, Status},
+    response::{
+        content::RawHtml,
+        status::Created,
+        stream::{Event as ServerSentEvent, EventStream},
+    },
+    tokio::{select, sync::broadcast::error::RecvError},
+    Build, Data, Request, Rocket, Shutdown,
 };
 use std::{
-    collections::HashMap,
-    ops::DerefMut,
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Mutex,
+    sync::Arc,
 };
 
 #[macro_use]
 extern crate rocket;
 
+mod accounts;
+mod atom;
+mod clock;
+mod failure;
+mod gpx;
+mod ics;
+mod jobs;
+mod mail;
+mod notification;
+mod rate_limit;
+mod repository;
+// the `FromForm` derive of this version of rocket allows a lint which recent compilers removed
+#[allow(renamed_and_removed_lints)]
+mod search;
+mod updates;
+
+/// Default lifetime of the issued tokens, can be changed with the `token_lifetime` setting (in seconds).
+const DEFAULT_TOKEN_LIFETIME: i64 = 7 * 24 * 60 * 60;
+
+/// Default size limit of the uploaded GPX files, can be changed with the `limits.gpx` setting.
+const DEFAULT_GPX_LIMIT_MIB: u64 = 5;
+
+/// Maximum number of characters of a comment.
+const MAX_COMMENT_LENGTH: usize = 2000;
+
+/// Default URL at which the users reach the server, used in the links of the emails. Can be
+/// changed with the `public_url` setting.
+const DEFAULT_PUBLIC_URL: &str = "http://localhost:8000";
+
+/// Default file the emails are appended to when no SMTP server is configured, can be changed
+/// with the `mail.mbox` setting.
+const DEFAULT_MBOX: &str = "join_my_ride.mbox";
+
+/// Default number of seconds between two runs of the scheduled jobs, can be changed with the
+/// `jobs_interval` setting.
+const DEFAULT_JOBS_INTERVAL: u64 = 60;
+
+/// Shared by the routes and the scheduler, the clones of the state share its repositories.
+#[derive(Clone)]
 struct State {
-    events: Mutex<Vec<Event>>,
+    events: Arc<dyn EventRepository>,
+    users: Arc<dyn UserRepository>,
+    tokens: Arc<dyn TokenRepository>,
+    routes: Arc<dyn RouteRepository>,
+    feed_tokens: Arc<dyn FeedTokenRepository>,
+    comments: Arc<dyn CommentRepository>,
+    recipients: Arc<dyn RecipientRepository>,
+    notifications: Arc<dyn NotificationRepository>,
+    reminders: Arc<dyn ReminderRepository>,
+    outbox: Arc<dyn OutboxRepository>,
+    updates: updates::Broadcaster,
+    rate_limiter: Arc<rate_limit::RateLimiter>,
+    mailer: Arc<dyn Mailer>,
+    clock: Arc<dyn Clock>,
+    token_lifetime: Duration,
+    public_url: String,
+    /// Time between two runs of the scheduled jobs, `None` to only run them by hand as the tests
+    /// do to control when they run.
+    jobs_interval: Option<std::time::Duration>,
 }
 
 impl State {
-    fn new() -> State {
-        // TODO(hard-coded): get events from database
+    fn sqlite(database: repository::Database) -> State {
+        State {
+            events: Arc::new(repository::SqliteEventRepository::new(database.clone())),
+            users: Arc::new(repository::SqliteUserRepository::new(database.clone())),
+            tokens: Arc::new(repository::SqliteTokenRepository::new(database.clone())),
+            routes: Arc::new(repository::SqliteRouteRepository::new(database.clone())),
+            feed_tokens: Arc::new(repository::SqliteFeedTokenRepository::new(database.clone())),
+            comments: Arc::new(repository::SqliteCommentRepository::new(database.clone())),
+            recipients: Arc::new(repository::SqliteRecipientRepository::new(database.clone())),
+            notifications: Arc::new(repository::SqliteNotificationRepository::new(
+                database.clone(),
+            )),
+            reminders: Arc::new(repository::SqliteReminderRepository::new(database.clone())),
+            outbox: Arc::new(repository::SqliteOutboxRepository::new(database)),
+            updates: updates::Broadcaster::new(),
+            rate_limiter: Arc::new(rate_limit::RateLimiter::default()),
+            mailer: Arc::new(mail::MboxMailer::new(DEFAULT_MBOX, mail::DEFAULT_FROM)),
+            clock: Arc::new(clock::SystemClock),
+            token_lifetime: Duration::seconds(DEFAULT_TOKEN_LIFETIME),
+            public_url: DEFAULT_PUBLIC_URL.to_owned(),
+            jobs_interval: None,
+        }
+    }
+
+    #[cfg(test)]
+    fn in_memory() -> State {
         State {
-            events: Mutex::new(vec![
-                Event::new("event_1".to_owned()),
-                Event::new("event_2".to_owned()),
-                Event::new("event_3".to_owned()),
-            ]),
+            events: Arc::new(repository::InMemoryEventRepository::new()),
+            users: Arc::new(repository::InMemoryUserRepository::new()),
+            tokens: Arc::new(repository::InMemoryTokenRepository::new()),
+            routes: Arc::new(repository::InMemoryRouteRepository::new()),
+            feed_tokens: Arc::new(repository::InMemoryFeedTokenRepository::new()),
+            comments: Arc::new(repository::InMemoryCommentRepository::new()),
+            recipients: Arc::new(repository::InMemoryRecipientRepository::new()),
+            notifications: Arc::new(repository::InMemoryNotificationRepository::new()),
+            reminders: Arc::new(repository::InMemoryReminderRepository::new()),
+            outbox: Arc::new(repository::InMemoryOutboxRepository::new()),
+            updates: updates::Broadcaster::new(),
+            rate_limiter: Arc::new(rate_limit::RateLimiter::default()),
+            mailer: Arc::new(mail::NullMailer),
+            clock: Arc::new(clock::SystemClock),
+            token_lifetime: Duration::seconds(DEFAULT_TOKEN_LIFETIME),
+            public_url: DEFAULT_PUBLIC_URL.to_owned(),
+            jobs_interval: None,
         }
     }
 }
 
+fn parse_id(id_str: &str) -> Result<common::Id, Failure> {
+    common::Id::from_str(id_str)
+        .map_err(|error| Failure::new(ErrorCode::NotFound, error.to_string()))
+}
+
+fn to_json<T: rocket::serde::Serialize>(value: &T) -> Result<String, Failure> {
+    serde_json::to_string(value).map_err(Failure::internal)
+}
+
+/// Read a body of at most `limit` bytes.
+async fn read_body<'r>(
+    request: &'r Request<'_>,
+    data: Data<'r>,
+    limit: ByteUnit,
+) -> Outcome<'r, String, ApiError> {
+    match data.open(limit).into_string().await {
+        Ok(string) if string.is_complete() => Outcome::Success(string.into_inner()),
+        Ok(_) => failure::reject(
+            request,
+            ApiError::new(
+                ErrorCode::InvalidRequest,
+                format!("the body must not exceed {}", limit),
+            ),
+        ),
+        Err(error) => failure::reject(
+            request,
+            ApiError::new(ErrorCode::InvalidRequest, error.to_string()),
+        ),
+    }
+}
+
+/// Read a JSON body of at most `limit` bytes, shared by the `FromData` implementations.
+async fn parse_json_body<'r, T>(
+    request: &'r Request<'_>,
+    data: Data<'r>,
+    limit: ByteUnit,
+) -> Outcome<'r, T, ApiError>
+where
+    T: for<'a> rocket::serde::Deserialize<'a>,
+{
+    match read_body(request, data, limit).await {
+        Outcome::Success(json_str) => match serde_json::from_str(&json_str) {
+            Ok(value) => Outcome::Success(value),
+            Err(error) => failure::reject(
+                request,
+                match unknown_field(&error) {
+                    Some(field) => ApiError::invalid_field(&field, "unknown field"),
+                    None => ApiError::new(ErrorCode::InvalidRequest, error.to_string()),
+                },
+            ),
+        },
+        Outcome::Failure(failure) => Outcome::Failure(failure),
+        Outcome::Forward(data) => Outcome::Forward(data),
+    }
+}
+
+/// Field of the body rejected by a type denying the unknown fields, if that is why `error` occurred.
+fn unknown_field(error: &serde_json::Error) -> Option<String> {
+    let message = error.to_string();
+    let field = message.strip_prefix("unknown field `")?.split('`').next()?;
+    Some(field.to_owned())
+}
+
 #[get("/<_url..>")]
 async fn index(_url: PathBuf) -> Option<NamedFile> {
     NamedFile::open(Path::new("../frontend-seed/index.html"))
@@ -58,292 +232,1548 @@ async fn package_wasm() -> Option<NamedFile> {
         .ok()
 }
 
-#[get("/api/events")]
-fn events(state: &rocket::State<State>) -> String {
-    serde_json::to_string(&state.events).unwrap()
+/// Page of the events matching the filters, see `common::EventQuery` for the parameters. The
+/// drafts are left out, unless the organizer requests theirs with the status filter.
+#[get("/api/events?<params..>")]
+fn events(
+    params: search::EventQueryParams,
+    state: &rocket::State<State>,
+    viewer: Option<User>,
+) -> Result<String, Failure> {
+    let query = params.parse()?;
+    let viewer = viewer.map(|user| user.name);
+    let page = state
+        .events
+        .page(&query, viewer.as_deref(), state.clock.now())
+        .map_err(search::cursor_failure)?;
+    to_json(&page)
 }
 
-#[get("/api/event/<id_str>")]
-fn event(id_str: String, state: &rocket::State<State>) -> Option<String> {
-    let id = common::Id::from_str(&id_str).ok()?;
-    let all_events = state.events.lock().ok()?;
-    let matching_events: Vec<&Event> = all_events.iter().filter(|event| event.id == id).collect();
-    if matching_events.is_empty() {
-        None
-    } else {
-        Some(serde_json::to_string(&matching_events[0]).ok()?)
-    }
-}
-
-struct User {
-    name: String,
-}
-
-#[crate::async_trait]
-impl<'r> FromRequest<'r> for User {
-    type Error = String;
-
-    async fn from_request(request: &'r Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
-        let user_tokens: HashMap<String, String> =
-            HashMap::from([("valid_token".to_owned(), "valid_user".to_owned())]);
-        match request.headers().get_one("Authorization") {
-            Some(authorization) => {
-                let parts: Vec<&str> = authorization.trim().split(' ').collect();
-                if parts.len() != 2 || parts[0] != "Bearer" {
-                    rocket::outcome::Outcome::Failure((
-                        Status::BadRequest,
-                        "invalid authorization header".to_owned(),
-                    ))
-                } else {
-                    let token = parts[1];
-                    match user_tokens.get(token) {
-                        Some(username) => rocket::outcome::Outcome::Success(User {
-                            name: username.to_string(),
-                        }),
-                        None => rocket::outcome::Outcome::Failure((
-                            Status::BadRequest,
-                            "invalid token".to_owned(),
-                        )),
-                    }
-                }
+/// Atom feed of the most recently published events matching the filters of `/api/events`. The
+/// sort order is ignored and the drafts are left out.
+#[get("/api/events/atom?<params..>")]
+fn events_atom(
+    params: search::EventQueryParams,
+    state: &rocket::State<State>,
+) -> Result<(ContentType, String), Failure> {
+    let mut query = params.parse()?;
+    query.sort = EventSort::Newest;
+    let now = state.clock.now();
+    let page = state
+        .events
+        .page(&query, None, now)
+        .map_err(search::cursor_failure)?;
+    // the filters are part of the ID, each filtered feed being a different feed
+    let filters: Vec<String> = query
+        .params()
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    let id = format!("tag:join-my-ride,2023:events?{}", filters.join("&"));
+    Ok((
+        ContentType::new("application", "atom+xml"),
+        atom::feed(&page.items, &id, &state.public_url, now),
+    ))
+}
+
+/// Stream of the changes of the events as server-sent events, each carrying an `EventUpdate` in
+/// JSON. Clients reading too slowly skip the oldest updates.
+#[get("/api/events/updates")]
+fn event_updates(state: &rocket::State<State>, mut shutdown: Shutdown) -> EventStream![] {
+    let mut updates = state.updates.subscribe();
+    EventStream! {
+        loop {
+            let update = select! {
+                update = updates.recv() => match update {
+                    Ok(update) => update,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            // the updates always serialize, as the responses of the other routes
+            if let Ok(json) = serde_json::to_string(&update) {
+                yield ServerSentEvent::data(json);
             }
-            None => rocket::outcome::Outcome::Failure((
-                Status::BadRequest,
-                "missing authorization header".to_owned(),
-            )),
         }
     }
 }
 
+/// Event identified by `id`, a draft being only found by its organizer.
+#[get("/api/event/<id_str>")]
+fn event(
+    id_str: String,
+    state: &rocket::State<State>,
+    viewer: Option<User>,
+) -> Result<String, Failure> {
+    let event = visible_event(state, parse_id(&id_str)?, viewer.as_ref())?;
+    to_json(&event)
+}
+
+/// Event identified by `id` as `viewer` sees it, a draft being hidden from all but its organizer.
+fn visible_event(state: &State, id: common::Id, viewer: Option<&User>) -> Result<Event, Failure> {
+    state
+        .events
+        .get(id)?
+        .filter(|event| is_visible(event, viewer))
+        .ok_or(Failure::new(ErrorCode::NotFound, "event not found"))
+}
+
+/// Whether `viewer` can see `event`, which is the case of everyone but for drafts.
+fn is_visible(event: &Event, viewer: Option<&User>) -> bool {
+    event.status != EventStatus::Draft
+        || viewer.is_some_and(|viewer| event.organizer.as_ref() == Some(&viewer.name))
+}
+
+/// Join an event, or its waitlist if the event is full.
 #[put("/api/join/<id_str>")]
 fn join_event(
+    _limit: RateLimit,
     id_str: String,
     state: &rocket::State<State>,
     user: User,
-) -> Result<(), NotFound<String>> {
-    let id = common::Id::from_str(&id_str).map_err(|err| NotFound::<String>(err.to_string()))?;
-    match state.events.lock() {
-        Ok(mut guard) => {
-            let all_events = guard.deref_mut();
-            let mut found = false;
-            for i in 1..all_events.len() {
-                if all_events[i].id == id {
-                    found = true;
-                    all_events[i].participants.insert(user.name.clone());
-                }
-            }
-            if found {
-                Ok(())
-            } else {
-                Err(NotFound::<String>("event not found".to_owned()))
-            }
+) -> Result<String, Failure> {
+    let id = parse_id(&id_str)?;
+    let mut refusal = None;
+    let mut participation = None;
+    let mut already_joined = false;
+    let event = state.events.update(id, &mut |event| {
+        if !is_visible(event, Some(&user)) {
+            refusal = Some(Failure::new(ErrorCode::NotFound, "event not found"));
+        } else if event.status.can_be_joined() {
+            already_joined = event.participation(&user.name).is_some();
+            participation = Some(event.join(&user.name));
+        } else {
+            refusal = Some(Failure::new(
+                ErrorCode::Conflict,
+                format!("cannot join a {} event", event.status),
+            ));
+        }
+    })?;
+    if let Some(refusal) = refusal {
+        return Err(refusal);
+    }
+    if !already_joined {
+        let organizer = event
+            .organizer
+            .iter()
+            .filter(|organizer| **organizer != user.name);
+        notification::notify(state, organizer, Notification::Joined(&event, &user.name));
+        state.updates.send(EventUpdate::Joined {
+            event,
+            username: user.name,
+        });
+    }
+    to_json(&participation)
+}
+
+/// Leave an event or its waitlist, the first waitlisted users are promoted to the freed spot.
+#[put("/api/leave/<id_str>")]
+fn leave_event(
+    _limit: RateLimit,
+    id_str: String,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<(), Failure> {
+    let id = parse_id(&id_str)?;
+    let mut joined = false;
+    let mut promoted = vec![];
+    let event = state.events.update(id, &mut |event| {
+        joined = event.participation(&user.name).is_some();
+        if joined {
+            promoted = event.leave(&user.name);
         }
-        Err(err) => Err(NotFound::<String>(err.to_string())),
+    })?;
+    if !joined {
+        return Err(Failure::new(
+            ErrorCode::Conflict,
+            "you did not join this event",
+        ));
     }
+    notification::notify(state, &promoted, Notification::Promoted(&event));
+    state.updates.send(EventUpdate::Left {
+        event,
+        username: user.name,
+    });
+    Ok(())
 }
 
-struct EventData {
-    event: Event,
+struct NewEventData {
+    event: NewEvent,
 }
 
 #[async_trait]
-impl<'r> FromData<'r> for EventData {
-    async fn from_data(_: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
-        match data.open(256.bytes()).into_string().await {
-            Ok(json_str) => match serde_json::from_str(json_str.as_str()) {
-                Ok(event) => Outcome::Success(EventData { event: event }),
-                Err(error) => Outcome::Failure((Status::BadRequest, error.to_string())),
-            },
-            Err(error) => Outcome::Failure((Status::BadRequest, error.to_string())),
+impl<'r> FromData<'r> for NewEventData {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        parse_json_body(request, data, 1.kibibytes())
+            .await
+            .map(|event| NewEventData { event })
+    }
+}
+
+/// Create an event organized by the logged-in user, the ID is generated by the server.
+///
+/// With a recurrence, every occurrence of the series is created as an event of its own and the
+/// server responds with the first one.
+#[post("/api/events", format = "application/json", data = "<data>")]
+fn publish_event(
+    _limit: RateLimit,
+    data: NewEventData,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<Created<String>, Failure> {
+    let new_event = data.event;
+    if new_event.name.trim().is_empty() {
+        return Err(Failure::invalid_field("name", "the name is required"));
+    }
+    if new_event.status != EventStatus::Draft && new_event.status != EventStatus::Published {
+        return Err(Failure::invalid_field(
+            "status",
+            format!("cannot publish a {} event", new_event.status),
+        ));
+    }
+    if new_event.start <= state.clock.now() {
+        return Err(Failure::invalid_field(
+            "start",
+            "the start must be in the future",
+        ));
+    }
+    let end = match (new_event.end, new_event.duration_minutes) {
+        (Some(_), Some(_)) => {
+            return Err(Failure::invalid_field(
+                "duration_minutes",
+                "give either an end or a duration",
+            ))
+        }
+        (Some(end), None) => Some(end),
+        (None, Some(0)) => {
+            return Err(Failure::invalid_field(
+                "duration_minutes",
+                "the duration must be positive",
+            ))
+        }
+        (None, Some(minutes)) => Some(new_event.start + Duration::minutes(minutes.into())),
+        (None, None) => None,
+    };
+    check_end(new_event.start, end)?;
+    if let Some(meeting_point) = &new_event.meeting_point {
+        check_meeting_point(meeting_point)?;
+    }
+    check_capacity(new_event.capacity)?;
+    if let Some(timezone) = new_event.timezone {
+        check_timezone(new_event.start, timezone)?;
+    }
+    if let Some(recurrence) = &new_event.recurrence {
+        let timezone = new_event.timezone.ok_or(Failure::invalid_field(
+            "timezone",
+            "the timezone is required to repeat a ride",
+        ))?;
+        check_recurrence(recurrence, new_event.start, timezone)?;
+    }
+    let mut event = Event::new(new_event.name);
+    event.status = new_event.status;
+    event.organizer = Some(user.name);
+    event.start = Some(new_event.start);
+    event.timezone = new_event.timezone;
+    event.end = end;
+    event.meeting_point = new_event.meeting_point;
+    event.capacity = new_event.capacity;
+    event.discipline = new_event.discipline;
+    event.pace = new_event.pace;
+    event.difficulty = new_event.difficulty;
+    event.no_drop = new_event.no_drop;
+    if event.status == EventStatus::Published {
+        event.published_at = Some(state.clock.now());
+    }
+    let occurrences = match (new_event.recurrence, new_event.timezone) {
+        (Some(recurrence), Some(timezone)) => {
+            let starts = recurrence.occurrences(new_event.start, timezone);
+            event.series = Some(Series {
+                id: Id::new_v4(),
+                recurrence,
+            });
+            starts
+                .into_iter()
+                .map(|start| Event {
+                    id: Id::new_v4(),
+                    start: Some(start),
+                    end: end.map(|end| start + (end - new_event.start)),
+                    ..event.clone()
+                })
+                .collect()
         }
+        _ => vec![event],
+    };
+    // a series is created as a whole or not at all
+    state.events.insert_all(occurrences.clone())?;
+    for occurrence in &occurrences {
+        state.updates.send(EventUpdate::Created {
+            event: occurrence.clone(),
+        });
     }
+    let first = &occurrences[0];
+    Ok(Created::new(format!("/api/event/{}", first.id)).body(to_json(first)?))
+}
+
+/// Fail with a field error on `timezone` if `start` does not have the offset of `timezone` at
+/// that date.
+fn check_timezone(start: DateTime<FixedOffset>, timezone: Tz) -> Result<(), Failure> {
+    if start.with_timezone(&timezone).offset().fix() != *start.offset() {
+        return Err(Failure::invalid_field(
+            "timezone",
+            format!("the start is not a time of {}", timezone),
+        ));
+    }
+    Ok(())
+}
 
-    type Error = String;
+fn check_recurrence(
+    recurrence: &Recurrence,
+    start: DateTime<FixedOffset>,
+    timezone: Tz,
+) -> Result<(), Failure> {
+    if recurrence.count == Some(0) {
+        return Err(Failure::invalid_field(
+            "recurrence.count",
+            "the number of occurrences must be positive",
+        ));
+    }
+    if recurrence.until.is_some_and(|until| until < start) {
+        return Err(Failure::invalid_field(
+            "recurrence.until",
+            "the series must end after its first occurrence",
+        ));
+    }
+    if recurrence.ends_after_horizon(start, timezone) {
+        let field = if recurrence
+            .until
+            .is_some_and(|until| until > Recurrence::horizon(start))
+        {
+            "recurrence.until"
+        } else {
+            "recurrence.count"
+        };
+        return Err(Failure::invalid_field(
+            field,
+            format!(
+                "a series cannot last more than {} months",
+                Recurrence::HORIZON_MONTHS
+            ),
+        ));
+    }
+    Ok(())
 }
 
-#[put("/api/event", format = "application/json", data = "<data>")]
-fn publish_event(data: EventData, state: &rocket::State<State>) {
-    match state.events.lock() {
-        Ok(mut guard) => guard.deref_mut().push(data.event),
-        Err(_) => {}
+/// Fail with a field error on `end` if the event would end before it starts.
+fn check_end(
+    start: DateTime<FixedOffset>,
+    end: Option<DateTime<FixedOffset>>,
+) -> Result<(), Failure> {
+    if end.is_some_and(|end| end <= start) {
+        return Err(Failure::invalid_field(
+            "end",
+            "the end must be after the start",
+        ));
     }
+    Ok(())
 }
 
-#[launch]
-fn rocket() -> _ {
-    rocket::build().manage(State::new()).mount(
-        "/",
-        routes![
-            event,
-            events,
-            index,
-            join_event,
-            package_js,
-            package_wasm,
-            publish_event,
-        ],
+fn check_meeting_point(meeting_point: &MeetingPoint) -> Result<(), Failure> {
+    if meeting_point.address.trim().is_empty() {
+        return Err(Failure::invalid_field(
+            "meeting_point.address",
+            "the address is required",
+        ));
+    }
+    search::check_coordinates(
+        &meeting_point.coordinates(),
+        "meeting_point.latitude",
+        "meeting_point.longitude",
     )
 }
 
-#[cfg(test)]
-mod test {
-    use super::rocket;
-    use common::{Event, Id};
-    use rocket::http::{ContentType, Status};
-    use rocket::local::blocking::Client;
+fn check_capacity(capacity: Option<u32>) -> Result<(), Failure> {
+    if capacity == Some(0) {
+        return Err(Failure::invalid_field(
+            "capacity",
+            "the capacity must be at least 1",
+        ));
+    }
+    Ok(())
+}
 
-    #[test]
-    fn list_all_events() {
-        // given 3 existing events
-        // TODO(hard-coded): populate events when the events are retrieved from the database
+struct EventPatchData {
+    patch: EventPatch,
+}
 
-        // when a user requests /api/events
-        let client = Client::tracked(rocket()).expect("valid rocket instance");
-        let response = client.get(uri!("/api/events")).dispatch();
+#[async_trait]
+impl<'r> FromData<'r> for EventPatchData {
+    type Error = ApiError;
 
-        // then the server responds with a list containing all 3 events
-        assert_eq!(response.status(), Status::Ok);
-        let actual: Vec<Event> =
-            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
-        let expected = vec![
-            Event::new("event_1".to_owned()),
-            Event::new("event_2".to_owned()),
-            Event::new("event_3".to_owned()),
-        ];
-        assert_eq!(actual, expected);
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        parse_json_body(request, data, 1.kibibytes())
+            .await
+            .map(|patch| EventPatchData { patch })
     }
+}
 
-    #[test]
-    fn get_an_event_by_id() {
-        // given 3 existing events
-        // TODO(hard-coded): populate events when the events are retrieved from the database
+/// Change the fields present in the patch, only the organizer of the event is allowed to.
+#[patch("/api/event/<id_str>", format = "application/json", data = "<data>")]
+fn update_event(
+    _limit: RateLimit,
+    id_str: String,
+    data: EventPatchData,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<String, Failure> {
+    let id = parse_id(&id_str)?;
+    let patch = data.patch;
+    if patch
+        .name
+        .as_ref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        return Err(Failure::invalid_field("name", "the name is required"));
+    }
+    if patch.status == Some(EventStatus::Cancelled) {
+        return Err(Failure::invalid_field(
+            "status",
+            "events are cancelled with a reason through the cancel endpoint",
+        ));
+    }
+    if patch.status == Some(EventStatus::Completed) {
+        return Err(Failure::invalid_field(
+            "status",
+            "events are completed by the server once they end",
+        ));
+    }
+    if patch.start.is_some_and(|start| start <= state.clock.now()) {
+        return Err(Failure::invalid_field(
+            "start",
+            "the start must be in the future",
+        ));
+    }
+    if let Some(Some(meeting_point)) = &patch.meeting_point {
+        check_meeting_point(meeting_point)?;
+    }
+    check_capacity(patch.capacity.flatten())?;
+    // the checks against the stored event are made while it is locked, for it not to change
+    // between them and the update
+    let mut refusal = None;
+    let mut promoted = vec![];
+    let mut was_draft = false;
+    let event = state.events.update(id, &mut |event| {
+        refusal = check_patch(event, &patch, &user, state.clock.now()).err();
+        was_draft = event.status == EventStatus::Draft;
+        if refusal.is_none() {
+            let participants = event.participants.clone();
+            patch.apply(event);
+            event.revision += 1;
+            if event.status == EventStatus::Published && event.published_at.is_none() {
+                event.published_at = Some(state.clock.now());
+            }
+            promoted = event
+                .participants
+                .difference(&participants)
+                .cloned()
+                .collect();
+        }
+    })?;
+    if let Some(refusal) = refusal {
+        return Err(refusal);
+    }
+    // a higher capacity promotes waitlisted users, who are told so rather than of the change
+    notification::notify(state, &promoted, Notification::Promoted(&event));
+    if event.status != EventStatus::Completed {
+        notification::notify(
+            state,
+            notification::joined_users(&event, &user.name)
+                .filter(|username| !promoted.contains(username)),
+            Notification::Changed(&event),
+        );
+    }
+    // the followers did not see the draft, it is new to them once published
+    state.updates.send(if was_draft {
+        EventUpdate::Created {
+            event: event.clone(),
+        }
+    } else {
+        EventUpdate::Updated {
+            event: event.clone(),
+        }
+    });
+    to_json(&event)
+}
 
-        // when a user requests /api/event/<id>
-        let client = Client::tracked(rocket()).expect("valid rocket instance");
-        // get an existing id
-        let events: Vec<Event> = serde_json::from_str(
-            client
-                .get(uri!("/api/events"))
-                .dispatch()
-                .into_string()
-                .unwrap()
-                .as_str(),
-        )
-        .unwrap();
-        let event = events[0].clone();
-        // actual request under test
-        let uri = format!("/api/event/{}", event.id);
-        let response = client.get(uri).dispatch();
+/// Whether `user` can apply `patch` to `event` at `now`: they must organize it, it must be neither
+/// cancelled nor completed, and a draft can only be published before it starts.
+fn check_patch(
+    event: &Event,
+    patch: &EventPatch,
+    user: &User,
+    now: DateTime<Utc>,
+) -> Result<(), Failure> {
+    if event.organizer.as_ref() != Some(&user.name) {
+        return Err(Failure::new(
+            ErrorCode::Forbidden,
+            "only the organizer can change the event",
+        ));
+    }
+    if matches!(
+        event.status,
+        EventStatus::Cancelled | EventStatus::Completed
+    ) {
+        return Err(Failure::new(
+            ErrorCode::Conflict,
+            format!("a {} event cannot be changed", event.status),
+        ));
+    }
+    if let Some(status) = patch.status {
+        if status != event.status && !event.status.can_become(status) {
+            return Err(Failure::new(
+                ErrorCode::Conflict,
+                format!("a {} event cannot become {}", event.status, status),
+            ));
+        }
+        if event.status == EventStatus::Draft
+            && status == EventStatus::Published
+            && patch
+                .start
+                .or(event.start)
+                .is_some_and(|start| start <= now)
+        {
+            return Err(Failure::invalid_field(
+                "start",
+                "the start must be in the future",
+            ));
+        }
+    }
+    let capacity = patch.capacity.unwrap_or(event.capacity);
+    if capacity.is_some_and(|capacity| (capacity as usize) < event.participants.len()) {
+        return Err(Failure::new(
+            ErrorCode::Conflict,
+            format!(
+                "the capacity cannot be lower than the {} participants",
+                event.participants.len()
+            ),
+        ));
+    }
+    if let Some(start) = patch.start.or(event.start) {
+        check_end(start, patch.end.unwrap_or(event.end))?;
+    }
+    Ok(())
+}
 
-        // then the server responds with a list containing all 3 events
-        assert_eq!(response.status(), Status::Ok);
-        let actual: Event = serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
-        let expected = event;
-        assert_eq!(actual, expected);
+/// Event identified by `id`, fails with a 403 if `user` does not organize it.
+fn check_is_organizer(state: &State, id: common::Id, user: &User) -> Result<Event, Failure> {
+    let event = state
+        .events
+        .get(id)?
+        .ok_or(Failure::new(ErrorCode::NotFound, "event not found"))?;
+    if event.organizer.as_ref() != Some(&user.name) {
+        return Err(Failure::new(
+            ErrorCode::Forbidden,
+            "only the organizer can change the event",
+        ));
     }
+    Ok(event)
+}
 
-    #[test]
-    fn get_a_non_existing_event_by_id() {
-        // given 3 existing events
-        // TODO(hard-coded): populate events when the events are retrieved from the database
+struct CancellationData {
+    cancellation: Cancellation,
+}
 
-        // when a user requests a non-existing event using /api/event/<id>
-        let client = Client::tracked(rocket()).expect("valid rocket instance");
-        let uri = format!("/api/event/{}", common::Id::new_v4());
-        let response = client.get(uri).dispatch();
+#[async_trait]
+impl<'r> FromData<'r> for CancellationData {
+    type Error = ApiError;
 
-        // then the server responds with 404 not found
-        assert_eq!(response.status(), Status::NotFound);
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        parse_json_body(request, data, 1.kibibytes())
+            .await
+            .map(|cancellation| CancellationData { cancellation })
     }
+}
 
-    #[test]
-    fn publish_an_event() {
-        let published_event_name = "published_event_name";
-
-        fn has_name(name: &str) -> impl Fn(&Event) -> bool {
-            let name_owned = name.to_owned();
-            return move |event: &Event| event.name == name_owned;
+/// Cancel an event, only the organizer is allowed to and completed events cannot be cancelled.
+#[put(
+    "/api/event/<id_str>/cancel",
+    format = "application/json",
+    data = "<data>"
+)]
+fn cancel_event(
+    _limit: RateLimit,
+    id_str: String,
+    data: CancellationData,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<String, Failure> {
+    let id = parse_id(&id_str)?;
+    let reason = data.cancellation.reason.trim().to_owned();
+    if reason.is_empty() {
+        return Err(Failure::invalid_field("reason", "the reason is required"));
+    }
+    check_is_organizer(state, id, &user)?;
+    let mut refusal = None;
+    let event = state.events.update(id, &mut |event| {
+        if event.status.can_become(EventStatus::Cancelled) {
+            event.status = EventStatus::Cancelled;
+            event.cancellation_reason = Some(reason.clone());
+            event.revision += 1;
+        } else {
+            refusal = Some(format!("cannot cancel a {} event", event.status));
         }
+    })?;
+    if let Some(refusal) = refusal {
+        return Err(Failure::new(ErrorCode::Conflict, refusal));
+    }
+    notification::notify(
+        state,
+        notification::joined_users(&event, &user.name),
+        Notification::Cancelled(&event),
+    );
+    state.updates.send(EventUpdate::Updated {
+        event: event.clone(),
+    });
+    to_json(&event)
+}
 
-        let client = Client::tracked(rocket()).expect("valid rocket instance");
+struct GpxData {
+    gpx: String,
+}
 
-        // given an non-existing event
-        let response = client.get(uri!("/api/events")).dispatch();
-        assert_eq!(response.status(), Status::Ok);
-        let initial_events: Vec<Event> =
-            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
-        assert!(initial_events
-            .into_iter()
-            .filter(has_name(published_event_name))
-            .collect::<Vec<Event>>()
-            .is_empty());
+#[async_trait]
+impl<'r> FromData<'r> for GpxData {
+    type Error = ApiError;
 
-        // when a client publishes an event
-        let event = Event::new(published_event_name.to_owned());
-        let event_json = serde_json::to_string(&event).unwrap();
-        let response = client
-            .put(uri!("/api/event"))
-            .header(ContentType::JSON)
-            .body(event_json)
-            .dispatch();
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        let limit = request
+            .limits()
+            .get("gpx")
+            .unwrap_or(DEFAULT_GPX_LIMIT_MIB.mebibytes());
+        read_body(request, data, limit)
+            .await
+            .map(|gpx| GpxData { gpx })
+    }
+}
 
-        // then the server responds with a success code
-        assert_eq!(response.status(), Status::Ok);
+/// Attach the route of a GPX file to an event, replacing the previous one. Only the organizer is
+/// allowed to.
+#[put("/api/event/<id_str>/route", data = "<data>")]
+fn upload_route(
+    _limit: RateLimit,
+    id_str: String,
+    data: GpxData,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<String, Failure> {
+    let id = parse_id(&id_str)?;
+    check_is_organizer(state, id, &user)?;
+    let route = gpx::parse(&data.gpx)?;
+    let summary = route.summary();
+    state.routes.set(id, route)?;
+    let event = state.events.update(id, &mut |event| {
+        event.route = summary;
+        event.revision += 1;
+    })?;
+    notification::notify(
+        state,
+        notification::joined_users(&event, &user.name),
+        Notification::Changed(&event),
+    );
+    state.updates.send(EventUpdate::Updated {
+        event: event.clone(),
+    });
+    to_json(&event)
+}
 
-        // then the event is added to the events list
-        let response = client.get(uri!("/api/events")).dispatch();
-        assert_eq!(response.status(), Status::Ok);
-        let initial_events: Vec<Event> =
-            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
-        assert!(!initial_events
-            .into_iter()
-            .filter(has_name(published_event_name))
-            .collect::<Vec<Event>>()
-            .is_empty());
+/// Points of the route attached to an event.
+#[get("/api/event/<id_str>/route")]
+fn event_route(
+    id_str: String,
+    state: &rocket::State<State>,
+    viewer: Option<User>,
+) -> Result<String, Failure> {
+    let id = parse_id(&id_str)?;
+    visible_event(state, id, viewer.as_ref())?;
+    let route = state
+        .routes
+        .get(id)?
+        .ok_or(Failure::new(ErrorCode::NotFound, "the event has no route"))?;
+    to_json(&route)
+}
+
+/// iCalendar file of an event, to add it to a calendar application.
+#[get("/api/event/<id_str>/ics")]
+fn event_ics(
+    id_str: String,
+    state: &rocket::State<State>,
+    viewer: Option<User>,
+) -> Result<(ContentType, String), Failure> {
+    let event = visible_event(state, parse_id(&id_str)?, viewer.as_ref())?;
+    if event.start.is_none() {
+        return Err(Failure::new(
+            ErrorCode::Conflict,
+            "the event has no start, it cannot be added to a calendar",
+        ));
     }
+    Ok((
+        ContentType::Calendar,
+        ics::calendar(&[event], state.clock.now()),
+    ))
+}
 
-    fn publish_event(client: &Client, event: &Event) -> Result<(), String> {
-        let event_json = serde_json::to_string(&event).unwrap();
-        let response = client
-            .put(uri!("/api/event"))
-            .header(ContentType::JSON)
-            .body(event_json)
-            .dispatch();
-        if response.status() == Status::Ok {
-            Ok(())
-        } else {
-            Err(format!(
-                "cannot publish event, received code {}:\n{}",
-                response.status(),
-                response.into_string().unwrap()
-            ))
-        }
+struct CommentTextData {
+    text: String,
+}
+
+#[async_trait]
+impl<'r> FromData<'r> for CommentTextData {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        parse_json_body(request, data, 16.kibibytes())
+            .await
+            .map(|comment: CommentText| CommentTextData { text: comment.text })
     }
+}
 
-    fn get_event(client: &Client, id: Id) -> Result<Event, String> {
-        let response = client.get(format!("/api/event/{}", id)).dispatch();
-        if response.status() != Status::Ok {
-            return Err(format!(
-                "cannot publish event, received code {}:\n{}",
-                response.status(),
-                response.into_string().unwrap()
-            ));
-        }
-        let event: Event = serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
-        Ok(event)
+/// Text of a comment without the surrounding whitespace, fails with a field error if it is empty
+/// or too long.
+fn check_comment_text(text: &str) -> Result<String, Failure> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(Failure::invalid_field("text", "the text is required"));
+    }
+    if text.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(Failure::invalid_field(
+            "text",
+            format!("the text must not exceed {} characters", MAX_COMMENT_LENGTH),
+        ));
     }
+    Ok(text.to_owned())
+}
 
-    fn join_event(client: &Client, id: Id, token: &str) -> Result<(), String> {
-        let response = client
-            .put(format!("/api/join/{}", id))
-            .header(rocket::http::Header {
-                name: "authorization".into(),
-                value: format!("Bearer {}", token).into(),
-            })
-            .dispatch();
-        if response.status() == Status::Ok {
-            Ok(())
-        } else {
+fn get_comment(state: &State, id_str: &str) -> Result<Comment, Failure> {
+    state
+        .comments
+        .get(parse_id(id_str)?)?
+        .ok_or(Failure::new(ErrorCode::NotFound, "comment not found"))
+}
+
+/// Comments of an event in the order they were posted, a page at a time.
+#[get("/api/event/<id_str>/comments?<cursor>&<limit>")]
+fn comments(
+    id_str: String,
+    cursor: Option<String>,
+    limit: Option<String>,
+    state: &rocket::State<State>,
+    viewer: Option<User>,
+) -> Result<String, Failure> {
+    let id = parse_id(&id_str)?;
+    let limit = search::parse_limit(limit)?;
+    visible_event(state, id, viewer.as_ref())?;
+    let comments = state.comments.of_event(id)?;
+    to_json(&search::paginate(
+        comments,
+        cursor.as_deref(),
+        limit,
+        |comment| comment.id,
+    )?)
+}
+
+/// Comment an event as the logged-in user.
+#[post(
+    "/api/event/<id_str>/comments",
+    format = "application/json",
+    data = "<data>"
+)]
+fn post_comment(
+    _limit: RateLimit,
+    id_str: String,
+    data: CommentTextData,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<Created<String>, Failure> {
+    let event_id = parse_id(&id_str)?;
+    let text = check_comment_text(&data.text)?;
+    visible_event(state, event_id, Some(&user))?;
+    let comment = Comment {
+        id: Id::new_v4(),
+        event_id,
+        author: user.name,
+        text,
+        posted_at: state.clock.now(),
+        edited_at: None,
+    };
+    state.comments.insert(comment.clone())?;
+    Ok(Created::new(format!("/api/comment/{}", comment.id)).body(to_json(&comment)?))
+}
+
+/// Change the text of a comment, only its author is allowed to.
+#[put("/api/comment/<id_str>", format = "application/json", data = "<data>")]
+fn edit_comment(
+    _limit: RateLimit,
+    id_str: String,
+    data: CommentTextData,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<String, Failure> {
+    let text = check_comment_text(&data.text)?;
+    let mut comment = get_comment(state, &id_str)?;
+    if comment.author != user.name {
+        return Err(Failure::new(
+            ErrorCode::Forbidden,
+            "only the author can edit the comment",
+        ));
+    }
+    comment.text = text;
+    comment.edited_at = Some(state.clock.now());
+    state.comments.update(comment.clone())?;
+    to_json(&comment)
+}
+
+/// Delete a comment, only the organizer of the event is allowed to, e.g. to moderate the thread.
+#[delete("/api/comment/<id_str>")]
+fn delete_comment(
+    _limit: RateLimit,
+    id_str: String,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<Status, Failure> {
+    let comment = get_comment(state, &id_str)?;
+    let event = state
+        .events
+        .get(comment.event_id)?
+        .ok_or(Failure::new(ErrorCode::NotFound, "event not found"))?;
+    if event.organizer.as_ref() != Some(&user.name) {
+        return Err(Failure::new(
+            ErrorCode::Forbidden,
+            "only the organizer of the event can delete comments",
+        ));
+    }
+    state.comments.delete(comment.id)?;
+    Ok(Status::NoContent)
+}
+
+fn calendar_feed_of(token: &str) -> CalendarFeed {
+    CalendarFeed {
+        path: format!("/api/calendar/{}", token),
+    }
+}
+
+/// Calendar feed of the logged-in user, its token is generated on the first request.
+#[get("/api/calendar")]
+fn calendar_feed(user: User, state: &rocket::State<State>) -> Result<String, Failure> {
+    let token = match state.feed_tokens.get(&user.name)? {
+        Some(token) => token,
+        None => {
+            let token = accounts::generate_token();
+            state.feed_tokens.set(&user.name, token.clone())?;
+            token
+        }
+    };
+    to_json(&calendar_feed_of(&token))
+}
+
+/// Give a new token to the calendar feed of the logged-in user, e.g. if its URL leaked.
+#[post("/api/calendar/reset")]
+fn reset_calendar_feed(
+    _limit: RateLimit,
+    user: User,
+    state: &rocket::State<State>,
+) -> Result<String, Failure> {
+    let token = accounts::generate_token();
+    state.feed_tokens.set(&user.name, token.clone())?;
+    to_json(&calendar_feed_of(&token))
+}
+
+/// Events joined or organized by the owner of the feed, in iCalendar format. The token
+/// authenticates the request since calendar applications cannot log in.
+#[get("/api/calendar/<token>")]
+fn calendar(token: String, state: &rocket::State<State>) -> Result<(ContentType, String), Failure> {
+    let username = state
+        .feed_tokens
+        .username(&token)?
+        .ok_or(Failure::new(ErrorCode::NotFound, "unknown calendar feed"))?;
+    let events: Vec<Event> = state
+        .events
+        .all()?
+        .into_iter()
+        .filter(|event| {
+            event.organizer.as_ref() == Some(&username) || event.participants.contains(&username)
+        })
+        .collect();
+    Ok((
+        ContentType::Calendar,
+        ics::calendar(&events, state.clock.now()),
+    ))
+}
+
+/// Notifications of the logged-in user, newest first.
+#[get("/api/notifications")]
+fn notifications(user: User, state: &rocket::State<State>) -> Result<String, Failure> {
+    to_json(&state.notifications.of_user(&user.name)?)
+}
+
+#[put("/api/notification/<id_str>/read")]
+fn mark_notification_read(
+    _limit: RateLimit,
+    id_str: String,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<Status, Failure> {
+    let id = parse_id(&id_str)?;
+    state.notifications.mark_read(&user.name, id)?;
+    Ok(Status::NoContent)
+}
+
+#[put("/api/notifications/read")]
+fn mark_all_notifications_read(
+    _limit: RateLimit,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<Status, Failure> {
+    state.notifications.mark_all_read(&user.name)?;
+    Ok(Status::NoContent)
+}
+
+struct EmailAddressData {
+    address: EmailAddress,
+}
+
+#[async_trait]
+impl<'r> FromData<'r> for EmailAddressData {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        parse_json_body(request, data, 1.kibibytes())
+            .await
+            .map(|address| EmailAddressData { address })
+    }
+}
+
+/// Notify the logged-in user by email of the changes of the events they joined, subscribing
+/// them again if they unsubscribed.
+#[put("/api/account/email", format = "application/json", data = "<data>")]
+fn set_email(
+    _limit: RateLimit,
+    data: EmailAddressData,
+    state: &rocket::State<State>,
+    user: User,
+) -> Result<Status, Failure> {
+    let email = data.address.email.trim().to_owned();
+    if email.parse::<lettre::Address>().is_err() {
+        return Err(Failure::invalid_field("email", "invalid email address"));
+    }
+    let unsubscribe_token = match state.recipients.get(&user.name)? {
+        Some(recipient) => recipient.unsubscribe_token,
+        None => accounts::generate_token(),
+    };
+    state.recipients.set(Recipient {
+        username: user.name,
+        email,
+        unsubscribe_token,
+        unsubscribed: false,
+    })?;
+    Ok(Status::NoContent)
+}
+
+fn recipient_by_unsubscribe_token(
+    token: &str,
+    state: &rocket::State<State>,
+) -> Result<Recipient, Failure> {
+    state
+        .recipients
+        .by_unsubscribe_token(token)?
+        .ok_or(Failure::new(
+            ErrorCode::NotFound,
+            "unknown unsubscribe link",
+        ))
+}
+
+/// Page asking to confirm the unsubscription, followed from the link at the bottom of the
+/// emails. Following the link changes nothing, as mail scanners and link previews open it too.
+#[get("/api/unsubscribe/<token>")]
+fn unsubscribe_confirmation(
+    token: String,
+    state: &rocket::State<State>,
+) -> Result<RawHtml<String>, Failure> {
+    recipient_by_unsubscribe_token(&token, state)?;
+    // the token is known, hence made of hexadecimal digits only
+    Ok(RawHtml(format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>join my ride</title></head><body><form method="post" action="/api/unsubscribe/{}"><p>Stop receiving emails from join my ride?</p><button type="submit">unsubscribe</button></form></body></html>"#,
+        token
+    )))
+}
+
+/// Stop emailing the owner of the token, posted by the confirmation page.
+#[post("/api/unsubscribe/<token>")]
+fn unsubscribe(
+    _limit: RateLimit,
+    token: String,
+    state: &rocket::State<State>,
+) -> Result<String, Failure> {
+    let mut recipient = recipient_by_unsubscribe_token(&token, state)?;
+    recipient.unsubscribed = true;
+    state.recipients.set(recipient)?;
+    Ok("you will not receive emails from join my ride anymore".to_owned())
+}
+
+#[post("/api/signup", format = "application/json", data = "<data>")]
+fn signup(
+    _limit: RateLimit,
+    data: accounts::CredentialsData,
+    state: &rocket::State<State>,
+) -> Result<Status, Failure> {
+    let credentials = data.credentials;
+    let username = credentials.username.trim();
+    if username.is_empty() {
+        return Err(Failure::invalid_field(
+            "username",
+            "the username is required",
+        ));
+    }
+    if credentials.password.chars().count() < accounts::MIN_PASSWORD_LENGTH {
+        return Err(Failure::invalid_field(
+            "password",
+            format!(
+                "the password must contain at least {} characters",
+                accounts::MIN_PASSWORD_LENGTH
+            ),
+        ));
+    }
+    let account = Account {
+        username: username.to_owned(),
+        password_hash: accounts::hash_password(&credentials.password)?,
+    };
+    match state.users.insert(account) {
+        Ok(()) => Ok(Status::Created),
+        Err(repository::Error::AlreadyExists) => Err(Failure::from(ApiError {
+            code: ErrorCode::Conflict,
+            message: "username already taken".to_owned(),
+            fields: vec![common::FieldError {
+                field: "username".to_owned(),
+                message: "username already taken".to_owned(),
+            }],
+        })),
+        Err(error) => Err(Failure::from(error)),
+    }
+}
+
+#[post("/api/login", format = "application/json", data = "<data>")]
+fn login(
+    _limit: RateLimit,
+    data: accounts::CredentialsData,
+    state: &rocket::State<State>,
+) -> Result<String, Failure> {
+    let credentials = data.credentials;
+    let account = state.users.get(credentials.username.trim())?;
+    // a password is verified even for an unknown username, so that the response time does not
+    // tell which usernames exist
+    let password_hash = account
+        .as_ref()
+        .map(|account| account.password_hash.as_str())
+        .unwrap_or_else(|| accounts::dummy_password_hash());
+    let valid = accounts::verify_password(&credentials.password, password_hash);
+    let account = account.filter(|_| valid).ok_or(Failure::new(
+        ErrorCode::InvalidCredentials,
+        "invalid username or password",
+    ))?;
+    let session = accounts::issue_token(state, account.username)?;
+    to_json(&session)
+}
+
+/// Exchange a valid token for a new one with a renewed lifetime, the old token is revoked.
+#[post("/api/session/refresh")]
+fn refresh_session(
+    _limit: RateLimit,
+    user: User,
+    state: &rocket::State<State>,
+) -> Result<String, Failure> {
+    let session = accounts::issue_token(state, user.name)?;
+    state.tokens.revoke(&user.token, state.clock.now())?;
+    to_json(&session)
+}
+
+/// Log out by revoking the token used to authenticate the request.
+#[delete("/api/session")]
+fn logout(_limit: RateLimit, user: User, state: &rocket::State<State>) -> Result<Status, Failure> {
+    state.tokens.revoke(&user.token, state.clock.now())?;
+    Ok(Status::NoContent)
+}
+
+/// Send an `ApiError` for every failure which is not already one, e.g. failed request guards.
+#[catch(default)]
+fn default_catcher(status: Status, request: &Request) -> Failure {
+    let error = failure::rejection(request).unwrap_or_else(|| {
+        ApiError::new(
+            ErrorCode::from_status(status.code),
+            status.reason_lossy().to_lowercase(),
+        )
+    });
+    Failure {
+        status,
+        error,
+        retry_after: rate_limit::retry_after(request),
+    }
+}
+
+/// `figment` reading the IP address of the clients from a header only if the `ip_header`
+/// setting is given. Rocket reads it from `X-Real-IP` by default, which any client can set when
+/// no reverse proxy overwrites it, and would then get a new rate limit with each address it
+/// makes up.
+fn trust_ip_header_if_configured(figment: Figment) -> Figment {
+    let configured = figment
+        .find_metadata("ip_header")
+        .is_some_and(|metadata| metadata.name != "Rocket Config");
+    if configured {
+        figment
+    } else {
+        figment.merge(("ip_header", false))
+    }
+}
+
+fn build(state: State) -> Rocket<Build> {
+    let jobs_interval = state.jobs_interval;
+    let rocket = rocket::custom(trust_ip_header_if_configured(rocket::Config::figment()))
+        .manage(state)
+        .register("/", catchers![default_catcher])
+        .mount(
+            "/",
+            routes![
+                calendar,
+                calendar_feed,
+                cancel_event,
+                comments,
+                delete_comment,
+                edit_comment,
+                event,
+                event_ics,
+                event_route,
+                event_updates,
+                events,
+                events_atom,
+                index,
+                join_event,
+                leave_event,
+                login,
+                logout,
+                mark_all_notifications_read,
+                mark_notification_read,
+                notifications,
+                package_js,
+                package_wasm,
+                post_comment,
+                publish_event,
+                refresh_session,
+                reset_calendar_feed,
+                set_email,
+                signup,
+                unsubscribe,
+                unsubscribe_confirmation,
+                update_event,
+                upload_route,
+            ],
+        );
+    match jobs_interval {
+        Some(interval) => rocket.attach(jobs::Scheduler { interval }),
+        None => rocket,
+    }
+}
+
+#[launch]
+fn rocket() -> _ {
+    let database: String = rocket::Config::figment()
+        .extract_inner("database")
+        .unwrap_or_else(|_| "join_my_ride.sqlite3".to_owned());
+    let token_lifetime: i64 = rocket::Config::figment()
+        .extract_inner("token_lifetime")
+        .unwrap_or(DEFAULT_TOKEN_LIFETIME);
+    let database = repository::Database::open(&database, Duration::seconds(token_lifetime))
+        .unwrap_or_else(|error| panic!("cannot open database {}: {}", database, error));
+    let mut state = State::sqlite(database);
+    state.token_lifetime = Duration::seconds(token_lifetime);
+    state.public_url = rocket::Config::figment()
+        .extract_inner("public_url")
+        .unwrap_or_else(|_| DEFAULT_PUBLIC_URL.to_owned());
+    let from: String = rocket::Config::figment()
+        .extract_inner("mail.from")
+        .unwrap_or_else(|_| mail::DEFAULT_FROM.to_owned());
+    state.mailer = match rocket::Config::figment().extract_inner::<mail::SmtpSettings>("mail.smtp")
+    {
+        Ok(settings) => Arc::new(
+            mail::SmtpMailer::new(&settings, &from)
+                .unwrap_or_else(|error| panic!("invalid mail settings: {}", error)),
+        ),
+        Err(_) => {
+            let mbox: String = rocket::Config::figment()
+                .extract_inner("mail.mbox")
+                .unwrap_or_else(|_| DEFAULT_MBOX.to_owned());
+            Arc::new(mail::MboxMailer::new(mbox, &from))
+        }
+    };
+    state.rate_limiter = Arc::new(rate_limit::RateLimiter::new(
+        rocket::Config::figment()
+            .extract_inner("rate_limits")
+            .unwrap_or_default(),
+    ));
+    let jobs_interval: u64 = rocket::Config::figment()
+        .extract_inner("jobs_interval")
+        .unwrap_or(DEFAULT_JOBS_INTERVAL);
+    state.jobs_interval = Some(std::time::Duration::from_secs(jobs_interval));
+    build(state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{build, trust_ip_header_if_configured, State, DEFAULT_PUBLIC_URL};
+    use crate::{
+        clock::FakeClock,
+        jobs,
+        mail::{Email, MboxMailer, NullMailer, DEFAULT_FROM},
+        notification,
+        rate_limit::{Limit, RateLimiter},
+        repository::{self, Database},
+    };
+    use chrono::{DateTime, Duration, FixedOffset, Utc};
+    use common::{
+        ApiError, CalendarFeed, Cancellation, Comment, CommentText, Credentials, Difficulty,
+        Discipline, EmailAddress, ErrorCode, Event, EventPatch, EventStatus, EventUpdate,
+        Frequency, Id, MeetingPoint, NewEvent, Notification, Pace, Page, Participation, Recurrence,
+        Route, Session, Tz,
+    };
+    use rocket::figment::Figment;
+    use rocket::http::{ContentType, Header, Status};
+    use rocket::local::blocking::Client;
+    use std::{collections::HashMap, sync::Arc};
+    use tempfile::{NamedTempFile, TempPath};
+
+    macro_rules! test_with_all_repositories {
+        ($($test:ident),* $(,)?) => {
+            mod in_memory {
+                use super::*;
+                $(
+                    #[test]
+                    fn $test() {
+                        super::$test(State::in_memory())
+                    }
+                )*
+            }
+
+            mod sqlite {
+                use super::*;
+                $(
+                    #[test]
+                    fn $test() {
+                        super::$test(State {
+                            mailer: Arc::new(NullMailer),
+                            ..State::sqlite(Database::open_in_memory().unwrap())
+                        })
+                    }
+                )*
+            }
+        };
+    }
+
+    test_with_all_repositories!(
+        list_all_events,
+        get_an_event_by_id,
+        get_a_non_existing_event_by_id,
+        publish_an_event,
+        joining_an_event,
+        joining_a_non_existing_event,
+        joining_an_event_twice,
+        joining_an_event_with_an_unknown_token,
+        signing_up_and_logging_in,
+        signing_up_with_a_taken_username,
+        signing_up_with_a_username_surrounded_by_spaces,
+        signing_up_with_a_short_password,
+        logging_in_with_a_wrong_password,
+        logging_in_with_an_unknown_username,
+        joining_an_event_without_a_token,
+        joining_an_event_with_a_malformed_authorization_header,
+        joining_an_event_with_an_expired_token,
+        logging_out,
+        refreshing_a_session,
+        publishing_an_event_records_the_organizer,
+        publishing_an_event_without_being_logged_in,
+        leaving_an_event,
+        leaving_an_event_not_joined,
+        leaving_a_non_existing_event,
+        leaving_an_event_without_a_token,
+        updating_an_event,
+        updating_an_event_without_being_the_organizer,
+        updating_an_event_with_an_empty_name,
+        completing_an_event_through_an_update,
+        updating_an_event_with_an_unknown_field,
+        updating_a_non_existing_event,
+        cancelling_an_event,
+        cancelling_an_event_without_a_reason,
+        cancelling_an_event_without_being_the_organizer,
+        cancelling_a_completed_event,
+        updating_a_cancelled_or_completed_event,
+        joining_a_cancelled_event,
+        publishing_a_draft,
+        publishing_a_draft_after_its_start,
+        hiding_drafts_from_other_users,
+        getting_a_non_existing_event_returns_an_api_error,
+        signing_up_with_a_short_password_reports_the_invalid_field,
+        publishing_a_malformed_event,
+        publishing_an_event_with_an_id,
+        publishing_the_same_event_twice,
+        paging_through_events,
+        sorting_events,
+        paging_through_sorted_events,
+        filtering_events,
+        listing_events_with_an_invalid_query,
+        publishing_an_event_with_a_duration,
+        publishing_an_event_with_an_invalid_schedule,
+        updating_the_schedule_of_an_event,
+        filtering_upcoming_and_past_events,
+        publishing_an_event_with_a_meeting_point,
+        publishing_an_event_with_an_invalid_meeting_point,
+        searching_events_around_a_point,
+        uploading_a_route,
+        uploading_a_malformed_route,
+        uploading_a_route_without_being_the_organizer,
+        uploading_a_too_large_route,
+        joining_a_full_event,
+        leaving_a_full_event_promotes_the_first_waitlisted_user,
+        leaving_a_cancelled_full_event,
+        changing_the_capacity_of_an_event,
+        publishing_an_event_without_capacity,
+        classifying_an_event,
+        filtering_events_by_classification,
+        publishing_a_series,
+        overriding_an_occurrence_of_a_series,
+        inserting_a_series_as_a_whole,
+        publishing_an_invalid_series,
+        exporting_an_event_to_a_calendar,
+        subscribing_to_the_calendar_feed,
+        getting_the_calendar_feed_without_being_logged_in,
+        following_newly_published_events,
+        commenting_an_event,
+        commenting_with_an_invalid_text,
+        editing_a_comment,
+        deleting_a_comment,
+        following_events_live,
+        notifying_participants_by_email,
+        unsubscribing_from_emails,
+        sending_the_queued_emails_again_after_a_failure,
+        reminding_the_participants_once,
+        completing_the_events_which_ended,
+        rate_limiting_the_requests_of_a_user,
+        rate_limiting_the_anonymous_requests_by_ip,
+        setting_an_invalid_email,
+        reading_notifications,
+    );
+
+    fn with_3_events(state: State) -> State {
+        for name in ["event_1", "event_2", "event_3"] {
+            state
+                .events
+                .insert_all(vec![Event::new(name.to_owned())])
+                .unwrap();
+        }
+        state
+    }
+
+    fn list_all_events(state: State) {
+        // given 3 existing events
+        let state = with_3_events(state);
+
+        // when a user requests /api/events
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let response = client.get(uri!("/api/events")).dispatch();
+
+        // then the server responds with a list containing all 3 events
+        assert_eq!(response.status(), Status::Ok);
+        let page: Page<Event> =
+            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
+        let actual = page.items;
+        let expected = vec![
+            Event::new("event_1".to_owned()),
+            Event::new("event_2".to_owned()),
+            Event::new("event_3".to_owned()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    fn get_an_event_by_id(state: State) {
+        // given 3 existing events
+        let state = with_3_events(state);
+
+        // when a user requests /api/event/<id>
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        // get an existing id
+        let events = get_events(&client, "");
+        let event = events.items[0].clone();
+        // actual request under test
+        let uri = format!("/api/event/{}", event.id);
+        let response = client.get(uri).dispatch();
+
+        // then the server responds with a list containing all 3 events
+        assert_eq!(response.status(), Status::Ok);
+        let actual: Event = serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
+        let expected = event;
+        assert_eq!(actual, expected);
+    }
+
+    fn get_a_non_existing_event_by_id(state: State) {
+        // given 3 existing events
+        let state = with_3_events(state);
+
+        // when a user requests a non-existing event using /api/event/<id>
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let uri = format!("/api/event/{}", common::Id::new_v4());
+        let response = client.get(uri).dispatch();
+
+        // then the server responds with 404 not found
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    fn publish_an_event(state: State) {
+        let published_event_name = "published_event_name";
+
+        fn has_name(name: &str) -> impl Fn(&Event) -> bool {
+            let name_owned = name.to_owned();
+            move |event: &Event| event.name == name_owned
+        }
+
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an non-existing event
+        let response = client.get(uri!("/api/events")).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let page: Page<Event> =
+            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
+        let initial_events = page.items;
+        assert!(initial_events
+            .into_iter()
+            .filter(has_name(published_event_name))
+            .collect::<Vec<Event>>()
+            .is_empty());
+
+        // when a logged-in user publishes an event
+        let token = logged_in_user(&client, "organizer");
+        let event = new_event(published_event_name);
+        let event_json = serde_json::to_string(&event).unwrap();
+        let response = client
+            .post(uri!("/api/events"))
+            .header(ContentType::JSON)
+            .header(bearer(&token))
+            .body(event_json)
+            .dispatch();
+
+        // then the server responds with a created code and the location of the stored event
+        assert_eq!(response.status(), Status::Created);
+        let location = response.headers().get_one("Location").unwrap().to_owned();
+        let created_event: Event =
+            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
+        assert_eq!(location, format!("/api/event/{}", created_event.id));
+        assert_eq!(created_event.name, published_event_name);
+        assert!(created_event.participants.is_empty());
+        assert_eq!(get_event(&client, created_event.id), Ok(created_event));
+
+        // then the event is added to the events list
+        let response = client.get(uri!("/api/events")).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let page: Page<Event> =
+            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
+        let initial_events = page.items;
+        assert!(!initial_events
+            .into_iter()
+            .filter(has_name(published_event_name))
+            .collect::<Vec<Event>>()
+            .is_empty());
+    }
+
+    /// Time in the timezone of the rides of the tests, `hours` after now.
+    /// Timezone which offset is the one of the dates given by `in_hours`.
+    const IN_HOURS_TIMEZONE: Tz = Tz::Etc__GMTMinus2;
+
+    fn in_hours(hours: i64) -> DateTime<FixedOffset> {
+        (Utc::now() + Duration::hours(hours))
+            .with_timezone(&FixedOffset::east_opt(2 * 60 * 60).unwrap())
+    }
+
+    /// Event starting tomorrow.
+    fn new_event(name: &str) -> NewEvent {
+        NewEvent::new(name, in_hours(24))
+    }
+
+    fn publish_event(client: &Client, event: &NewEvent, token: &str) -> Result<Event, String> {
+        let event_json = serde_json::to_string(&event).unwrap();
+        let response = client
+            .post(uri!("/api/events"))
+            .header(ContentType::JSON)
+            .header(bearer(token))
+            .body(event_json)
+            .dispatch();
+        if response.status() == Status::Created {
+            Ok(serde_json::from_str(response.into_string().unwrap().as_str()).unwrap())
+        } else {
+            Err(format!(
+                "cannot publish event, received code {}:\n{}",
+                response.status(),
+                response.into_string().unwrap()
+            ))
+        }
+    }
+
+    /// Get a page of events, `query` being the query string of the request.
+    fn get_events(client: &Client, query: &str) -> Page<Event> {
+        let response = client.get(format!("/api/events?{}", query)).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        serde_json::from_str(response.into_string().unwrap().as_str()).unwrap()
+    }
+
+    fn get_event(client: &Client, id: Id) -> Result<Event, String> {
+        let response = client.get(format!("/api/event/{}", id)).dispatch();
+        if response.status() != Status::Ok {
+            return Err(format!(
+                "cannot publish event, received code {}:\n{}",
+                response.status(),
+                response.into_string().unwrap()
+            ));
+        }
+        let event: Event = serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
+        Ok(event)
+    }
+
+    fn bearer(token: &str) -> rocket::http::Header<'static> {
+        rocket::http::Header {
+            name: "authorization".into(),
+            value: format!("Bearer {}", token).into(),
+        }
+    }
+
+    fn api_error(response: rocket::local::blocking::LocalResponse) -> ApiError {
+        assert_eq!(
+            response.headers().get_one("Content-Type"),
+            Some("application/json")
+        );
+        serde_json::from_str(response.into_string().unwrap().as_str()).unwrap()
+    }
+
+    fn authentication_failure(response: rocket::local::blocking::LocalResponse) -> ErrorCode {
+        assert_eq!(response.status(), Status::Unauthorized);
+        api_error(response).code
+    }
+
+    fn join_event(client: &Client, id: Id, token: &str) -> Result<Participation, String> {
+        let response = client
+            .put(format!("/api/join/{}", id))
+            .header(rocket::http::Header {
+                name: "authorization".into(),
+                value: format!("Bearer {}", token).into(),
+            })
+            .dispatch();
+        if response.status() == Status::Ok {
+            Ok(serde_json::from_str(&response.into_string().unwrap()).unwrap())
+        } else {
             Err(format!(
                 "cannot join event, received code {}:\n{}",
                 response.status(),
@@ -352,80 +1782,3260 @@ mod test {
         }
     }
 
-    #[test]
-    fn joining_an_event() {
-        let client = Client::tracked(rocket()).expect("valid rocket instance");
+    fn sign_up(client: &Client, username: &str, password: &str) -> Status {
+        let credentials = Credentials {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        };
+        client
+            .post(uri!("/api/signup"))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&credentials).unwrap())
+            .dispatch()
+            .status()
+    }
+
+    fn log_in(client: &Client, username: &str, password: &str) -> Result<Session, Status> {
+        let credentials = Credentials {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        };
+        let response = client
+            .post(uri!("/api/login"))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&credentials).unwrap())
+            .dispatch();
+        if response.status() == Status::Ok {
+            Ok(serde_json::from_str(response.into_string().unwrap().as_str()).unwrap())
+        } else {
+            Err(response.status())
+        }
+    }
+
+    /// Sign up a new user and return a valid session for this user.
+    fn log_in_as_new_user(client: &Client, username: &str) -> Session {
+        assert_eq!(sign_up(client, username, "password"), Status::Created);
+        log_in(client, username, "password").unwrap()
+    }
+
+    /// Sign up a new user and return a valid token for this user.
+    fn logged_in_user(client: &Client, username: &str) -> String {
+        log_in_as_new_user(client, username).token
+    }
+
+    fn joining_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+
+        // when a logged-in user requests to join the event
+        let response = client
+            .put(format!("/api/join/{}", event.id))
+            .header(rocket::http::Header {
+                name: "authorization".into(),
+                value: format!("Bearer {}", token).into(),
+            })
+            .dispatch();
+
+        // then the server responds with a success code
+        assert_eq!(response.status(), Status::Ok);
+
+        // then the user is added to the list of participants in the event
+        let updated_event = get_event(&client, event.id).unwrap();
+        assert!(updated_event.participants.contains("valid_user"));
+    }
+
+    fn joining_a_non_existing_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a non-existing event
+        let id = Id::new_v4();
+        assert!(get_event(&client, id).is_err());
+        let token = logged_in_user(&client, "valid_user");
+
+        // when a logged-in user requests to join the event
+        let response = client
+            .put(format!("/api/join/{}", id))
+            .header(rocket::http::Header {
+                name: "authorization".into(),
+                value: format!("Bearer {}", token).into(),
+            })
+            .dispatch();
+
+        // then the server responds with a not found code
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    fn joining_an_event_twice(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let mut updates = client
+            .rocket()
+            .state::<State>()
+            .unwrap()
+            .updates
+            .subscribe();
+
+        // given an event that a user already joined
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+        assert_eq!(
+            join_event(&client, event.id, &token),
+            Ok(Participation::Going)
+        );
+
+        // when a logged-in user requests to join the event
+        let response = client
+            .put(format!("/api/join/{}", event.id))
+            .header(rocket::http::Header {
+                name: "authorization".into(),
+                value: format!("Bearer {}", token).into(),
+            })
+            .dispatch();
+
+        // then the server responds with a success code
+        assert_eq!(response.status(), Status::Ok);
+
+        // then the user is not added again to the list of participants in the event
+        let updated_event = get_event(&client, event.id).unwrap();
+        assert_eq!(updated_event.participants.len(), 1);
+        assert!(updated_event.participants.contains("valid_user"));
+
+        // then only the first join is broadcast
+        assert_eq!(
+            received_updates(&mut updates),
+            vec![
+                ("created", event.id, None),
+                ("joined", event.id, Some("valid_user".to_owned()))
+            ]
+        );
+    }
+
+    fn joining_an_event_with_an_unknown_token(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when a user requests to join the event with a token that was never issued
+        let result = join_event(&client, event.id, "unknown_token");
+
+        // then the server rejects the request
+        assert!(result.is_err());
+
+        // then the event has no participant
+        let updated_event = get_event(&client, event.id).unwrap();
+        assert!(updated_event.participants.is_empty());
+    }
+
+    fn signing_up_and_logging_in(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user signs up
+        let status = sign_up(&client, "some_user", "some_password");
+
+        // then the account is created
+        assert_eq!(status, Status::Created);
+
+        // then the user can log in with the same credentials
+        let session = log_in(&client, "some_user", "some_password").unwrap();
+        assert_eq!(session.username, "some_user");
+        assert!(!session.token.is_empty());
+
+        // then the token identifies the user
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        assert_eq!(
+            join_event(&client, event.id, &session.token),
+            Ok(Participation::Going)
+        );
+        let updated_event = get_event(&client, event.id).unwrap();
+        assert!(updated_event.participants.contains("some_user"));
+    }
+
+    fn signing_up_with_a_taken_username(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an existing user
+        assert_eq!(
+            sign_up(&client, "some_user", "some_password"),
+            Status::Created
+        );
+
+        // when another user signs up with the same username
+        let status = sign_up(&client, "some_user", "other_password");
+
+        // then the server responds with a conflict code
+        assert_eq!(status, Status::Conflict);
+
+        // then the original password is still the valid one
+        assert!(log_in(&client, "some_user", "some_password").is_ok());
+        assert_eq!(
+            log_in(&client, "some_user", "other_password"),
+            Err(Status::Unauthorized)
+        );
+    }
+
+    fn signing_up_with_a_username_surrounded_by_spaces(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a user who signed up with spaces around their username
+        assert_eq!(
+            sign_up(&client, " some_user ", "some_password"),
+            Status::Created
+        );
+
+        // when another user signs up with the same username without the spaces
+        let status = sign_up(&client, "some_user", "other_password");
+
+        // then the username is taken already
+        assert_eq!(status, Status::Conflict);
+
+        // then the user logs in with or without the spaces
+        let session = log_in(&client, "some_user", "some_password").unwrap();
+        assert_eq!(session.username, "some_user");
+        assert!(log_in(&client, " some_user", "some_password").is_ok());
+    }
+
+    fn signing_up_with_a_short_password(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user signs up with a too short password
+        let status = sign_up(&client, "some_user", "short");
+
+        // then the server responds with a bad request code
+        assert_eq!(status, Status::BadRequest);
+    }
+
+    fn logging_in_with_a_wrong_password(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an existing user
+        assert_eq!(
+            sign_up(&client, "some_user", "some_password"),
+            Status::Created
+        );
+
+        // when the user logs in with a wrong password
+        let result = log_in(&client, "some_user", "wrong_password");
+
+        // then the server responds with an unauthorized code
+        assert_eq!(result, Err(Status::Unauthorized));
+    }
+
+    fn logging_in_with_an_unknown_username(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user that never signed up logs in
+        let result = log_in(&client, "unknown_user", "some_password");
+
+        // then the server responds with an unauthorized code
+        assert_eq!(result, Err(Status::Unauthorized));
+    }
+
+    fn joining_an_event_without_a_token(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when a user requests to join the event without authorization header
+        let response = client.put(format!("/api/join/{}", event.id)).dispatch();
+
+        // then the server responds with an unauthorized code telling that the token is missing
+        assert_eq!(authentication_failure(response), ErrorCode::MissingToken);
+    }
+
+    fn joining_an_event_with_a_malformed_authorization_header(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when a user requests to join the event with a malformed authorization header
+        let response = client
+            .put(format!("/api/join/{}", event.id))
+            .header(rocket::http::Header {
+                name: "authorization".into(),
+                value: "Basic dXNlcjpwYXNzd29yZA==".into(),
+            })
+            .dispatch();
+
+        // then the server responds with a bad request code
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    fn joining_an_event_with_an_expired_token(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        state.token_lifetime = Duration::hours(1);
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event and a token issued more than its lifetime ago
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+        clock.advance(Duration::hours(1) + Duration::seconds(1));
+
+        // when the user requests to join the event
+        let response = client
+            .put(format!("/api/join/{}", event.id))
+            .header(bearer(&token))
+            .dispatch();
+
+        // then the server responds with an unauthorized code telling that the token expired
+        assert_eq!(authentication_failure(response), ErrorCode::ExpiredToken);
+    }
+
+    fn logging_out(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a logged-in user
+        let token = logged_in_user(&client, "valid_user");
+
+        // when the user logs out
+        let response = client
+            .delete(uri!("/api/session"))
+            .header(bearer(&token))
+            .dispatch();
+
+        // then the server responds with a success code
+        assert_eq!(response.status(), Status::NoContent);
+
+        // then the token cannot be used anymore
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let response = client
+            .put(format!("/api/join/{}", event.id))
+            .header(bearer(&token))
+            .dispatch();
+        assert_eq!(authentication_failure(response), ErrorCode::RevokedToken);
+    }
+
+    #[test]
+    fn upgrading_the_database_keeps_the_sessions() {
+        // given a database of the version issuing tokens without expiry, with a logged-in user
+        let path = std::env::temp_dir().join(format!("join_my_ride_{}.sqlite3", Id::new_v4()));
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "
+                CREATE TABLE events (id TEXT PRIMARY KEY NOT NULL, name TEXT NOT NULL);
+                CREATE TABLE participants (
+                    event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                    username TEXT NOT NULL,
+                    PRIMARY KEY (event_id, username)
+                );
+                CREATE TABLE users (username TEXT PRIMARY KEY NOT NULL, password_hash TEXT NOT NULL);
+                CREATE TABLE tokens (
+                    value TEXT PRIMARY KEY NOT NULL,
+                    username TEXT NOT NULL REFERENCES users(username) ON DELETE CASCADE
+                );
+                INSERT INTO users VALUES ('rider', 'hash');
+                INSERT INTO tokens VALUES ('old_token', 'rider');
+                PRAGMA user_version = 2;
+                ",
+            )
+            .unwrap();
+        drop(connection);
+
+        // when the server upgrades the database
+        let database = Database::open(&path, Duration::hours(1)).unwrap();
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        let mut state = State::sqlite(database);
+        state.clock = clock.clone();
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // then the user is still logged in
+        let response = client
+            .get("/api/notifications")
+            .header(bearer("old_token"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        // then the token expires after the token lifetime
+        clock.advance(Duration::hours(1) + Duration::minutes(1));
+        let response = client
+            .get("/api/notifications")
+            .header(bearer("old_token"))
+            .dispatch();
+        assert_eq!(authentication_failure(response), ErrorCode::ExpiredToken);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn refreshing_a_session(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        state.token_lifetime = Duration::hours(1);
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a token about to expire
+        let session = log_in_as_new_user(&client, "valid_user");
+        clock.advance(Duration::minutes(50));
+
+        // when the user refreshes the session
+        let response = client
+            .post(uri!("/api/session/refresh"))
+            .header(bearer(&session.token))
+            .dispatch();
+
+        // then the server responds with a new session expiring later
+        assert_eq!(response.status(), Status::Ok);
+        let refreshed: Session =
+            serde_json::from_str(response.into_string().unwrap().as_str()).unwrap();
+        assert_eq!(refreshed.username, "valid_user");
+        assert_ne!(refreshed.token, session.token);
+        assert!(refreshed.expires_at > session.expires_at);
+
+        // then the new token is still valid after the lifetime of the old one
+        clock.advance(Duration::minutes(20));
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        assert_eq!(
+            join_event(&client, event.id, &refreshed.token),
+            Ok(Participation::Going)
+        );
+
+        // then the old token is revoked
+        let response = client
+            .put(format!("/api/join/{}", event.id))
+            .header(bearer(&session.token))
+            .dispatch();
+        assert_eq!(authentication_failure(response), ErrorCode::RevokedToken);
+    }
+
+    fn publishing_an_event_records_the_organizer(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a logged-in user
+        let token = logged_in_user(&client, "organizer");
+
+        // when the user publishes an event
+        let event = publish_event(&client, &new_event("some_event"), &token).unwrap();
+
+        // then the logged-in user is recorded as the organizer
+        let published_event = get_event(&client, event.id).unwrap();
+        assert_eq!(published_event.organizer, Some("organizer".to_owned()));
+    }
+
+    fn publishing_an_event_without_being_logged_in(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when an anonymous user publishes an event
+        let event = new_event("some_event");
+        let response = client
+            .post(uri!("/api/events"))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&event).unwrap())
+            .dispatch();
+
+        // then the server responds with an unauthorized code
+        assert_eq!(authentication_failure(response), ErrorCode::MissingToken);
+
+        // then the event is not published
+        assert!(get_events(&client, "").items.is_empty());
+    }
+
+    fn leaving_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event joined by two users
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+        assert_eq!(
+            join_event(&client, event.id, &token),
+            Ok(Participation::Going)
+        );
+        let other_token = logged_in_user(&client, "other_user");
+        assert_eq!(
+            join_event(&client, event.id, &other_token),
+            Ok(Participation::Going)
+        );
+
+        // when one of them requests to leave the event
+        let response = client
+            .put(format!("/api/leave/{}", event.id))
+            .header(bearer(&token))
+            .dispatch();
+
+        // then the server responds with a success code
+        assert_eq!(response.status(), Status::Ok);
+
+        // then only this user is removed from the list of participants
+        let updated_event = get_event(&client, event.id).unwrap();
+        assert!(!updated_event.participants.contains("valid_user"));
+        assert!(updated_event.participants.contains("other_user"));
+    }
+
+    fn leaving_an_event_not_joined(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event that a user did not join
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+        let mut updates = client
+            .rocket()
+            .state::<State>()
+            .unwrap()
+            .updates
+            .subscribe();
+
+        // when the user requests to leave the event
+        let response = client
+            .put(format!("/api/leave/{}", event.id))
+            .header(bearer(&token))
+            .dispatch();
+
+        // then the server responds with a conflict code
+        assert_eq!(response.status(), Status::Conflict);
+
+        // then the list of participants is unchanged and nothing is broadcast
+        let updated_event = get_event(&client, event.id).unwrap();
+        assert!(updated_event.participants.is_empty());
+        assert!(received_updates(&mut updates).is_empty());
+    }
+
+    fn leaving_a_non_existing_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a non-existing event
+        let id = Id::new_v4();
+        assert!(get_event(&client, id).is_err());
+        let token = logged_in_user(&client, "valid_user");
+
+        // when a logged-in user requests to leave the event
+        let response = client
+            .put(format!("/api/leave/{}", id))
+            .header(bearer(&token))
+            .dispatch();
+
+        // then the server responds with a not found code
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    fn leaving_an_event_without_a_token(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event joined by a user
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+        assert_eq!(
+            join_event(&client, event.id, &token),
+            Ok(Participation::Going)
+        );
+
+        // when an anonymous user requests to leave the event
+        let response = client.put(format!("/api/leave/{}", event.id)).dispatch();
+
+        // then the server responds with an unauthorized code
+        assert_eq!(authentication_failure(response), ErrorCode::MissingToken);
+
+        // then the participant is still in the event
+        let updated_event = get_event(&client, event.id).unwrap();
+        assert!(updated_event.participants.contains("valid_user"));
+    }
+
+    fn update_event(
+        client: &Client,
+        id: Id,
+        patch: &EventPatch,
+        token: &str,
+    ) -> Result<Event, Status> {
+        let response = client
+            .patch(format!("/api/event/{}", id))
+            .header(ContentType::JSON)
+            .header(bearer(token))
+            .body(serde_json::to_string(patch).unwrap())
+            .dispatch();
+        if response.status() == Status::Ok {
+            Ok(serde_json::from_str(response.into_string().unwrap().as_str()).unwrap())
+        } else {
+            Err(response.status())
+        }
+    }
+
+    fn updating_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event joined by a user
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+        assert_eq!(
+            join_event(&client, event.id, &token),
+            Ok(Participation::Going)
+        );
+
+        // when the organizer renames the event
+        let patch = EventPatch {
+            name: Some("renamed_event".to_owned()),
+            ..EventPatch::default()
+        };
+        let updated_event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the server responds with the updated event
+        assert_eq!(updated_event.name, "renamed_event");
+
+        // then the event is renamed and the other fields are unchanged
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(stored_event, updated_event);
+        assert!(stored_event.participants.contains("valid_user"));
+        assert_eq!(stored_event.organizer, Some("organizer".to_owned()));
+    }
+
+    fn updating_an_event_without_being_the_organizer(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when another user renames the event
+        let token = logged_in_user(&client, "valid_user");
+        let patch = EventPatch {
+            name: Some("renamed_event".to_owned()),
+            ..EventPatch::default()
+        };
+        let result = update_event(&client, event.id, &patch, &token);
+
+        // then the server responds with a forbidden code
+        assert_eq!(result, Err(Status::Forbidden));
+
+        // then the event is unchanged
+        assert_eq!(get_event(&client, event.id).unwrap().name, "some_event");
+    }
+
+    fn updating_an_event_with_an_empty_name(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer removes the name of the event
+        let patch = EventPatch {
+            name: Some(" ".to_owned()),
+            ..EventPatch::default()
+        };
+        let result = update_event(&client, event.id, &patch, &organizer);
+
+        // then the server responds with a bad request code
+        assert_eq!(result, Err(Status::BadRequest));
+    }
+
+    fn completing_an_event_through_an_update(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer marks the event completed
+        let patch = EventPatch {
+            status: Some(EventStatus::Completed),
+            ..EventPatch::default()
+        };
+        let response = client
+            .patch(format!("/api/event/{}", event.id))
+            .header(ContentType::JSON)
+            .header(bearer(&organizer))
+            .body(serde_json::to_string(&patch).unwrap())
+            .dispatch();
+
+        // then the server responds with a validation error on the status
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(api_error(response).fields[0].field, "status");
+
+        // then the event is still published
+        assert_eq!(get_event(&client, event.id), Ok(event));
+    }
+
+    fn updating_an_event_with_an_unknown_field(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer changes a field which does not exist, e.g. misspelling one
+        let response = client
+            .patch(format!("/api/event/{}", event.id))
+            .header(ContentType::JSON)
+            .header(bearer(&organizer))
+            .body(r#"{"name":"renamed_event","capcity":10}"#)
+            .dispatch();
+
+        // then the server responds with a validation error on the unknown field
+        assert_eq!(response.status(), Status::BadRequest);
+        let error = api_error(response);
+        assert_eq!(error.code, ErrorCode::Validation);
+        assert_eq!(error.fields.len(), 1);
+        assert_eq!(error.fields[0].field, "capcity");
+
+        // then the event is unchanged
+        assert_eq!(get_event(&client, event.id), Ok(event));
+    }
+
+    fn updating_a_non_existing_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a non-existing event
+        let id = Id::new_v4();
+        let token = logged_in_user(&client, "valid_user");
+
+        // when a logged-in user renames the event
+        let patch = EventPatch {
+            name: Some("renamed_event".to_owned()),
+            ..EventPatch::default()
+        };
+        let result = update_event(&client, id, &patch, &token);
+
+        // then the server responds with a not found code
+        assert_eq!(result, Err(Status::NotFound));
+    }
+
+    fn cancel_event(client: &Client, id: Id, reason: &str, token: &str) -> Result<Event, Status> {
+        let response = client
+            .put(format!("/api/event/{}/cancel", id))
+            .header(ContentType::JSON)
+            .header(bearer(token))
+            .body(
+                serde_json::to_string(&Cancellation {
+                    reason: reason.to_owned(),
+                })
+                .unwrap(),
+            )
+            .dispatch();
+        if response.status() == Status::Ok {
+            Ok(serde_json::from_str(response.into_string().unwrap().as_str()).unwrap())
+        } else {
+            Err(response.status())
+        }
+    }
+
+    fn cancelling_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer cancels the event
+        let cancelled_event = cancel_event(&client, event.id, "storm", &organizer).unwrap();
+
+        // then the event is cancelled with the given reason
+        assert_eq!(cancelled_event.status, EventStatus::Cancelled);
+        assert_eq!(
+            cancelled_event.cancellation_reason,
+            Some("storm".to_owned())
+        );
+        assert_eq!(get_event(&client, event.id).unwrap(), cancelled_event);
+    }
+
+    fn cancelling_an_event_without_a_reason(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer cancels the event without giving a reason
+        let result = cancel_event(&client, event.id, " ", &organizer);
+
+        // then the server responds with a bad request code
+        assert_eq!(result, Err(Status::BadRequest));
+
+        // then the event is still published
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(stored_event.status, EventStatus::Published);
+    }
+
+    fn cancelling_an_event_without_being_the_organizer(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when another user cancels the event
+        let token = logged_in_user(&client, "valid_user");
+        let result = cancel_event(&client, event.id, "storm", &token);
+
+        // then the server responds with a forbidden code
+        assert_eq!(result, Err(Status::Forbidden));
+
+        // then the event is still published
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(stored_event.status, EventStatus::Published);
+    }
+
+    /// Complete an event as the jobs do once it ends.
+    fn complete_event(client: &Client, id: Id) {
+        let state = client.rocket().state::<State>().unwrap();
+        state
+            .events
+            .update(id, &mut |event| event.status = EventStatus::Completed)
+            .unwrap();
+    }
+
+    fn cancelling_a_completed_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a completed event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        complete_event(&client, event.id);
+
+        // when the organizer cancels the event
+        let result = cancel_event(&client, event.id, "storm", &organizer);
+
+        // then the server responds with a conflict code
+        assert_eq!(result, Err(Status::Conflict));
+
+        // then the event is still completed
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(stored_event.status, EventStatus::Completed);
+        assert_eq!(stored_event.cancellation_reason, None);
+    }
+
+    fn updating_a_cancelled_or_completed_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a cancelled event and a completed event
+        let organizer = logged_in_user(&client, "organizer");
+        let cancelled = publish_event(&client, &new_event("cancelled"), &organizer).unwrap();
+        assert!(cancel_event(&client, cancelled.id, "storm", &organizer).is_ok());
+        let completed = publish_event(&client, &new_event("completed"), &organizer).unwrap();
+        complete_event(&client, completed.id);
+
+        for id in [cancelled.id, completed.id] {
+            let stored_event = get_event(&client, id).unwrap();
+
+            // when the organizer renames the event
+            let patch = EventPatch {
+                name: Some("renamed".to_owned()),
+                ..EventPatch::default()
+            };
+            let result = update_event(&client, id, &patch, &organizer);
+
+            // then the server responds with a conflict code and the event is not changed
+            assert_eq!(result, Err(Status::Conflict));
+            assert_eq!(get_event(&client, id), Ok(stored_event));
+        }
+    }
+
+    fn joining_a_cancelled_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a cancelled event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+        assert!(cancel_event(&client, event.id, "storm", &organizer).is_ok());
+
+        // when a logged-in user requests to join the event
+        let token = logged_in_user(&client, "valid_user");
+        let response = client
+            .put(format!("/api/join/{}", event.id))
+            .header(bearer(&token))
+            .dispatch();
+
+        // then the server responds with a conflict code explaining why
+        assert_eq!(response.status(), Status::Conflict);
+        let error = api_error(response);
+        assert_eq!(error.code, ErrorCode::Conflict);
+        assert_eq!(error.message, "cannot join a cancelled event");
+
+        // then the user is not added to the participants
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert!(stored_event.participants.is_empty());
+    }
+
+    fn publishing_a_draft(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a draft
+        let organizer = logged_in_user(&client, "organizer");
+        let draft = NewEvent {
+            status: EventStatus::Draft,
+            ..new_event("some_event")
+        };
+        let event = publish_event(&client, &draft, &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+        assert!(join_event(&client, event.id, &token).is_err());
+
+        // when the organizer publishes the draft
+        let patch = EventPatch {
+            status: Some(EventStatus::Published),
+            ..EventPatch::default()
+        };
+        let published_event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the event is published and can be joined
+        assert_eq!(published_event.status, EventStatus::Published);
+        assert_eq!(
+            join_event(&client, event.id, &token),
+            Ok(Participation::Going)
+        );
+    }
+
+    fn publishing_a_draft_after_its_start(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a draft whose start has passed
+        let organizer = logged_in_user(&client, "organizer");
+        let draft = NewEvent {
+            status: EventStatus::Draft,
+            ..new_event("some_event")
+        };
+        let draft = publish_event(&client, &draft, &organizer).unwrap();
+        clock.advance(Duration::hours(25));
+
+        // when the organizer publishes the draft
+        let patch = EventPatch {
+            status: Some(EventStatus::Published),
+            ..EventPatch::default()
+        };
+        let response = client
+            .patch(format!("/api/event/{}", draft.id))
+            .header(ContentType::JSON)
+            .header(bearer(&organizer))
+            .body(serde_json::to_string(&patch).unwrap())
+            .dispatch();
+
+        // then the server responds with a validation error on the start
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(api_error(response).fields[0].field, "start");
+
+        // then the event is still a draft
+        let state = client.rocket().state::<State>().unwrap();
+        assert_eq!(
+            state
+                .events
+                .get(draft.id)
+                .unwrap()
+                .map(|event| event.status),
+            Some(EventStatus::Draft)
+        );
+
+        // when the organizer publishes the draft with a new start
+        let patch = EventPatch {
+            status: Some(EventStatus::Published),
+            start: Some(in_hours(48)),
+            ..EventPatch::default()
+        };
+
+        // then the event is published
+        assert_eq!(
+            update_event(&client, draft.id, &patch, &organizer).map(|event| event.status),
+            Ok(EventStatus::Published)
+        );
+    }
+
+    fn hiding_drafts_from_other_users(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let mut updates = client
+            .rocket()
+            .state::<State>()
+            .unwrap()
+            .updates
+            .subscribe();
+
+        // given a draft and a published event
+        let organizer = logged_in_user(&client, "organizer");
+        let draft = NewEvent {
+            status: EventStatus::Draft,
+            ..new_event("draft")
+        };
+        let draft = publish_event(&client, &draft, &organizer).unwrap();
+        upload_route(&client, draft.id, GPX, &organizer);
+        publish_event(&client, &new_event("published"), &organizer).unwrap();
+        let token = logged_in_user(&client, "valid_user");
+
+        // when another user or an anonymous visitor requests the events or the draft
+        // then the draft is neither listed nor found
+        for authorization in [Some(&token), None] {
+            for query in ["", "status=draft"] {
+                let mut request = client.get(format!("/api/events?{}", query));
+                if let Some(token) = authorization {
+                    request = request.header(bearer(token));
+                }
+                let page: Page<Event> =
+                    serde_json::from_str(&request.dispatch().into_string().unwrap()).unwrap();
+                assert!(names(&page).iter().all(|name| *name != "draft"));
+            }
+            for path in ["", "/route", "/ics", "/comments"] {
+                let mut request = client.get(format!("/api/event/{}{}", draft.id, path));
+                if let Some(token) = authorization {
+                    request = request.header(bearer(token));
+                }
+                let response = request.dispatch();
+                assert_eq!(response.status(), Status::NotFound);
+                assert_eq!(api_error(response).code, ErrorCode::NotFound);
+            }
+        }
+
+        // when another user joins or comments the draft
+        // then the draft is not found
+        let response = client
+            .put(format!("/api/join/{}", draft.id))
+            .header(bearer(&token))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(api_error(response).code, ErrorCode::NotFound);
+        let response = client
+            .post(format!("/api/event/{}/comments", draft.id))
+            .header(ContentType::JSON)
+            .header(bearer(&token))
+            .body(r#"{"text":"some text"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(api_error(response).code, ErrorCode::NotFound);
+
+        // then the draft is not sent to the users following the events live
+        assert_eq!(
+            updates.try_recv().map(|update| update.into_event().name),
+            Ok("published".to_owned())
+        );
+        assert!(updates.try_recv().is_err());
+
+        // when the organizer requests their drafts or the draft
+        let page: Page<Event> = serde_json::from_str(
+            &client
+                .get("/api/events?status=draft")
+                .header(bearer(&organizer))
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+        .unwrap();
+        let response = client
+            .get(format!("/api/event/{}", draft.id))
+            .header(bearer(&organizer))
+            .dispatch();
+
+        // then the draft is returned
+        assert_eq!(names(&page), vec!["draft"]);
+        assert_eq!(response.status(), Status::Ok);
+        for path in ["/route", "/ics", "/comments"] {
+            let response = client
+                .get(format!("/api/event/{}{}", draft.id, path))
+                .header(bearer(&organizer))
+                .dispatch();
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        // then the draft is still left out of the events listed by default
+        assert_eq!(names(&get_events(&client, "")), vec!["published"]);
+    }
+
+    fn getting_a_non_existing_event_returns_an_api_error(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user requests a non-existing event
+        let response = client
+            .get(format!("/api/event/{}", Id::new_v4()))
+            .dispatch();
+
+        // then the server responds with a not found error
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(api_error(response).code, ErrorCode::NotFound);
+    }
+
+    fn signing_up_with_a_short_password_reports_the_invalid_field(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user signs up with a too short password
+        let credentials = Credentials {
+            username: "some_user".to_owned(),
+            password: "short".to_owned(),
+        };
+        let response = client
+            .post(uri!("/api/signup"))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&credentials).unwrap())
+            .dispatch();
+
+        // then the server responds with a validation error on the password field
+        assert_eq!(response.status(), Status::BadRequest);
+        let error = api_error(response);
+        assert_eq!(error.code, ErrorCode::Validation);
+        assert_eq!(error.fields.len(), 1);
+        assert_eq!(error.fields[0].field, "password");
+    }
+
+    fn publishing_a_malformed_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let token = logged_in_user(&client, "organizer");
+
+        // when a user publishes an event which is not valid JSON
+        let response = client
+            .post(uri!("/api/events"))
+            .header(ContentType::JSON)
+            .header(bearer(&token))
+            .body("{\"name\":")
+            .dispatch();
+
+        // then the server responds with an invalid request error
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(api_error(response).code, ErrorCode::InvalidRequest);
+    }
+
+    fn publishing_an_event_with_an_id(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let token = logged_in_user(&client, "organizer");
+
+        // when a user publishes an event choosing its ID and participants
+        let event = Event::new("some_event".to_owned());
+        let response = client
+            .post(uri!("/api/events"))
+            .header(ContentType::JSON)
+            .header(bearer(&token))
+            .body(serde_json::to_string(&event).unwrap())
+            .dispatch();
+
+        // then the server responds with a validation error on the ID
+        assert_eq!(response.status(), Status::BadRequest);
+        let error = api_error(response);
+        assert_eq!(error.code, ErrorCode::Validation);
+        assert_eq!(error.fields[0].field, "id");
+
+        // then the event is not published
+        assert!(get_event(&client, event.id).is_err());
+    }
+
+    fn publishing_the_same_event_twice(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let token = logged_in_user(&client, "organizer");
+
+        // when a user publishes the same event twice
+        let event = new_event("some_event");
+        let first = publish_event(&client, &event, &token).unwrap();
+        let second = publish_event(&client, &event, &token).unwrap();
+
+        // then two events with different IDs are created
+        assert_ne!(first.id, second.id);
+        assert_eq!(get_event(&client, first.id), Ok(first));
+        assert_eq!(get_event(&client, second.id), Ok(second));
+    }
+
+    fn names(page: &Page<Event>) -> Vec<&str> {
+        page.items.iter().map(|event| event.name.as_str()).collect()
+    }
+
+    fn paging_through_events(state: State) {
+        // given 5 events
+        for name in ["event_1", "event_2", "event_3", "event_4", "event_5"] {
+            state
+                .events
+                .insert_all(vec![Event::new(name.to_owned())])
+                .unwrap();
+        }
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user requests the events 2 by 2
+        let first = get_events(&client, "limit=2");
+        let second = get_events(
+            &client,
+            &format!("limit=2&cursor={}", first.next_cursor.clone().unwrap()),
+        );
+        let third = get_events(
+            &client,
+            &format!("limit=2&cursor={}", second.next_cursor.clone().unwrap()),
+        );
+
+        // then the pages contain all the events in publication order
+        assert_eq!(names(&first), vec!["event_1", "event_2"]);
+        assert_eq!(names(&second), vec!["event_3", "event_4"]);
+        assert_eq!(names(&third), vec!["event_5"]);
+
+        // then the last page has no next cursor
+        assert_eq!(third.next_cursor, None);
+    }
+
+    fn sorting_events(state: State) {
+        // given 3 events
+        for name in ["b_event", "c_event", "a_event"] {
+            state
+                .events
+                .insert_all(vec![Event::new(name.to_owned())])
+                .unwrap();
+        }
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user requests the events sorted in different orders
+        let oldest = get_events(&client, "sort=oldest");
+        let newest = get_events(&client, "sort=newest");
+        let by_name = get_events(&client, "sort=name");
+
+        // then the events are sorted accordingly
+        assert_eq!(names(&oldest), vec!["b_event", "c_event", "a_event"]);
+        assert_eq!(names(&newest), vec!["a_event", "c_event", "b_event"]);
+        assert_eq!(names(&by_name), vec!["a_event", "b_event", "c_event"]);
+    }
+
+    fn paging_through_sorted_events(state: State) {
+        // given events sharing names, starts and meeting points, one of them being published
+        // after the events stored after it
+        let now = Utc::now();
+        let events = [
+            ("b_event", Some(48), Some(1.0), Some(2)),
+            ("A_event", None, Some(1.0), Some(0)),
+            ("a_event", Some(24), None, None),
+            ("c_event", Some(24), Some(0.5), Some(1)),
+            ("b_event", Some(72), Some(1.0), Some(1)),
+        ];
+        for (name, hours, longitude, published_hours_ago) in events {
+            let mut event = Event::new(name.to_owned());
+            // the starts are given in different timezones
+            event.start = hours.map(|hours| {
+                in_hours(hours).with_timezone(&FixedOffset::east_opt(hours as i32 * 60).unwrap())
+            });
+            event.meeting_point = longitude.map(|longitude| meeting_point("here", 48.0, longitude));
+            event.published_at = published_hours_ago.map(|hours| now - Duration::hours(hours));
+            state.events.insert_all(vec![event]).unwrap();
+        }
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // then the events are in publication order, those without a date first
+        let page = get_events(&client, "");
+        assert_eq!(
+            names(&page),
+            vec!["a_event", "b_event", "c_event", "b_event", "A_event"]
+        );
+
+        for query in [
+            "sort=oldest",
+            "sort=newest",
+            "sort=name",
+            "sort=start",
+            "latitude=48&longitude=0&radius_km=1000&sort=distance",
+        ] {
+            // when a user requests the events 2 by 2
+            let mut pages = vec![get_events(&client, &format!("{}&limit=2", query))];
+            while let Some(cursor) = pages.last().unwrap().next_cursor.clone() {
+                let page = get_events(&client, &format!("{}&limit=2&cursor={}", query, cursor));
+                pages.push(page);
+            }
+
+            // then the pages hold the events of a single page, in the same order
+            let paged: Vec<Id> = pages
+                .iter()
+                .flat_map(|page| page.items.iter().map(|event| event.id))
+                .collect();
+            let all: Vec<Id> = get_events(&client, query)
+                .items
+                .iter()
+                .map(|event| event.id)
+                .collect();
+            assert_eq!(paged, all, "{}", query);
+        }
+
+        // then the events are sorted in UTC, those without a start or meeting point last
+        assert_eq!(
+            names(&get_events(&client, "sort=start")),
+            vec!["a_event", "c_event", "b_event", "b_event", "A_event"]
+        );
+        assert_eq!(
+            names(&get_events(
+                &client,
+                "latitude=48&longitude=0&radius_km=1000&sort=distance"
+            )),
+            vec!["c_event", "b_event", "b_event", "A_event"]
+        );
+    }
+
+    fn filtering_events(state: State) {
+        // given events with different statuses, organizers and participants
+        let mut morning_ride = Event::new("Morning ride".to_owned());
+        morning_ride.organizer = Some("alice".to_owned());
+        morning_ride.participants.insert("bob".to_owned());
+        let mut evening_ride = Event::new("Evening ride".to_owned());
+        evening_ride.organizer = Some("bob".to_owned());
+        evening_ride.status = EventStatus::Cancelled;
+        let mut gravel_tour = Event::new("Gravel tour".to_owned());
+        gravel_tour.organizer = Some("alice".to_owned());
+        for event in [morning_ride, evening_ride, gravel_tour] {
+            state.events.insert_all(vec![event]).unwrap();
+        }
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user filters the events, then only the matching events are returned
+        assert_eq!(
+            names(&get_events(&client, "status=cancelled")),
+            vec!["Evening ride"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "organizer=alice")),
+            vec!["Morning ride", "Gravel tour"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "participant=bob")),
+            vec!["Morning ride"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "text=RIDE")),
+            vec!["Morning ride", "Evening ride"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "organizer=alice&text=ride")),
+            vec!["Morning ride"]
+        );
+    }
+
+    fn listing_events_with_an_invalid_query(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        for (query, field) in [
+            ("cursor=unknown", "cursor"),
+            ("limit=0", "limit"),
+            ("sort=random", "sort"),
+            ("status=unknown", "status"),
+            ("when=tomorrow", "when"),
+            ("latitude=48.8&longitude=2.3", "radius_km"),
+            ("latitude=91&longitude=2.3&radius_km=10", "latitude"),
+            ("latitude=48.8&longitude=east&radius_km=10", "longitude"),
+            ("latitude=48.8&longitude=2.3&radius_km=0", "radius_km"),
+            ("sort=distance", "sort"),
+            ("discipline=bmx", "discipline"),
+            ("max_pace=slow", "max_pace"),
+            ("max_difficulty=3", "max_difficulty"),
+            ("no_drop=yes", "no_drop"),
+            ("series=unknown", "series"),
+        ] {
+            // when a user requests the events with an invalid parameter
+            let response = client.get(format!("/api/events?{}", query)).dispatch();
+
+            // then the server responds with a validation error on this parameter
+            assert_eq!(response.status(), Status::BadRequest);
+            let error = api_error(response);
+            assert_eq!(error.code, ErrorCode::Validation);
+            assert_eq!(error.fields[0].field, field);
+        }
+    }
+
+    fn publishing_an_event_with_a_duration(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        // when the organizer publishes an event with a start and a duration
+        let start = in_hours(24);
+        let event = NewEvent {
+            duration_minutes: Some(90),
+            ..NewEvent::new("some_event", start)
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+
+        // then the event ends after the duration, in the timezone of the start
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(stored_event.start, Some(start));
+        assert_eq!(stored_event.end, Some(start + Duration::minutes(90)));
+        assert_eq!(stored_event.start.unwrap().offset(), start.offset());
+        assert_eq!(stored_event.end.unwrap().offset(), start.offset());
+    }
+
+    fn publishing_an_event_with_an_invalid_schedule(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        for (event, field) in [
+            (NewEvent::new("past_event", in_hours(-1)), "start"),
+            (
+                NewEvent {
+                    end: Some(in_hours(23)),
+                    ..new_event("ending_before_starting")
+                },
+                "end",
+            ),
+            (
+                NewEvent {
+                    end: Some(in_hours(26)),
+                    duration_minutes: Some(60),
+                    ..new_event("with_an_end_and_a_duration")
+                },
+                "duration_minutes",
+            ),
+        ] {
+            // when the organizer publishes an event with an invalid schedule
+            let response = client
+                .post(uri!("/api/events"))
+                .header(ContentType::JSON)
+                .header(bearer(&organizer))
+                .body(serde_json::to_string(&event).unwrap())
+                .dispatch();
+
+            // then the server responds with a validation error on the invalid field
+            assert_eq!(response.status(), Status::BadRequest);
+            let error = api_error(response);
+            assert_eq!(error.code, ErrorCode::Validation);
+            assert_eq!(error.fields[0].field, field);
+        }
+
+        // then no event is published
+        assert!(get_events(&client, "").items.is_empty());
+    }
+
+    fn updating_the_schedule_of_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer moves the event and gives an end
+        let patch = EventPatch {
+            start: Some(in_hours(48)),
+            end: Some(Some(in_hours(50))),
+            ..EventPatch::default()
+        };
+        let updated_event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the schedule is changed
+        assert_eq!(updated_event.start, patch.start);
+        assert_eq!(updated_event.end, patch.end.flatten());
+
+        // when the organizer moves the start after the end
+        let patch = EventPatch {
+            start: Some(in_hours(51)),
+            ..EventPatch::default()
+        };
+
+        // then the event is not updated
+        assert_eq!(
+            update_event(&client, event.id, &patch, &organizer),
+            Err(Status::BadRequest)
+        );
+        assert_eq!(get_event(&client, event.id), Ok(updated_event));
+
+        // when the organizer removes the end
+        let patch = EventPatch {
+            end: Some(None),
+            ..EventPatch::default()
+        };
+        let updated_event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the event has no end anymore
+        assert_eq!(updated_event.end, None);
+    }
+
+    fn filtering_upcoming_and_past_events(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+
+        // given events starting at different times and an event without a start
+        for (name, hours) in [
+            ("in_2_days", Some(48)),
+            ("yesterday", Some(-24)),
+            ("tomorrow", Some(24)),
+            ("unknown", None),
+        ] {
+            let mut event = Event::new(name.to_owned());
+            event.start = hours.map(in_hours);
+            state.events.insert_all(vec![event]).unwrap();
+        }
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user requests the upcoming and the past events
+        // then only the events starting after or before now are returned
+        assert_eq!(
+            names(&get_events(&client, "when=upcoming&sort=start")),
+            vec!["tomorrow", "in_2_days"]
+        );
+        assert_eq!(names(&get_events(&client, "when=past")), vec!["yesterday"]);
+
+        // when a user sorts all the events by start
+        // then the events without a start come last
+        assert_eq!(
+            names(&get_events(&client, "sort=start")),
+            vec!["yesterday", "tomorrow", "in_2_days", "unknown"]
+        );
+
+        // when a day and a half passes
+        clock.advance(Duration::hours(36));
+
+        // then the event of tomorrow is past
+        assert_eq!(
+            names(&get_events(&client, "when=past&sort=start")),
+            vec!["yesterday", "tomorrow"]
+        );
+    }
+
+    fn meeting_point(address: &str, latitude: f64, longitude: f64) -> MeetingPoint {
+        MeetingPoint {
+            address: address.to_owned(),
+            latitude,
+            longitude,
+        }
+    }
+
+    fn publishing_an_event_with_a_meeting_point(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        // when the organizer publishes an event with a meeting point
+        let event = NewEvent {
+            meeting_point: Some(meeting_point("Place de la Bastille", 48.8532, 2.3691)),
+            ..new_event("some_event")
+        };
+        let published_event = publish_event(&client, &event, &organizer).unwrap();
+
+        // then the meeting point is stored with the event
+        assert_eq!(published_event.meeting_point, event.meeting_point);
+        assert_eq!(get_event(&client, published_event.id), Ok(published_event));
+    }
+
+    fn publishing_an_event_with_an_invalid_meeting_point(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        for (meeting_point, field) in [
+            (meeting_point(" ", 48.8532, 2.3691), "meeting_point.address"),
+            (
+                meeting_point("North", 91.0, 2.3691),
+                "meeting_point.latitude",
+            ),
+            (
+                meeting_point("East", 48.8532, 181.0),
+                "meeting_point.longitude",
+            ),
+        ] {
+            // when the organizer publishes an event with an invalid meeting point
+            let event = NewEvent {
+                meeting_point: Some(meeting_point),
+                ..new_event("some_event")
+            };
+            let response = client
+                .post(uri!("/api/events"))
+                .header(ContentType::JSON)
+                .header(bearer(&organizer))
+                .body(serde_json::to_string(&event).unwrap())
+                .dispatch();
+
+            // then the server responds with a validation error on the invalid field
+            assert_eq!(response.status(), Status::BadRequest);
+            let error = api_error(response);
+            assert_eq!(error.code, ErrorCode::Validation);
+            assert_eq!(error.fields[0].field, field);
+        }
+    }
+
+    fn searching_events_around_a_point(state: State) {
+        // given events meeting at different distances from Paris and an event without a meeting point
+        for (name, meeting_point) in [
+            (
+                "versailles",
+                Some(meeting_point("Versailles", 48.8049, 2.1204)),
+            ),
+            ("lyon", Some(meeting_point("Lyon", 45.764, 4.8357))),
+            ("bastille", Some(meeting_point("Bastille", 48.8532, 2.3691))),
+            ("anywhere", None),
+        ] {
+            let mut event = Event::new(name.to_owned());
+            event.meeting_point = meeting_point;
+            state.events.insert_all(vec![event]).unwrap();
+        }
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user searches the events within 50 km of the center of Paris
+        let page = get_events(&client, "latitude=48.8566&longitude=2.3522&radius_km=50");
+
+        // then the events meeting in the area are returned, from the nearest to the farthest
+        assert_eq!(names(&page), vec!["bastille", "versailles"]);
+
+        // when a user searches the events within 500 km sorted by name
+        let page = get_events(
+            &client,
+            "latitude=48.8566&longitude=2.3522&radius_km=500&sort=name",
+        );
+
+        // then the requested sort order is kept
+        assert_eq!(names(&page), vec!["bastille", "lyon", "versailles"]);
+    }
+
+    /// GPX file with a track going 0.1 degree north, climbing 50 m and descending 20 m.
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <name>some ride</name>
+    <trkseg>
+      <trkpt lat="48.80" lon="2.30"><ele>100</ele></trkpt>
+      <trkpt lat="48.85" lon="2.30"><ele>150</ele></trkpt>
+      <trkpt lat="48.90" lon="2.30"><ele>130</ele></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    fn upload_route<'c>(
+        client: &'c Client,
+        id: Id,
+        gpx: &str,
+        token: &str,
+    ) -> rocket::local::blocking::LocalResponse<'c> {
+        client
+            .put(format!("/api/event/{}/route", id))
+            .header(bearer(token))
+            .body(gpx)
+            .dispatch()
+    }
+
+    fn uploading_a_route(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer uploads a GPX file
+        let response = upload_route(&client, event.id, GPX, &organizer);
+
+        // then the summary of the route is added to the event
+        assert_eq!(response.status(), Status::Ok);
+        let updated_event: Event = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let summary = updated_event.route.unwrap();
+        assert!((summary.distance_km - 11.12).abs() < 0.01);
+        assert_eq!(summary.elevation_gain_m, 50.0);
+        assert_eq!(summary.elevation_loss_m, 20.0);
+        assert_eq!(summary.bounds.min_latitude, 48.80);
+        assert_eq!(summary.bounds.max_latitude, 48.90);
+        assert_eq!(get_event(&client, event.id), Ok(updated_event));
+
+        // then the points of the route are stored
+        let response = client
+            .get(format!("/api/event/{}/route", event.id))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let route: Route = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(route.segments.len(), 1);
+        assert_eq!(route.segments[0].len(), 3);
+        assert_eq!(route.segments[0][1].elevation, Some(150.0));
+    }
+
+    fn uploading_a_malformed_route(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        for (gpx, fields) in [
+            ("not xml", vec!["gpx"]),
+            ("<kml></kml>", vec!["gpx"]),
+            ("<gpx><trk><trkseg></trkseg></trk></gpx>", vec!["gpx"]),
+            (
+                r#"<gpx><trk><trkseg><trkpt lat="91" lon="2"/></trkseg></trk></gpx>"#,
+                vec!["trk[0].trkseg[0].trkpt[0].lat"],
+            ),
+            (
+                r#"<gpx><rte><rtept lat="48" lon="2"/><rtept lat="48"><ele>high</ele></rtept></rte></gpx>"#,
+                vec!["rte[0].rtept[1].lon", "rte[0].rtept[1].ele"],
+            ),
+        ] {
+            // when the organizer uploads an invalid GPX file
+            let response = upload_route(&client, event.id, gpx, &organizer);
+
+            // then the server responds with validation errors on the invalid fields
+            assert_eq!(response.status(), Status::BadRequest);
+            let error = api_error(response);
+            assert_eq!(error.code, ErrorCode::Validation);
+            let error_fields: Vec<&str> = error
+                .fields
+                .iter()
+                .map(|field| field.field.as_str())
+                .collect();
+            assert_eq!(error_fields, fields);
+        }
+
+        // then the event has no route
+        assert_eq!(get_event(&client, event.id).unwrap().route, None);
+    }
+
+    fn uploading_a_route_without_being_the_organizer(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when another user uploads a route for the event
+        let token = logged_in_user(&client, "other_user");
+        let response = upload_route(&client, event.id, GPX, &token);
+
+        // then the server responds with a forbidden code
+        assert_eq!(response.status(), Status::Forbidden);
+        assert_eq!(get_event(&client, event.id).unwrap().route, None);
+    }
+
+    fn uploading_a_too_large_route(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("some_event"), &organizer).unwrap();
+
+        // when the organizer uploads a file exceeding the size limit
+        let padding = " ".repeat(5 * 1024 * 1024);
+        let gpx = GPX.replace("<trk>", &format!("{}<trk>", padding));
+        let response = upload_route(&client, event.id, &gpx, &organizer);
+
+        // then the server responds with an invalid request error
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(api_error(response).code, ErrorCode::InvalidRequest);
+    }
+
+    fn leave_event(client: &Client, id: Id, token: &str) -> Status {
+        client
+            .put(format!("/api/leave/{}", id))
+            .header(bearer(token))
+            .dispatch()
+            .status()
+    }
+
+    fn joining_a_full_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event with 1 spot, taken by a user
+        let organizer = logged_in_user(&client, "organizer");
+        let event = NewEvent {
+            capacity: Some(1),
+            ..new_event("some_event")
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+        let alice = logged_in_user(&client, "alice");
+        assert_eq!(
+            join_event(&client, event.id, &alice),
+            Ok(Participation::Going)
+        );
+
+        // when other users join the event
+        let bob = logged_in_user(&client, "bob");
+        let carol = logged_in_user(&client, "carol");
+        let bob_participation = join_event(&client, event.id, &bob);
+        let carol_participation = join_event(&client, event.id, &carol);
+
+        // then they are waitlisted in the order they joined
+        assert_eq!(
+            bob_participation,
+            Ok(Participation::Waitlisted { position: 1 })
+        );
+        assert_eq!(
+            carol_participation,
+            Ok(Participation::Waitlisted { position: 2 })
+        );
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(stored_event.participants.len(), 1);
+        assert_eq!(stored_event.waitlist, vec!["bob", "carol"]);
+
+        // when a waitlisted user joins again, then the position does not change
+        assert_eq!(
+            join_event(&client, event.id, &bob),
+            Ok(Participation::Waitlisted { position: 1 })
+        );
+    }
+
+    fn leaving_a_full_event_promotes_the_first_waitlisted_user(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a full event with 2 waitlisted users
+        let organizer = logged_in_user(&client, "organizer");
+        let event = NewEvent {
+            capacity: Some(1),
+            ..new_event("some_event")
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+        let alice = logged_in_user(&client, "alice");
+        let bob = logged_in_user(&client, "bob");
+        let carol = logged_in_user(&client, "carol");
+        for token in [&alice, &bob, &carol] {
+            join_event(&client, event.id, token).unwrap();
+        }
+
+        // when the participant leaves
+        assert_eq!(leave_event(&client, event.id, &alice), Status::Ok);
+
+        // then the first waitlisted user is promoted
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(
+            stored_event.participation("bob"),
+            Some(Participation::Going)
+        );
+        assert_eq!(
+            stored_event.participation("carol"),
+            Some(Participation::Waitlisted { position: 1 })
+        );
+        assert_eq!(stored_event.participation("alice"), None);
+
+        // when the last waitlisted user leaves the waitlist
+        assert_eq!(leave_event(&client, event.id, &carol), Status::Ok);
+
+        // then the participants are unchanged
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert!(stored_event.waitlist.is_empty());
+        assert_eq!(stored_event.participants.len(), 1);
+    }
+
+    fn leaving_a_cancelled_full_event(mut state: State) {
+        let (_mbox, mailer) = mbox_mailer(&mut state);
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a cancelled full event with a waitlisted user having an address
+        let organizer = logged_in_user(&client, "organizer");
+        let event = NewEvent {
+            capacity: Some(1),
+            ..new_event("some_event")
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+        let alice = logged_in_user(&client, "alice");
+        let bob = logged_in_user(&client, "bob");
+        set_email(&client, "bob@example.com", &bob);
+        for token in [&alice, &bob] {
+            join_event(&client, event.id, token).unwrap();
+        }
+        cancel_event(&client, event.id, "storm forecast", &organizer).unwrap();
+        let emails = sent_emails(&client, &mailer).len();
+        let notifications = get_notifications(&client, &bob).len();
+
+        // when the participant leaves
+        assert_eq!(leave_event(&client, event.id, &alice), Status::Ok);
+
+        // then the waitlisted user is neither promoted nor told so
+        let stored_event = get_event(&client, event.id).unwrap();
+        assert_eq!(stored_event.participation("alice"), None);
+        assert_eq!(
+            stored_event.participation("bob"),
+            Some(Participation::Waitlisted { position: 1 })
+        );
+        assert_eq!(sent_emails(&client, &mailer).len(), emails);
+        assert_eq!(get_notifications(&client, &bob).len(), notifications);
+    }
+
+    fn changing_the_capacity_of_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a full event of 1 spot with 2 waitlisted users
+        let organizer = logged_in_user(&client, "organizer");
+        let event = NewEvent {
+            capacity: Some(1),
+            ..new_event("some_event")
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+        for username in ["alice", "bob", "carol"] {
+            let token = logged_in_user(&client, username);
+            join_event(&client, event.id, &token).unwrap();
+        }
+
+        // when the organizer raises the capacity to 2
+        let patch = EventPatch {
+            capacity: Some(Some(2)),
+            ..EventPatch::default()
+        };
+        let updated_event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the first waitlisted user is promoted
+        assert_eq!(
+            updated_event.participation("bob"),
+            Some(Participation::Going)
+        );
+        assert_eq!(updated_event.waitlist, vec!["carol"]);
+
+        // when the organizer lowers the capacity below the number of participants
+        let patch = EventPatch {
+            capacity: Some(Some(1)),
+            ..EventPatch::default()
+        };
+
+        // then the capacity is not changed
+        assert_eq!(
+            update_event(&client, event.id, &patch, &organizer),
+            Err(Status::Conflict)
+        );
+        assert_eq!(get_event(&client, event.id), Ok(updated_event));
+
+        // when the organizer removes the capacity
+        let patch = EventPatch {
+            capacity: Some(None),
+            ..EventPatch::default()
+        };
+        let updated_event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the event has no capacity and the last waitlisted user is promoted
+        assert_eq!(updated_event.capacity, None);
+        assert_eq!(
+            updated_event.participation("carol"),
+            Some(Participation::Going)
+        );
+        assert!(updated_event.waitlist.is_empty());
+    }
+
+    fn publishing_an_event_without_capacity(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        // when the organizer publishes an event with no spot
+        let event = NewEvent {
+            capacity: Some(0),
+            ..new_event("some_event")
+        };
+        let response = client
+            .post(uri!("/api/events"))
+            .header(ContentType::JSON)
+            .header(bearer(&organizer))
+            .body(serde_json::to_string(&event).unwrap())
+            .dispatch();
+
+        // then the server responds with a validation error on the capacity
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(api_error(response).fields[0].field, "capacity");
+    }
+
+    fn classifying_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        // when the organizer publishes a classified event
+        let event = NewEvent {
+            discipline: Some(Discipline::Gravel),
+            pace: Some(Pace::Moderate),
+            difficulty: Some(Difficulty::Easy),
+            no_drop: true,
+            ..new_event("some_event")
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+
+        // then the event has the classification chosen by the organizer
+        assert_eq!(event.discipline, Some(Discipline::Gravel));
+        assert_eq!(event.pace, Some(Pace::Moderate));
+        assert_eq!(event.difficulty, Some(Difficulty::Easy));
+        assert!(event.no_drop);
+        assert_eq!(get_event(&client, event.id), Ok(event.clone()));
+
+        // when the organizer changes the pace and allows dropping riders
+        let patch = EventPatch {
+            pace: Some(Some(Pace::Brisk)),
+            no_drop: Some(false),
+            ..EventPatch::default()
+        };
+        let updated_event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then only these attributes are changed
+        assert_eq!(updated_event.pace, Some(Pace::Brisk));
+        assert!(!updated_event.no_drop);
+        assert_eq!(updated_event.discipline, Some(Discipline::Gravel));
+        assert_eq!(get_event(&client, event.id), Ok(updated_event));
+    }
+
+    fn filtering_events_by_classification(state: State) {
+        // given events of different disciplines, paces and difficulties
+        let mut coffee_ride = Event::new("Coffee ride".to_owned());
+        coffee_ride.discipline = Some(Discipline::Road);
+        coffee_ride.pace = Some(Pace::Social);
+        coffee_ride.difficulty = Some(Difficulty::Easy);
+        coffee_ride.no_drop = true;
+        let mut hill_repeats = Event::new("Hill repeats".to_owned());
+        hill_repeats.discipline = Some(Discipline::Road);
+        hill_repeats.pace = Some(Pace::Fast);
+        hill_repeats.difficulty = Some(Difficulty::Expert);
+        let mut forest_loop = Event::new("Forest loop".to_owned());
+        forest_loop.discipline = Some(Discipline::Mtb);
+        forest_loop.difficulty = Some(Difficulty::Intermediate);
+        let unclassified = Event::new("Unclassified".to_owned());
+        for event in [coffee_ride, hill_repeats, forest_loop, unclassified] {
+            state.events.insert_all(vec![event]).unwrap();
+        }
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user filters the events, then only the matching events are returned
+        assert_eq!(
+            names(&get_events(&client, "discipline=road")),
+            vec!["Coffee ride", "Hill repeats"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "max_pace=brisk")),
+            vec!["Coffee ride"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "max_difficulty=intermediate")),
+            vec!["Coffee ride", "Forest loop"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "no_drop=true")),
+            vec!["Coffee ride"]
+        );
+        assert_eq!(
+            names(&get_events(&client, "no_drop=false")),
+            vec!["Hill repeats", "Forest loop", "Unclassified"]
+        );
+    }
+
+    /// Publish a weekly series of `count` occurrences organized by `token`.
+    fn publish_weekly_series(client: &Client, count: u32, token: &str) -> Vec<Event> {
+        let event = NewEvent {
+            duration_minutes: Some(90),
+            timezone: Some(IN_HOURS_TIMEZONE),
+            recurrence: Some(Recurrence {
+                count: Some(count),
+                ..Recurrence::new(Frequency::Weekly)
+            }),
+            ..new_event("Tuesday ride")
+        };
+        let first = publish_event(client, &event, token).unwrap();
+        let series = first.series.unwrap();
+        get_events(client, &format!("series={}&sort=start", series.id)).items
+    }
+
+    fn publishing_a_series(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        // when the organizer publishes a weekly ride repeated 3 times
+        let occurrences = publish_weekly_series(&client, 3, &organizer);
+
+        // then 3 events are created, one week apart and with the same duration
+        assert_eq!(occurrences.len(), 3);
+        let first_start = occurrences[0].start.unwrap();
+        for (index, occurrence) in occurrences.iter().enumerate() {
+            let start = first_start + Duration::weeks(index as i64);
+            assert_eq!(occurrence.start, Some(start));
+            assert_eq!(occurrence.end, Some(start + Duration::minutes(90)));
+            assert_eq!(occurrence.organizer.as_deref(), Some("organizer"));
+            assert_eq!(occurrence.timezone, Some(IN_HOURS_TIMEZONE));
+        }
+
+        // then each occurrence can be joined on its own
+        let participant = logged_in_user(&client, "participant");
+        join_event(&client, occurrences[1].id, &participant).unwrap();
+        let occurrences = get_events(&client, "participant=participant").items;
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].start, Some(first_start + Duration::weeks(1)));
+    }
+
+    fn overriding_an_occurrence_of_a_series(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a weekly series of 3 rides
+        let organizer = logged_in_user(&client, "organizer");
+        let occurrences = publish_weekly_series(&client, 3, &organizer);
+
+        // when the organizer cancels the first occurrence and moves the second one
+        cancel_event(&client, occurrences[0].id, "bad weather", &organizer).unwrap();
+        let patch = EventPatch {
+            start: Some(in_hours(24 + 7 * 24 + 1)),
+            ..EventPatch::default()
+        };
+        let moved = update_event(&client, occurrences[1].id, &patch, &organizer).unwrap();
+
+        // then only these occurrences are changed and they stay in the series
+        assert_eq!(moved.series, occurrences[1].series);
+        assert_eq!(
+            get_event(&client, occurrences[0].id).unwrap().status,
+            EventStatus::Cancelled
+        );
+        assert_eq!(get_event(&client, occurrences[1].id), Ok(moved));
+        assert_eq!(
+            get_event(&client, occurrences[2].id),
+            Ok(occurrences[2].clone())
+        );
+    }
+
+    fn inserting_a_series_as_a_whole(state: State) {
+        // given a stored event
+        let existing = Event::new("existing".into());
+        state.events.insert_all(vec![existing.clone()]).unwrap();
+
+        // when events are inserted along with an event having the same ID
+        let new = Event::new("new".into());
+        let result = state.events.insert_all(vec![new.clone(), existing]);
+
+        // then none of them is inserted
+        assert_eq!(result, Err(repository::Error::AlreadyExists));
+        assert_eq!(state.events.get(new.id), Ok(None));
+        assert_eq!(state.events.all().unwrap().len(), 1);
+    }
+
+    fn publishing_an_invalid_series(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        for (recurrence, field) in [
+            (
+                Recurrence {
+                    count: Some(0),
+                    ..Recurrence::new(Frequency::Weekly)
+                },
+                "recurrence.count",
+            ),
+            (
+                Recurrence {
+                    until: Some(in_hours(1)),
+                    ..Recurrence::new(Frequency::Monthly)
+                },
+                "recurrence.until",
+            ),
+            (
+                Recurrence {
+                    until: Some(in_hours(24 * 400)),
+                    ..Recurrence::new(Frequency::Monthly)
+                },
+                "recurrence.until",
+            ),
+            (
+                Recurrence {
+                    count: Some(60),
+                    ..Recurrence::new(Frequency::Weekly)
+                },
+                "recurrence.count",
+            ),
+        ] {
+            // when the organizer publishes a series with an invalid recurrence
+            let event = NewEvent {
+                timezone: Some(IN_HOURS_TIMEZONE),
+                recurrence: Some(recurrence),
+                ..new_event("some_event")
+            };
+            let response = client
+                .post(uri!("/api/events"))
+                .header(ContentType::JSON)
+                .header(bearer(&organizer))
+                .body(serde_json::to_string(&event).unwrap())
+                .dispatch();
+
+            // then the server responds with a validation error on the recurrence
+            assert_eq!(response.status(), Status::BadRequest);
+            assert_eq!(api_error(response).fields[0].field, field);
+        }
+
+        // when the organizer publishes a series without its timezone, or in a timezone which
+        // offset is not the one of the start
+        for timezone in [None, Some(Tz::Etc__GMTPlus5)] {
+            let event = NewEvent {
+                timezone,
+                recurrence: Some(Recurrence::new(Frequency::Weekly)),
+                ..new_event("some_event")
+            };
+            let response = client
+                .post(uri!("/api/events"))
+                .header(ContentType::JSON)
+                .header(bearer(&organizer))
+                .body(serde_json::to_string(&event).unwrap())
+                .dispatch();
+
+            // then the server responds with a validation error on the timezone
+            assert_eq!(response.status(), Status::BadRequest);
+            assert_eq!(api_error(response).fields[0].field, "timezone");
+        }
+
+        // then no event is created
+        assert!(get_events(&client, "").items.is_empty());
+    }
+
+    /// Body of a successful iCalendar response.
+    fn get_ics(client: &Client, uri: &str) -> String {
+        let response = client.get(uri.to_owned()).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::Calendar));
+        response.into_string().unwrap()
+    }
 
-        // given an event
-        let event = Event::new("some_event".to_owned());
-        assert_eq!(publish_event(&client, &event), Ok(()));
+    fn exporting_an_event_to_a_calendar(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
 
-        // when a logged-in user requests to join the event
+        // given an event with a meeting point and a long name
+        let organizer = logged_in_user(&client, "organizer");
+        let event = NewEvent {
+            meeting_point: Some(meeting_point(
+                "Place de la Bastille, Paris",
+                48.8532,
+                2.3691,
+            )),
+            duration_minutes: Some(120),
+            ..new_event(&"Long ride ".repeat(10))
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+
+        // when a user exports the event
+        let ics = get_ics(&client, &format!("/api/event/{}/ics", event.id));
+
+        // then the calendar holds a VEVENT identified by the ID of the event
+        let start = event.start.unwrap().with_timezone(&Utc);
+        for line in [
+            "BEGIN:VCALENDAR",
+            "BEGIN:VEVENT",
+            &format!("UID:{}@join-my-ride", event.id),
+            &format!("DTSTART:{}", start.format("%Y%m%dT%H%M%SZ")),
+            &format!(
+                "DTEND:{}",
+                (start + Duration::minutes(120)).format("%Y%m%dT%H%M%SZ")
+            ),
+            "LOCATION:Place de la Bastille\\, Paris",
+            "GEO:48.8532;2.3691",
+            "SEQUENCE:0",
+            "STATUS:CONFIRMED",
+            "END:VCALENDAR",
+        ] {
+            assert!(
+                ics.contains(&format!("{}\r\n", line)),
+                "{} not in {}",
+                line,
+                ics
+            );
+        }
+        // then the long lines are folded
+        assert!(ics
+            .split("\r\n")
+            .all(|line| line.len() <= 75 && !line.contains('\n')));
+
+        // when the event is cancelled and exported again
+        cancel_event(&client, event.id, "storm", &organizer).unwrap();
+        let ics = get_ics(&client, &format!("/api/event/{}/ics", event.id));
+
+        // then the same VEVENT is cancelled in a later revision
+        assert!(ics.contains(&format!("UID:{}@join-my-ride\r\n", event.id)));
+        assert!(ics.contains("SEQUENCE:1\r\n"));
+        assert!(ics.contains("STATUS:CANCELLED\r\n"));
+    }
+
+    fn subscribing_to_the_calendar_feed(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a user organizing an event and joining another one
+        let alice = logged_in_user(&client, "alice");
+        let bob = logged_in_user(&client, "bob");
+        let organized = publish_event(&client, &new_event("organized"), &alice).unwrap();
+        let joined = publish_event(&client, &new_event("joined"), &bob).unwrap();
+        let other = publish_event(&client, &new_event("other"), &bob).unwrap();
+        join_event(&client, joined.id, &alice).unwrap();
+
+        // when the user requests their calendar feed
+        let get_feed = |uri: &str| -> CalendarFeed {
+            let response = client.get(uri.to_owned()).header(bearer(&alice)).dispatch();
+            assert_eq!(response.status(), Status::Ok);
+            serde_json::from_str(&response.into_string().unwrap()).unwrap()
+        };
+        let feed = get_feed("/api/calendar");
+
+        // then the feed is the same on every request
+        assert_eq!(get_feed("/api/calendar"), feed);
+
+        // then the feed lists the joined and organized events, without logging in
+        let ics = get_ics(&client, &feed.path);
+        assert!(ics.contains(&format!("UID:{}@join-my-ride", organized.id)));
+        assert!(ics.contains(&format!("UID:{}@join-my-ride", joined.id)));
+        assert!(!ics.contains(&format!("UID:{}@join-my-ride", other.id)));
+
+        // when the user resets their feed
         let response = client
-            .put(format!("/api/join/{}", event.id))
-            .header(rocket::http::Header {
-                name: "authorization".into(),
-                value: "Bearer valid_token".into(),
+            .post("/api/calendar/reset")
+            .header(bearer(&alice))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let new_feed: CalendarFeed =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+
+        // then the previous feed stops working
+        assert_ne!(new_feed, feed);
+        assert_eq!(
+            client.get(feed.path.clone()).dispatch().status(),
+            Status::NotFound
+        );
+        get_ics(&client, &new_feed.path);
+    }
+
+    fn getting_the_calendar_feed_without_being_logged_in(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // when a user requests a calendar feed without being logged in
+        let response = client.get("/api/calendar").dispatch();
+
+        // then the server asks them to log in
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(api_error(response).code, ErrorCode::MissingToken);
+    }
+
+    /// Titles and links of the entries of the Atom feed of `/api/events/atom?<query>`.
+    fn get_atom_entries(client: &Client, query: &str) -> Vec<(String, String)> {
+        let response = client.get(format!("/api/events/atom?{}", query)).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.content_type(),
+            Some(ContentType::new("application", "atom+xml"))
+        );
+        let xml = response.into_string().unwrap();
+        let document = roxmltree::Document::parse(&xml).unwrap();
+        let child_text = |node: roxmltree::Node, name: &str| -> String {
+            node.children()
+                .find(|child| child.has_tag_name(name))
+                .and_then(|child| child.text())
+                .unwrap_or_default()
+                .to_owned()
+        };
+        document
+            .root_element()
+            .children()
+            .filter(|node| node.has_tag_name("entry"))
+            .map(|entry| {
+                let link = entry
+                    .children()
+                    .find(|child| child.has_tag_name("link"))
+                    .and_then(|link| link.attribute("href"))
+                    .unwrap_or_default();
+                // the entries are identified by the URL of their event
+                assert_eq!(child_text(entry, "id"), link);
+                (child_text(entry, "title"), link.to_owned())
             })
+            .collect()
+    }
+
+    fn following_newly_published_events(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+
+        // given a draft and two events published one hour apart
+        let draft = NewEvent {
+            status: EventStatus::Draft,
+            ..new_event("Draft & co")
+        };
+        let draft = publish_event(&client, &draft, &organizer).unwrap();
+        clock.advance(Duration::hours(1));
+        let road = NewEvent {
+            discipline: Some(Discipline::Road),
+            ..new_event("Road <ride>")
+        };
+        let road = publish_event(&client, &road, &organizer).unwrap();
+        clock.advance(Duration::hours(1));
+        let gravel = NewEvent {
+            discipline: Some(Discipline::Gravel),
+            ..new_event("Gravel ride")
+        };
+        let gravel = publish_event(&client, &gravel, &organizer).unwrap();
+
+        // when a user reads the feed
+        // then it lists the published events, the most recent first, linking to their pages
+        assert_eq!(
+            get_atom_entries(&client, ""),
+            vec![
+                (
+                    "Gravel ride".to_owned(),
+                    format!("{}/event/{}", DEFAULT_PUBLIC_URL, gravel.id)
+                ),
+                (
+                    "Road <ride>".to_owned(),
+                    format!("{}/event/{}", DEFAULT_PUBLIC_URL, road.id)
+                ),
+            ]
+        );
+
+        // when the draft is published
+        clock.advance(Duration::hours(1));
+        let patch = EventPatch {
+            status: Some(EventStatus::Published),
+            ..EventPatch::default()
+        };
+        update_event(&client, draft.id, &patch, &organizer).unwrap();
+
+        // then it becomes the most recent entry
+        assert_eq!(
+            get_atom_entries(&client, "limit=1"),
+            vec![(
+                "Draft & co".to_owned(),
+                format!("{}/event/{}", DEFAULT_PUBLIC_URL, draft.id)
+            )]
+        );
+
+        // when the user filters the feed, then only the matching events are listed
+        assert_eq!(
+            get_atom_entries(&client, "discipline=road"),
+            vec![(
+                "Road <ride>".to_owned(),
+                format!("{}/event/{}", DEFAULT_PUBLIC_URL, road.id)
+            )]
+        );
+        let response = client.get("/api/events/atom?discipline=bmx").dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    fn post_comment(
+        client: &Client,
+        event_id: Id,
+        text: &str,
+        token: &str,
+    ) -> Result<Comment, Status> {
+        let response = client
+            .post(format!("/api/event/{}/comments", event_id))
+            .header(ContentType::JSON)
+            .header(bearer(token))
+            .body(serde_json::to_string(&CommentText { text: text.into() }).unwrap())
             .dispatch();
+        if response.status() == Status::Created {
+            Ok(serde_json::from_str(response.into_string().unwrap().as_str()).unwrap())
+        } else {
+            Err(response.status())
+        }
+    }
 
-        // then the server responds with a success code
+    fn edit_comment(client: &Client, id: Id, text: &str, token: &str) -> Result<Comment, Status> {
+        let response = client
+            .put(format!("/api/comment/{}", id))
+            .header(ContentType::JSON)
+            .header(bearer(token))
+            .body(serde_json::to_string(&CommentText { text: text.into() }).unwrap())
+            .dispatch();
+        if response.status() == Status::Ok {
+            Ok(serde_json::from_str(response.into_string().unwrap().as_str()).unwrap())
+        } else {
+            Err(response.status())
+        }
+    }
+
+    fn delete_comment(client: &Client, id: Id, token: &str) -> Status {
+        client
+            .delete(format!("/api/comment/{}", id))
+            .header(bearer(token))
+            .dispatch()
+            .status()
+    }
+
+    /// Page of the comments of an event, `query` holding the pagination parameters.
+    fn get_comments(client: &Client, event_id: Id, query: &str) -> Page<Comment> {
+        let response = client
+            .get(format!("/api/event/{}/comments?{}", event_id, query))
+            .dispatch();
         assert_eq!(response.status(), Status::Ok);
+        serde_json::from_str(&response.into_string().unwrap()).unwrap()
+    }
 
-        // then the user is added to the list of participants in the event
-        let updated_event = get_event(&client, event.id).unwrap();
-        assert!(updated_event
-            .participants
-            .contains(&"valid_user".to_owned()));
+    fn texts(comments: &[Comment]) -> Vec<&str> {
+        comments
+            .iter()
+            .map(|comment| comment.text.as_str())
+            .collect()
     }
 
-    #[test]
-    fn joining_a_non_existing_event() {
-        let client = Client::tracked(rocket()).expect("valid rocket instance");
+    fn commenting_an_event(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
 
-        // given a non-existing event
-        let id = Id::new_v4();
-        assert!(get_event(&client, id).is_err());
+        // given an event
+        let organizer = logged_in_user(&client, "organizer");
+        let rider = logged_in_user(&client, "rider");
+        let event = publish_event(&client, &new_event("event"), &organizer).unwrap();
 
-        // when a logged-in user requests to join the event
+        // when users comment the event
+        let first = post_comment(&client, event.id, "  Is the route paved?  ", &rider).unwrap();
+        post_comment(
+            &client,
+            event.id,
+            "Mostly, a few gravel sections.",
+            &organizer,
+        )
+        .unwrap();
+        post_comment(&client, event.id, "Great, see you there!", &rider).unwrap();
+
+        // then the comments are recorded with their author, without the surrounding whitespace
+        assert_eq!(first.author, "rider");
+        assert_eq!(first.event_id, event.id);
+        assert_eq!(first.text, "Is the route paved?");
+        assert_eq!(first.edited_at, None);
+
+        // then anyone can read the thread a page at a time, the oldest comments first
+        let page = get_comments(&client, event.id, "limit=2");
+        assert_eq!(
+            texts(&page.items),
+            vec!["Is the route paved?", "Mostly, a few gravel sections."]
+        );
+        let cursor = page.next_cursor.unwrap();
+        let page = get_comments(&client, event.id, &format!("cursor={}&limit=2", cursor));
+        assert_eq!(texts(&page.items), vec!["Great, see you there!"]);
+        assert_eq!(page.next_cursor, None);
+
+        // then the comments of other events are not listed
+        let other = publish_event(&client, &new_event("other"), &organizer).unwrap();
+        assert!(get_comments(&client, other.id, "").items.is_empty());
+
+        // when a user comments without being logged in, then they are asked to log in
         let response = client
-            .put(format!("/api/join/{}", id))
-            .header(rocket::http::Header {
-                name: "authorization".into(),
-                value: "Bearer valid_token".into(),
-            })
+            .post(format!("/api/event/{}/comments", event.id))
+            .header(ContentType::JSON)
+            .body(r#"{"text":"anonymous"}"#)
             .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
 
-        // then the server responds with a not found code
+        // when a user comments an event which does not exist, then the event is not found
+        assert_eq!(
+            post_comment(&client, Id::new_v4(), "hello", &rider),
+            Err(Status::NotFound)
+        );
+        let response = client
+            .get(format!("/api/event/{}/comments", Id::new_v4()))
+            .dispatch();
         assert_eq!(response.status(), Status::NotFound);
     }
 
-    #[test]
-    fn joining_an_event_twice() {
-        let client = Client::tracked(rocket()).expect("valid rocket instance");
+    fn commenting_with_an_invalid_text(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let user = logged_in_user(&client, "user");
+        let event = publish_event(&client, &new_event("event"), &user).unwrap();
 
-        // given an event that a user already joined
-        let event = Event::new("some_event".to_owned());
-        assert_eq!(publish_event(&client, &event), Ok(()));
-        assert_eq!(join_event(&client, event.id, "valid_token"), Ok(()));
+        for text in ["   ".to_owned(), "a".repeat(2001)] {
+            // when a user posts a blank or too long comment
+            let response = client
+                .post(format!("/api/event/{}/comments", event.id))
+                .header(ContentType::JSON)
+                .header(bearer(&user))
+                .body(serde_json::to_string(&CommentText { text }).unwrap())
+                .dispatch();
 
-        // when a logged-in user requests to join the event
-        let response = client
-            .put(format!("/api/join/{}", event.id))
-            .header(rocket::http::Header {
-                name: "authorization".into(),
-                value: "Bearer valid_token".into(),
+            // then the server reports the invalid text
+            assert_eq!(response.status(), Status::BadRequest);
+            let error = api_error(response);
+            assert_eq!(error.code, ErrorCode::Validation);
+            assert_eq!(error.fields[0].field, "text");
+        }
+        assert!(get_comments(&client, event.id, "").items.is_empty());
+    }
+
+    fn editing_a_comment(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a comment
+        let organizer = logged_in_user(&client, "organizer");
+        let rider = logged_in_user(&client, "rider");
+        let event = publish_event(&client, &new_event("event"), &organizer).unwrap();
+        let comment = post_comment(&client, event.id, "see you at 9", &rider).unwrap();
+
+        // when its author edits it
+        clock.advance(Duration::minutes(5));
+        let edited = edit_comment(&client, comment.id, "see you at 10", &rider).unwrap();
+
+        // then the new text is listed and the comment is marked as edited
+        assert_eq!(edited.text, "see you at 10");
+        assert_eq!(edited.posted_at, comment.posted_at);
+        assert_eq!(
+            edited.edited_at,
+            Some(comment.posted_at + Duration::minutes(5))
+        );
+        assert_eq!(get_comments(&client, event.id, "").items, vec![edited]);
+
+        // when another user, even the organizer, edits it, then they are not allowed to
+        assert_eq!(
+            edit_comment(&client, comment.id, "changed", &organizer),
+            Err(Status::Forbidden)
+        );
+
+        // when the author empties it, then the text is refused
+        assert_eq!(
+            edit_comment(&client, comment.id, "", &rider),
+            Err(Status::BadRequest)
+        );
+
+        // when a user edits a comment which does not exist, then it is not found
+        assert_eq!(
+            edit_comment(&client, Id::new_v4(), "hello", &rider),
+            Err(Status::NotFound)
+        );
+    }
+
+    fn deleting_a_comment(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given two comments
+        let organizer = logged_in_user(&client, "organizer");
+        let rider = logged_in_user(&client, "rider");
+        let event = publish_event(&client, &new_event("event"), &organizer).unwrap();
+        let spam = post_comment(&client, event.id, "buy cheap bikes", &rider).unwrap();
+        post_comment(&client, event.id, "see you there", &rider).unwrap();
+
+        // when a user who does not organize the event deletes a comment, even their own
+        // then they are not allowed to
+        assert_eq!(delete_comment(&client, spam.id, &rider), Status::Forbidden);
+
+        // when the organizer deletes it
+        assert_eq!(
+            delete_comment(&client, spam.id, &organizer),
+            Status::NoContent
+        );
+
+        // then it is not listed anymore
+        assert_eq!(
+            texts(&get_comments(&client, event.id, "").items),
+            vec!["see you there"]
+        );
+
+        // when the organizer deletes it again, then it is not found
+        assert_eq!(
+            delete_comment(&client, spam.id, &organizer),
+            Status::NotFound
+        );
+    }
+
+    /// Kind, event ID and user of the updates broadcast so far.
+    fn received_updates(
+        updates: &mut rocket::tokio::sync::broadcast::Receiver<EventUpdate>,
+    ) -> Vec<(&'static str, Id, Option<String>)> {
+        std::iter::from_fn(|| updates.try_recv().ok())
+            .map(|update| match update {
+                EventUpdate::Created { event } => ("created", event.id, None),
+                EventUpdate::Updated { event } => ("updated", event.id, None),
+                EventUpdate::Joined { event, username } => ("joined", event.id, Some(username)),
+                EventUpdate::Left { event, username } => ("left", event.id, Some(username)),
             })
+            .collect()
+    }
+
+    fn following_events_live(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+        let rider = logged_in_user(&client, "rider");
+
+        // given a client following the events live
+        let response = client.get("/api/events/updates").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::EventStream));
+        let mut updates = client
+            .rocket()
+            .state::<State>()
+            .unwrap()
+            .updates
+            .subscribe();
+
+        // when an event is published, changed, joined, left and cancelled
+        let event = publish_event(&client, &new_event("event"), &organizer).unwrap();
+        let patch = EventPatch {
+            name: Some("renamed".into()),
+            ..EventPatch::default()
+        };
+        update_event(&client, event.id, &patch, &organizer).unwrap();
+        join_event(&client, event.id, &rider).unwrap();
+        assert_eq!(leave_event(&client, event.id, &rider), Status::Ok);
+        cancel_event(&client, event.id, "storm", &organizer).unwrap();
+
+        // then every change is broadcast
+        assert_eq!(
+            received_updates(&mut updates),
+            vec![
+                ("created", event.id, None),
+                ("updated", event.id, None),
+                ("joined", event.id, Some("rider".to_owned())),
+                ("left", event.id, Some("rider".to_owned())),
+                ("updated", event.id, None),
+            ]
+        );
+
+        // when a change is refused, then nothing is broadcast
+        join_event(&client, event.id, &rider).unwrap_err();
+        assert!(received_updates(&mut updates).is_empty());
+    }
+
+    /// Make the server append its emails to a new mbox file, to read them back. The file is
+    /// deleted when the returned path is dropped.
+    fn mbox_mailer(state: &mut State) -> (TempPath, Arc<MboxMailer>) {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        let mailer = Arc::new(MboxMailer::new(&*path, DEFAULT_FROM));
+        state.mailer = mailer.clone();
+        (path, mailer)
+    }
+
+    /// Emails sent so far, once the queued ones are sent.
+    fn sent_emails(client: &Client, mailer: &MboxMailer) -> Vec<Email> {
+        notification::send_queued_emails(client.rocket().state::<State>().unwrap());
+        mailer.emails().unwrap()
+    }
+
+    fn set_email(client: &Client, email: &str, token: &str) -> Status {
+        client
+            .put("/api/account/email")
+            .header(ContentType::JSON)
+            .header(bearer(token))
+            .body(
+                serde_json::to_string(&EmailAddress {
+                    email: email.into(),
+                })
+                .unwrap(),
+            )
+            .dispatch()
+            .status()
+    }
+
+    /// Recipient and subject of the emails sent so far.
+    fn subjects(emails: &[Email]) -> Vec<(&str, &str)> {
+        emails
+            .iter()
+            .map(|email| (email.to.as_str(), email.subject.as_str()))
+            .collect()
+    }
+
+    fn notifying_participants_by_email(mut state: State) {
+        let (_mbox, mailer) = mbox_mailer(&mut state);
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a full event joined by alice and bob, carol being waitlisted, bob has no address
+        let organizer = logged_in_user(&client, "organizer");
+        let event = NewEvent {
+            capacity: Some(2),
+            ..new_event("sunday ride")
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+        let alice = logged_in_user(&client, "alice");
+        let bob = logged_in_user(&client, "bob");
+        let carol = logged_in_user(&client, "carol");
+        assert_eq!(
+            set_email(&client, "alice@example.com", &alice),
+            Status::NoContent
+        );
+        assert_eq!(
+            set_email(&client, "carol@example.com", &carol),
+            Status::NoContent
+        );
+        for token in [&alice, &bob, &carol] {
+            join_event(&client, event.id, token).unwrap();
+        }
+
+        // when the organizer changes the meeting point
+        let patch = EventPatch {
+            meeting_point: Some(Some(meeting_point("main square", 48.85, 2.35))),
+            ..EventPatch::default()
+        };
+        update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the emails are queued, not sent while answering the request
+        assert!(mailer.emails().unwrap().is_empty());
+
+        // then the participants and waitlisted users having an address are told so
+        let emails = sent_emails(&client, &mailer);
+        assert_eq!(
+            subjects(&emails),
+            vec![
+                ("alice@example.com", "sunday ride has changed"),
+                ("carol@example.com", "sunday ride has changed"),
+            ]
+        );
+        assert!(emails[0].body.contains("Meeting point: main square"));
+        assert!(emails[0]
+            .body
+            .contains(&format!("http://localhost:8000/event/{}", event.id)));
+        assert!(emails[0]
+            .body
+            .contains("http://localhost:8000/api/unsubscribe/"));
+
+        // when bob leaves
+        assert_eq!(leave_event(&client, event.id, &bob), Status::Ok);
+
+        // then carol is told she was promoted from the waitlist
+        let emails = sent_emails(&client, &mailer);
+        assert_eq!(
+            subjects(&emails[2..]),
+            vec![("carol@example.com", "You are going to sunday ride")]
+        );
+
+        // when the organizer cancels the event
+        cancel_event(&client, event.id, "storm forecast", &organizer).unwrap();
+
+        // then the participants are told why
+        let emails = sent_emails(&client, &mailer);
+        assert_eq!(emails.len(), 5);
+        for email in &emails[3..] {
+            assert_eq!(email.subject, "sunday ride is cancelled");
+            assert!(email.body.contains("Reason: storm forecast"));
+        }
+    }
+
+    fn unsubscribing_from_emails(mut state: State) {
+        let (_mbox, mailer) = mbox_mailer(&mut state);
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a participant notified of a change of the event
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("sunday ride"), &organizer).unwrap();
+        let rider = logged_in_user(&client, "rider");
+        set_email(&client, "rider@example.com", &rider);
+        join_event(&client, event.id, &rider).unwrap();
+        let rename = |name: &str| {
+            let patch = EventPatch {
+                name: Some(name.into()),
+                ..EventPatch::default()
+            };
+            update_event(&client, event.id, &patch, &organizer).unwrap();
+        };
+        rename("saturday ride");
+        let emails = sent_emails(&client, &mailer);
+        assert_eq!(emails.len(), 1);
+
+        // when the participant follows the unsubscribe link of the email
+        let link = emails[0]
+            .body
+            .lines()
+            .find_map(|line| line.strip_prefix("Stop receiving these emails: "))
+            .unwrap()
+            .trim_start_matches("http://localhost:8000")
+            .to_owned();
+        let response = client.get(&link).dispatch();
+
+        // then they are asked to confirm, still being notified until they do
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+        assert!(response
+            .into_string()
+            .unwrap()
+            .contains(&format!(r#"<form method="post" action="{}">"#, link)));
+        rename("sunday ride");
+        assert_eq!(sent_emails(&client, &mailer).len(), 2);
+
+        // when they confirm
+        let response = client.post(&link).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        // then they are not notified anymore
+        rename("monday ride");
+        assert_eq!(sent_emails(&client, &mailer).len(), 2);
+
+        // when they give their address again, then they are notified again
+        assert_eq!(
+            set_email(&client, "rider@example.com", &rider),
+            Status::NoContent
+        );
+        rename("tuesday ride");
+        assert_eq!(sent_emails(&client, &mailer).len(), 3);
+
+        // when following or confirming an unknown link, then it is not found
+        let response = client.get("/api/unsubscribe/unknown").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        let response = client.post("/api/unsubscribe/unknown").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    fn sending_the_queued_emails_again_after_a_failure(mut state: State) {
+        // given a queued email and a mail server which cannot be reached
+        let email = Email {
+            to: "rider@example.com".into(),
+            subject: "sunday ride".into(),
+            body: "see you there".into(),
+        };
+        state.outbox.push(email.clone()).unwrap();
+        let unreachable = tempfile::tempdir().unwrap();
+        state.mailer = Arc::new(MboxMailer::new(unreachable.path(), DEFAULT_FROM));
+
+        // when the queued emails are sent, then the email stays in the outbox
+        notification::send_queued_emails(&state);
+        assert_eq!(state.outbox.queued().unwrap().len(), 1);
+
+        // when they are sent again once the mail server is back, then the email is sent once
+        let (_mbox, mailer) = mbox_mailer(&mut state);
+        notification::send_queued_emails(&state);
+        notification::send_queued_emails(&state);
+        assert_eq!(mailer.emails().unwrap(), vec![email]);
+        assert!(state.outbox.queued().unwrap().is_empty());
+    }
+
+    fn setting_an_invalid_email(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let rider = logged_in_user(&client, "rider");
+
+        // when a user gives an invalid address
+        let response = client
+            .put("/api/account/email")
+            .header(ContentType::JSON)
+            .header(bearer(&rider))
+            .body(r#"{"email":"not an address"}"#)
             .dispatch();
 
-        // then the server responds with a success code
+        // then the field is reported invalid
+        assert_eq!(response.status(), Status::BadRequest);
+        let error = api_error(response);
+        assert_eq!(error.code, ErrorCode::Validation);
+        assert_eq!(error.fields[0].field, "email");
+    }
+
+    fn get_notifications(client: &Client, token: &str) -> Vec<Notification> {
+        let response = client
+            .get("/api/notifications")
+            .header(bearer(token))
+            .dispatch();
         assert_eq!(response.status(), Status::Ok);
+        serde_json::from_str(&response.into_string().unwrap()).unwrap()
+    }
 
-        // then the user is not added again to the list of participants in the event
-        let updated_event = get_event(&client, event.id).unwrap();
-        assert_eq!(updated_event.participants.len(), 1);
-        assert!(updated_event
-            .participants
-            .contains(&"valid_user".to_owned()));
+    /// Text and read flag of the notifications.
+    fn inbox(notifications: &[Notification]) -> Vec<(&str, bool)> {
+        notifications
+            .iter()
+            .map(|notification| (notification.text.as_str(), notification.read))
+            .collect()
+    }
+
+    fn reading_notifications(state: State) {
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given an event joined by a rider
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("sunday ride"), &organizer).unwrap();
+        let rider = logged_in_user(&client, "rider");
+        join_event(&client, event.id, &rider).unwrap();
+
+        // when the organizer changes its meeting point
+        let patch = EventPatch {
+            meeting_point: Some(Some(meeting_point("main square", 48.85, 2.35))),
+            ..EventPatch::default()
+        };
+        let event = update_event(&client, event.id, &patch, &organizer).unwrap();
+
+        // then the organizer and the rider find an unread notification in their inbox
+        let notifications = get_notifications(&client, &organizer);
+        assert_eq!(
+            inbox(&notifications),
+            vec![("rider joined sunday ride", false)]
+        );
+        assert_eq!(notifications[0].event_id, Some(event.id));
+        let start = event.start.unwrap().format("%A %-d %B %Y, %H:%M");
+        let changed = format!(
+            "sunday ride changed, start: {}, meeting point: main square",
+            start
+        );
+        assert_eq!(
+            inbox(&get_notifications(&client, &rider)),
+            vec![(changed.as_str(), false)]
+        );
+
+        // when the rider leaves and joins again, then the organizer is notified of both joins
+        assert_eq!(leave_event(&client, event.id, &rider), Status::Ok);
+        join_event(&client, event.id, &rider).unwrap();
+        join_event(&client, event.id, &rider).unwrap();
+        let notifications = get_notifications(&client, &organizer);
+        assert_eq!(notifications.len(), 2);
+
+        // when the organizer marks the oldest one read
+        let response = client
+            .put(format!("/api/notification/{}/read", notifications[1].id))
+            .header(bearer(&organizer))
+            .dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+
+        // then only the newest one is unread
+        assert_eq!(
+            inbox(&get_notifications(&client, &organizer)),
+            vec![
+                ("rider joined sunday ride", false),
+                ("rider joined sunday ride", true)
+            ]
+        );
+
+        // when the rider marks a notification of the organizer read, then it is not found
+        let response = client
+            .put(format!("/api/notification/{}/read", notifications[0].id))
+            .header(bearer(&rider))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+
+        // when the organizer marks all their notifications read, then none is unread
+        let response = client
+            .put("/api/notifications/read")
+            .header(bearer(&organizer))
+            .dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+        assert!(get_notifications(&client, &organizer)
+            .iter()
+            .all(|notification| notification.read));
+        assert!(!get_notifications(&client, &rider)[0].read);
+
+        // when reading the notifications without being logged in, then it is refused
+        let response = client.get("/api/notifications").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    fn reminding_the_participants_once(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        let (_mbox, mailer) = mbox_mailer(&mut state);
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let state = client.rocket().state::<State>().unwrap();
+
+        // given an event starting in 30 hours, joined by a rider, another one being waitlisted
+        let organizer = logged_in_user(&client, "organizer");
+        let event = NewEvent {
+            capacity: Some(1),
+            ..NewEvent::new("sunday ride", in_hours(30))
+        };
+        let event = publish_event(&client, &event, &organizer).unwrap();
+        let rider = logged_in_user(&client, "rider");
+        let waitlisted = logged_in_user(&client, "waitlisted");
+        set_email(&client, "rider@example.com", &rider);
+        set_email(&client, "waitlisted@example.com", &waitlisted);
+        join_event(&client, event.id, &rider).unwrap();
+        join_event(&client, event.id, &waitlisted).unwrap();
+
+        // when the jobs run more than a day before the start, then nobody is reminded
+        jobs::run(state);
+        assert!(mailer.emails().unwrap().is_empty());
+
+        // when the jobs run less than a day before the start
+        clock.advance(Duration::hours(7));
+        jobs::run(state);
+
+        // then the participant is reminded by email and in their inbox
+        let emails = mailer.emails().unwrap();
+        assert_eq!(
+            subjects(&emails),
+            vec![("rider@example.com", "sunday ride starts tomorrow")]
+        );
+        assert!(get_notifications(&client, &rider)[0]
+            .text
+            .starts_with("sunday ride starts"));
+
+        // when the jobs run again, then the reminder is not sent again
+        clock.advance(Duration::hours(1));
+        jobs::run(state);
+        assert_eq!(mailer.emails().unwrap().len(), 1);
+        assert_eq!(get_notifications(&client, &rider).len(), 1);
+    }
+
+    fn completing_the_events_which_ended(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let state = client.rocket().state::<State>().unwrap();
+        let mut updates = state.updates.subscribe();
+
+        // given an event ending 3 hours after its start, and an event without an end
+        let organizer = logged_in_user(&client, "organizer");
+        let with_end = NewEvent {
+            end: Some(in_hours(4)),
+            ..NewEvent::new("with end", in_hours(1))
+        };
+        let with_end = publish_event(&client, &with_end, &organizer).unwrap();
+        let without_end = publish_event(
+            &client,
+            &NewEvent::new("without end", in_hours(2)),
+            &organizer,
+        )
+        .unwrap();
+        received_updates(&mut updates);
+
+        // when the jobs run after the start of both events
+        clock.advance(Duration::hours(3));
+        jobs::run(state);
+
+        // then only the event without an end is completed
+        assert_eq!(
+            get_event(&client, with_end.id).unwrap().status,
+            EventStatus::Published
+        );
+        assert_eq!(
+            get_event(&client, without_end.id).unwrap().status,
+            EventStatus::Completed
+        );
+
+        // when the jobs run after the end of the other event
+        clock.advance(Duration::hours(2));
+        jobs::run(state);
+        jobs::run(state);
+
+        // then it is completed as well, and each completion is broadcast once
+        assert_eq!(
+            get_event(&client, with_end.id).unwrap().status,
+            EventStatus::Completed
+        );
+        assert_eq!(
+            received_updates(&mut updates),
+            vec![
+                ("updated", without_end.id, None),
+                ("updated", with_end.id, None)
+            ]
+        );
+    }
+
+    /// Rate limiter applying `limit` to `route`, the other routes being left unlimited in practice.
+    fn rate_limiter(route: &str, limit: Limit) -> Arc<RateLimiter> {
+        let unlimited = Limit {
+            burst: 1000,
+            per_minute: 1000,
+        };
+        Arc::new(RateLimiter::new(HashMap::from([
+            ("default".to_owned(), unlimited),
+            (route.to_owned(), limit),
+        ])))
+    }
+
+    fn rate_limiting_the_requests_of_a_user(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        state.rate_limiter = rate_limiter(
+            "publish_event",
+            Limit {
+                burst: 2,
+                per_minute: 2,
+            },
+        );
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let organizer = logged_in_user(&client, "organizer");
+        let other = logged_in_user(&client, "other");
+
+        // given an organizer who published as many events as allowed at once
+        for name in ["first", "second"] {
+            publish_event(&client, &new_event(name), &organizer).unwrap();
+        }
+
+        // when they publish another one
+        let response = client
+            .post(uri!("/api/events"))
+            .header(ContentType::JSON)
+            .header(bearer(&organizer))
+            .body(serde_json::to_string(&new_event("third")).unwrap())
+            .dispatch();
+
+        // then it is refused until the bucket is refilled
+        assert_eq!(response.status(), Status::TooManyRequests);
+        assert_eq!(response.headers().get_one("Retry-After"), Some("30"));
+        assert_eq!(api_error(response).code, ErrorCode::TooManyRequests);
+
+        // then another user is not limited
+        publish_event(&client, &new_event("other"), &other).unwrap();
+
+        // when the organizer waits for the delay, then they can publish again once
+        clock.advance(Duration::seconds(30));
+        publish_event(&client, &new_event("third"), &organizer).unwrap();
+        assert!(publish_event(&client, &new_event("fourth"), &organizer).is_err());
+    }
+
+    fn rate_limiting_the_anonymous_requests_by_ip(mut state: State) {
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        state.clock = clock.clone();
+        state.rate_limiter = rate_limiter(
+            "signup",
+            Limit {
+                burst: 1,
+                per_minute: 1,
+            },
+        );
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+        let sign_up_from = |username: &str, address: &str| {
+            let credentials = Credentials {
+                username: username.to_owned(),
+                password: "password".to_owned(),
+            };
+            client
+                .post(uri!("/api/signup"))
+                .remote(address.parse().unwrap())
+                .header(ContentType::JSON)
+                .body(serde_json::to_string(&credentials).unwrap())
+                .dispatch()
+        };
+
+        // given a client who signed up once
+        assert_eq!(
+            sign_up_from("alice", "192.0.2.1:4000").status(),
+            Status::Created
+        );
+
+        // when it signs up again, then it is refused
+        let response = sign_up_from("bob", "192.0.2.1:4001");
+        assert_eq!(response.status(), Status::TooManyRequests);
+        assert_eq!(response.headers().get_one("Retry-After"), Some("60"));
+
+        // when it claims another address in a header, then it is still refused
+        let credentials = Credentials {
+            username: "bob".to_owned(),
+            password: "password".to_owned(),
+        };
+        let response = client
+            .post(uri!("/api/signup"))
+            .remote("192.0.2.1:4002".parse().unwrap())
+            .header(Header::new("X-Real-IP", "192.0.2.3"))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&credentials).unwrap())
+            .dispatch();
+        assert_eq!(response.status(), Status::TooManyRequests);
+
+        // when another client signs up, then it is not limited
+        assert_eq!(
+            sign_up_from("bob", "192.0.2.2:4000").status(),
+            Status::Created
+        );
+
+        // when the first client retries after the delay, then it is accepted
+        clock.advance(Duration::seconds(60));
+        assert_eq!(
+            sign_up_from("carol", "192.0.2.1:4000").status(),
+            Status::Created
+        );
+    }
+
+    #[test]
+    fn trusting_the_ip_header_only_if_configured() {
+        let ip_header = |figment: Figment| {
+            trust_ip_header_if_configured(figment)
+                .extract::<rocket::Config>()
+                .unwrap()
+                .ip_header
+                .map(|header| header.to_string())
+        };
+
+        // when no header is configured, then the address of the connection is used
+        assert_eq!(ip_header(Figment::from(rocket::Config::default())), None);
+
+        // when a header is configured, then it is read
+        assert_eq!(
+            ip_header(
+                Figment::from(rocket::Config::default()).merge(("ip_header", "X-Forwarded-For"))
+            ),
+            Some("X-Forwarded-For".to_owned())
+        );
+    }
+
+    #[test]
+    fn reminders_are_not_sent_again_after_a_restart() {
+        let database = Database::open_in_memory().unwrap();
+        let clock = Arc::new(FakeClock::new(Utc::now()));
+        let mut state = State::sqlite(database.clone());
+        state.clock = clock.clone();
+        let (_mbox, mailer) = mbox_mailer(&mut state);
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // given a participant reminded of an event starting in less than a day
+        let organizer = logged_in_user(&client, "organizer");
+        let event = publish_event(&client, &new_event("sunday ride"), &organizer).unwrap();
+        let rider = logged_in_user(&client, "rider");
+        set_email(&client, "rider@example.com", &rider);
+        join_event(&client, event.id, &rider).unwrap();
+        clock.advance(Duration::hours(1));
+        jobs::run(client.rocket().state::<State>().unwrap());
+        assert_eq!(mailer.emails().unwrap().len(), 1);
+
+        // when the server restarts on the same database and runs the jobs
+        drop(client);
+        let mut state = State::sqlite(database);
+        state.clock = clock.clone();
+        state.mailer = mailer.clone();
+        clock.advance(Duration::hours(1));
+        jobs::run(&state);
+
+        // then the reminder is not sent again
+        assert_eq!(mailer.emails().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn running_the_jobs_once_launched() {
+        // given an event which ended while the server was stopped
+        let mut state = State::in_memory();
+        let mut event = Event::new("sunday ride".into());
+        event.start = Some((Utc::now() - Duration::hours(3)).into());
+        event.status = EventStatus::Published;
+        state.events.insert_all(vec![event.clone()]).unwrap();
+
+        // when the server launches with scheduled jobs
+        state.jobs_interval = Some(std::time::Duration::from_secs(3600));
+        let client = Client::tracked(build(state)).expect("valid rocket instance");
+
+        // then the jobs catch up without waiting for the interval
+        let completed = (0..100).any(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            get_event(&client, event.id).unwrap().status == EventStatus::Completed
+        });
+        assert!(completed);
     }
 }
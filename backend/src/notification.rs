@@ -0,0 +1,229 @@
+use crate::{
+    mail::Email,
+    repository::{QueuedEmail, Recipient},
+    State,
+};
+use common::{Event, Id};
+
+/// Appended to every email.
+const FOOTER: &str = include_str!("../templates/mail/footer.txt");
+
+/// Change of an event which users are told about in their inbox, and by email for the
+/// participants.
+pub enum Notification<'a> {
+    /// The organizer changed the event.
+    Changed(&'a Event),
+    Cancelled(&'a Event),
+    /// The recipient moved from the waitlist to the participants.
+    Promoted(&'a Event),
+    /// A user joined the event or its waitlist, told to the organizer.
+    Joined(&'a Event, &'a str),
+    /// The event starts in less than a day, told to the participants.
+    Reminder(&'a Event),
+}
+
+impl Notification<'_> {
+    /// Subject line followed by a blank line and by the body, `None` if the notification is only
+    /// shown in the inbox.
+    fn template(&self) -> Option<&'static str> {
+        match self {
+            Notification::Changed(_) => Some(include_str!("../templates/mail/changed.txt")),
+            Notification::Cancelled(_) => Some(include_str!("../templates/mail/cancelled.txt")),
+            Notification::Promoted(_) => Some(include_str!("../templates/mail/promoted.txt")),
+            Notification::Reminder(_) => Some(include_str!("../templates/mail/reminder.txt")),
+            Notification::Joined(..) => None,
+        }
+    }
+
+    fn event(&self) -> &Event {
+        match self {
+            Notification::Changed(event)
+            | Notification::Cancelled(event)
+            | Notification::Promoted(event)
+            | Notification::Joined(event, _)
+            | Notification::Reminder(event) => event,
+        }
+    }
+
+    /// One line summary shown in the inbox.
+    pub fn text(&self) -> String {
+        let event = self.event();
+        match self {
+            Notification::Changed(_) => format!(
+                "{} changed, start: {}, meeting point: {}",
+                event.name,
+                start(event),
+                meeting_point(event)
+            ),
+            Notification::Cancelled(_) => format!(
+                "{} is cancelled: {}",
+                event.name,
+                event.cancellation_reason.clone().unwrap_or_default()
+            ),
+            Notification::Promoted(_) => {
+                format!("a spot freed up, you are going to {}", event.name)
+            }
+            Notification::Joined(_, username) => match event.participation(username) {
+                Some(common::Participation::Waitlisted { .. }) => {
+                    format!("{} joined the waitlist of {}", username, event.name)
+                }
+                _ => format!("{} joined {}", username, event.name),
+            },
+            Notification::Reminder(_) => format!(
+                "{} starts {}, meeting point: {}",
+                event.name,
+                start(event),
+                meeting_point(event)
+            ),
+        }
+    }
+
+    /// Email to `recipient`, the links pointing to the server at `public_url`. `None` if the
+    /// notification is not emailed.
+    pub fn render(&self, recipient: &Recipient, public_url: &str) -> Option<Email> {
+        let template = self.template()?;
+        let event = self.event();
+        let values = [
+            ("username", recipient.username.clone()),
+            // the name is part of the subject, which is a single line
+            ("event", event.name.replace(['\r', '\n'], " ")),
+            ("start", start(event)),
+            ("meeting_point", meeting_point(event)),
+            (
+                "reason",
+                event.cancellation_reason.clone().unwrap_or_default(),
+            ),
+            ("event_url", format!("{}/event/{}", public_url, event.id)),
+            (
+                "unsubscribe_url",
+                format!(
+                    "{}/api/unsubscribe/{}",
+                    public_url, recipient.unsubscribe_token
+                ),
+            ),
+        ];
+        let text = fill(&format!("{}{}", template, FOOTER), &values);
+        let (subject, body) = text.split_once("\n\n").unwrap_or_default();
+        Some(Email {
+            to: recipient.email.clone(),
+            subject: subject.trim_start_matches("Subject:").trim().to_owned(),
+            body: body.to_owned(),
+        })
+    }
+}
+
+/// Start of the event in the timezone of the organizer.
+fn start(event: &Event) -> String {
+    event
+        .start
+        .map(|start| start.format("%A %-d %B %Y, %H:%M").to_string())
+        .unwrap_or_else(|| "to be announced".to_owned())
+}
+
+fn meeting_point(event: &Event) -> String {
+    event
+        .meeting_point
+        .as_ref()
+        .map(|meeting_point| meeting_point.address.clone())
+        .unwrap_or_else(|| "to be announced".to_owned())
+}
+
+/// Replace the `{{name}}` placeholders of `template` with their value, the values are not
+/// searched for placeholders.
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find("}}").and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &rest[2..end])
+                .map(|(_, value)| (value, end + 2))
+        });
+        match value {
+            Some((value, length)) => {
+                text.push_str(value);
+                rest = &rest[length..];
+            }
+            None => {
+                text.push_str("{{");
+                rest = &rest[2..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Participants and waitlisted users of `event`, except `author` who made the change.
+pub fn joined_users<'a>(event: &'a Event, author: &'a str) -> impl Iterator<Item = &'a String> {
+    event
+        .participants
+        .iter()
+        .chain(event.waitlist.iter())
+        .filter(move |username| username.as_str() != author)
+}
+
+/// Add `notification` to the inbox of `usernames`, and queue an email for those who gave an
+/// address and did not unsubscribe. Failures are only logged, the change of the event is done
+/// already.
+pub fn notify<'a>(
+    state: &State,
+    usernames: impl IntoIterator<Item = &'a String>,
+    notification: Notification,
+) {
+    for username in usernames {
+        if let Err(error) = notify_user(state, username, &notification) {
+            error!("cannot notify {}: {}", username, error);
+        }
+    }
+}
+
+fn notify_user(state: &State, username: &str, notification: &Notification) -> Result<(), String> {
+    state
+        .notifications
+        .insert(
+            username,
+            common::Notification {
+                id: Id::new_v4(),
+                text: notification.text(),
+                event_id: Some(notification.event().id),
+                created_at: state.clock.now(),
+                read: false,
+            },
+        )
+        .map_err(|error| error.to_string())?;
+    match state.recipients.get(username) {
+        Ok(Some(recipient)) if !recipient.unsubscribed => {
+            match notification.render(&recipient, &state.public_url) {
+                Some(email) => state.outbox.push(email).map_err(|error| error.to_string()),
+                None => Ok(()),
+            }
+        }
+        Ok(_) => Ok(()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Send the emails of the outbox, oldest first. The emails which cannot be sent stay in the
+/// outbox to be sent on the next run.
+pub fn send_queued_emails(state: &State) {
+    let queued = match state.outbox.queued() {
+        Ok(queued) => queued,
+        Err(error) => {
+            error!("cannot read the outbox: {}", error);
+            return;
+        }
+    };
+    for QueuedEmail { id, email } in queued {
+        if let Err(error) = state.mailer.send(&email) {
+            error!("cannot email {}: {}", email.to, error);
+            continue;
+        }
+        if let Err(error) = state.outbox.remove(id) {
+            error!("cannot remove email {} from the outbox: {}", id, error);
+        }
+    }
+}
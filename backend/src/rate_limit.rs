@@ -0,0 +1,168 @@
+use crate::{accounts, failure, State};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use common::{ApiError, ErrorCode};
+use rocket::{request::FromRequest, serde::Deserialize, Request};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+/// Limit of the routes without their own, unless the `rate_limits.default` setting says otherwise.
+pub const DEFAULT_LIMIT: Limit = Limit {
+    burst: 30,
+    per_minute: 30,
+};
+
+/// Number of buckets above which the full ones are forgotten, they are the same as new ones.
+const MAX_BUCKETS: usize = 10_000;
+
+/// Token bucket: a client can send `burst` requests at once, then `per_minute` requests a minute.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "rocket::serde")]
+pub struct Limit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl Limit {
+    /// Tokens added to a bucket every second.
+    fn rate(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: DateTime<Utc>,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: Limit, now: DateTime<Utc>) {
+        let elapsed = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+        self.tokens = (self.tokens + elapsed * limit.rate()).min(limit.burst as f64);
+        self.updated_at = now;
+    }
+}
+
+/// Buckets of the clients of the rate-limited routes.
+pub struct RateLimiter {
+    default: Limit,
+    /// Limits of the routes having their own, by name of their handler.
+    routes: HashMap<String, Limit>,
+    /// Bucket of each route and client.
+    buckets: Mutex<HashMap<(String, String), Bucket>>,
+}
+
+impl RateLimiter {
+    /// Limiter applying `limits` to the routes named after their handler, and the one named
+    /// `default` to the other routes.
+    pub fn new(mut limits: HashMap<String, Limit>) -> RateLimiter {
+        RateLimiter {
+            default: limits.remove("default").unwrap_or(DEFAULT_LIMIT),
+            routes: limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limit(&self, route: &str) -> Limit {
+        self.routes.get(route).copied().unwrap_or(self.default)
+    }
+
+    /// Take a token from the bucket of `client` for `route`, or fail with the number of seconds
+    /// until the next token.
+    pub fn acquire(&self, route: &str, client: &str, now: DateTime<Utc>) -> Result<(), u64> {
+        let limit = self.limit(route);
+        // the buckets are left consistent by every update, a panic cannot corrupt them
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|(route, _), bucket| {
+                let limit = self.limit(route);
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst as f64
+            });
+        }
+        let bucket = buckets
+            .entry((route.to_owned(), client.to_owned()))
+            .or_insert(Bucket {
+                tokens: limit.burst as f64,
+                updated_at: now,
+            });
+        bucket.refill(limit, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if limit.per_minute == 0 {
+            // the bucket is never refilled, the client should not retry soon
+            Err(60)
+        } else {
+            Err(((1.0 - bucket.tokens) / limit.rate()).ceil() as u64)
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+/// Request guard counting the request against the limit of its route, the requests of a user
+/// being counted together and those without authentication by IP address. Fails with a 429 once
+/// the limit is exceeded.
+///
+/// The IP address is the one of the connection unless the `ip_header` setting names the header
+/// in which a reverse proxy gives it, see `trust_ip_header_if_configured`.
+pub struct RateLimit;
+
+/// Seconds after which the request rejected by `RateLimit` can be sent again.
+struct RetryAfter(Option<u64>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RateLimit {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
+        let state = match request.rocket().state::<State>() {
+            Some(state) => state,
+            None => {
+                return failure::reject(
+                    request,
+                    ApiError::new(ErrorCode::Internal, "missing server state"),
+                )
+            }
+        };
+        let route = request
+            .route()
+            .and_then(|route| route.name.as_deref())
+            .unwrap_or_default();
+        let client = match accounts::authenticate(request, state) {
+            Ok(user) => format!("user {}", user.name),
+            Err(_) => match request.client_ip() {
+                Some(ip) => format!("ip {}", ip),
+                None => "unknown".to_owned(),
+            },
+        };
+        match state
+            .rate_limiter
+            .acquire(route, &client, state.clock.now())
+        {
+            Ok(()) => rocket::outcome::Outcome::Success(RateLimit),
+            Err(retry_after) => {
+                request.local_cache(|| RetryAfter(Some(retry_after)));
+                failure::reject(
+                    request,
+                    ApiError::new(
+                        ErrorCode::TooManyRequests,
+                        format!("too many requests, retry in {} seconds", retry_after),
+                    ),
+                )
+            }
+        }
+    }
+}
+
+/// Seconds after which the request can be sent again if `RateLimit` rejected it.
+pub fn retry_after(request: &Request<'_>) -> Option<u64> {
+    request.local_cache(|| RetryAfter(None)).0
+}
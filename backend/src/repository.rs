@@ -0,0 +1,190 @@
+use crate::mail::Email;
+use chrono::{DateTime, Utc};
+use common::{Comment, Event, EventQuery, Id, Notification, Page, Route};
+use std::fmt::Display;
+
+#[cfg(test)]
+pub mod memory;
+pub mod sqlite;
+
+#[cfg(test)]
+pub use memory::{
+    InMemoryCommentRepository, InMemoryEventRepository, InMemoryFeedTokenRepository,
+    InMemoryNotificationRepository, InMemoryOutboxRepository, InMemoryRecipientRepository,
+    InMemoryReminderRepository, InMemoryRouteRepository, InMemoryTokenRepository,
+    InMemoryUserRepository,
+};
+pub use sqlite::{
+    Database, SqliteCommentRepository, SqliteEventRepository, SqliteFeedTokenRepository,
+    SqliteNotificationRepository, SqliteOutboxRepository, SqliteRecipientRepository,
+    SqliteReminderRepository, SqliteRouteRepository, SqliteTokenRepository, SqliteUserRepository,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    NotFound,
+    AlreadyExists,
+    Storage(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "not found"),
+            Error::AlreadyExists => write!(f, "already exists"),
+            Error::Storage(error) => write!(f, "storage error: {}", error),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Storage of the published events.
+///
+/// Modifications of an existing event go through `update` so that the read-modify-write cycle is
+/// atomic for every implementation.
+pub trait EventRepository: Send + Sync {
+    fn all(&self) -> Result<Vec<Event>>;
+    /// Page of the events matching `query` that `viewer` can see, the drafts being left out
+    /// unless their organizer requests them with the status filter. `now` tells upcoming events
+    /// from past ones. Fails with `Error::NotFound` if the cursor is not the ID of an event.
+    ///
+    /// Unless sorted otherwise, the events are in publication order, those published before the
+    /// publication date was recorded coming first. The cursor is the ID of the last event of the
+    /// previous page, so that pages do not shift when events are published in the meantime.
+    fn page(
+        &self,
+        query: &EventQuery,
+        viewer: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Page<Event>>;
+    fn get(&self, id: Id) -> Result<Option<Event>>;
+    /// Insert all of `events` or, if any of them cannot be inserted, none of them.
+    fn insert_all(&self, events: Vec<Event>) -> Result<()>;
+    /// Apply `change` to the event identified by `id`, store it and return the updated event.
+    /// `change` must not modify the ID of the event.
+    fn update(&self, id: Id, change: &mut dyn FnMut(&mut Event)) -> Result<Event>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub username: String,
+    /// Argon2 hash of the password in the PHC string format.
+    pub password_hash: String,
+}
+
+/// Storage of the user accounts.
+pub trait UserRepository: Send + Sync {
+    /// Fails with `Error::AlreadyExists` if the username is already taken.
+    fn insert(&self, account: Account) -> Result<()>;
+    fn get(&self, username: &str) -> Result<Option<Account>>;
+}
+
+/// Bearer token issued to a user when logging in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub value: String,
+    pub username: String,
+    pub expires_at: DateTime<Utc>,
+    /// Revoked tokens are kept so that the server can tell them apart from unknown tokens.
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Storage of the issued bearer tokens.
+pub trait TokenRepository: Send + Sync {
+    fn insert(&self, token: Token) -> Result<()>;
+    fn get(&self, value: &str) -> Result<Option<Token>>;
+    /// Fails with `Error::NotFound` if the token was never issued.
+    fn revoke(&self, value: &str, at: DateTime<Utc>) -> Result<()>;
+}
+
+/// Storage of the routes uploaded for the events, the summaries are stored with the events.
+pub trait RouteRepository: Send + Sync {
+    fn get(&self, event_id: Id) -> Result<Option<Route>>;
+    /// Replace the route of the event, if any.
+    fn set(&self, event_id: Id, route: Route) -> Result<()>;
+}
+
+/// Storage of the secret tokens of the personal calendar feeds, at most one per user.
+pub trait FeedTokenRepository: Send + Sync {
+    fn get(&self, username: &str) -> Result<Option<String>>;
+    /// Replace the token of the user, the feed of the previous token stops working.
+    fn set(&self, username: &str, token: String) -> Result<()>;
+    /// User owning the feed of `token`.
+    fn username(&self, token: &str) -> Result<Option<String>>;
+}
+
+/// Storage of the comments posted under the events.
+pub trait CommentRepository: Send + Sync {
+    /// Comments of the event, in the order they were posted.
+    fn of_event(&self, event_id: Id) -> Result<Vec<Comment>>;
+    fn get(&self, id: Id) -> Result<Option<Comment>>;
+    fn insert(&self, comment: Comment) -> Result<()>;
+    /// Replace the stored comment having the ID of `comment`, fails with `Error::NotFound` if
+    /// there is none.
+    fn update(&self, comment: Comment) -> Result<()>;
+    /// Fails with `Error::NotFound` if there is no such comment.
+    fn delete(&self, id: Id) -> Result<()>;
+}
+
+/// Email address at which a user is notified of the changes of the events they joined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipient {
+    pub username: String,
+    pub email: String,
+    /// Authenticates the unsubscribe link of the emails, which is followed without logging in.
+    pub unsubscribe_token: String,
+    pub unsubscribed: bool,
+}
+
+/// Storage of the email addresses of the users.
+pub trait RecipientRepository: Send + Sync {
+    fn get(&self, username: &str) -> Result<Option<Recipient>>;
+    /// Store the recipient, replacing the previous one of the same user.
+    fn set(&self, recipient: Recipient) -> Result<()>;
+    /// Recipient whose unsubscribe link carries `token`.
+    fn by_unsubscribe_token(&self, token: &str) -> Result<Option<Recipient>>;
+}
+
+/// Storage of the notification inboxes of the users.
+pub trait NotificationRepository: Send + Sync {
+    /// Notifications of the user, newest first.
+    fn of_user(&self, username: &str) -> Result<Vec<Notification>>;
+    fn insert(&self, username: &str, notification: Notification) -> Result<()>;
+    /// Fails with `Error::NotFound` if the user has no such notification.
+    fn mark_read(&self, username: &str, id: Id) -> Result<()>;
+    fn mark_all_read(&self, username: &str) -> Result<()>;
+}
+
+/// Reminder of an event sent to one of its participants before it starts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reminder {
+    pub event_id: Id,
+    pub username: String,
+    /// Start of the event when the reminder was sent, a postponed event is reminded again.
+    pub start: DateTime<Utc>,
+}
+
+/// Storage of the reminders sent, so that none is sent twice.
+pub trait ReminderRepository: Send + Sync {
+    /// Record `reminder` before sending it, fails with `Error::AlreadyExists` if it was recorded
+    /// already.
+    fn insert(&self, reminder: Reminder) -> Result<()>;
+}
+
+/// Email waiting in the outbox.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedEmail {
+    pub id: Id,
+    pub email: Email,
+}
+
+/// Storage of the emails to send. The requests queue them and the scheduler sends them, so that
+/// the requests do not wait for the mail server.
+pub trait OutboxRepository: Send + Sync {
+    fn push(&self, email: Email) -> Result<()>;
+    /// Emails waiting to be sent, oldest first.
+    fn queued(&self) -> Result<Vec<QueuedEmail>>;
+    /// Remove an email once it is sent.
+    fn remove(&self, id: Id) -> Result<()>;
+}
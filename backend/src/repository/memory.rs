@@ -0,0 +1,513 @@
+use super::{
+    Account, CommentRepository, Error, EventRepository, FeedTokenRepository,
+    NotificationRepository, OutboxRepository, QueuedEmail, Recipient, RecipientRepository,
+    Reminder, ReminderRepository, Result, RouteRepository, Token, TokenRepository, UserRepository,
+};
+use crate::mail::Email;
+use crate::search::DEFAULT_PAGE_SIZE;
+use chrono::{DateTime, Utc};
+use common::{
+    Comment, Event, EventQuery, EventSort, EventStatus, EventTime, Id, Notification, Page, Route,
+};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    sync::{Mutex, MutexGuard},
+};
+
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>> {
+    mutex
+        .lock()
+        .map_err(|error| Error::Storage(error.to_string()))
+}
+
+/// Keeps the events in memory, everything is lost when the server stops.
+pub struct InMemoryEventRepository {
+    events: Mutex<Vec<Event>>,
+}
+
+impl InMemoryEventRepository {
+    pub fn new() -> InMemoryEventRepository {
+        InMemoryEventRepository {
+            events: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl Default for InMemoryEventRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventRepository for InMemoryEventRepository {
+    fn all(&self) -> Result<Vec<Event>> {
+        Ok(lock(&self.events)?.clone())
+    }
+
+    fn page(
+        &self,
+        query: &EventQuery,
+        viewer: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Page<Event>> {
+        let events = lock(&self.events)?;
+        // the events are paired with their position, which breaks the ties of the sort
+        let cursor = match &query.cursor {
+            Some(cursor) => Some(
+                events
+                    .iter()
+                    .enumerate()
+                    .find(|(_, event)| event.id.to_string() == *cursor)
+                    .ok_or(Error::NotFound)?,
+            ),
+            None => None,
+        };
+        let mut selected: Vec<(usize, &Event)> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| is_listed(event, query, viewer) && matches(event, query, now))
+            .filter(|event| {
+                cursor.is_none_or(|cursor| compare(query, *event, cursor) == Ordering::Greater)
+            })
+            .collect();
+        selected.sort_by(|a, b| compare(query, *a, *b));
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let next_cursor = if selected.len() > limit {
+            Some(selected[limit - 1].1.id.to_string())
+        } else {
+            None
+        };
+        Ok(Page {
+            items: selected
+                .into_iter()
+                .take(limit)
+                .map(|(_, event)| event.clone())
+                .collect(),
+            next_cursor,
+        })
+    }
+
+    fn get(&self, id: Id) -> Result<Option<Event>> {
+        Ok(lock(&self.events)?
+            .iter()
+            .find(|event| event.id == id)
+            .cloned())
+    }
+
+    fn insert_all(&self, new_events: Vec<Event>) -> Result<()> {
+        let mut events = lock(&self.events)?;
+        for (index, event) in new_events.iter().enumerate() {
+            if events
+                .iter()
+                .chain(&new_events[..index])
+                .any(|existing| existing.id == event.id)
+            {
+                return Err(Error::AlreadyExists);
+            }
+        }
+        events.extend(new_events);
+        Ok(())
+    }
+
+    fn update(&self, id: Id, change: &mut dyn FnMut(&mut Event)) -> Result<Event> {
+        let mut events = lock(&self.events)?;
+        let event = events
+            .iter_mut()
+            .find(|event| event.id == id)
+            .ok_or(Error::NotFound)?;
+        change(event);
+        Ok(event.clone())
+    }
+}
+
+/// Whether `viewer` can see `event` in the lists, the drafts being left out unless requested with
+/// the status filter by their organizer.
+fn is_listed(event: &Event, query: &EventQuery, viewer: Option<&str>) -> bool {
+    event.status != EventStatus::Draft
+        || (query.status.is_some() && viewer.is_some() && event.organizer.as_deref() == viewer)
+}
+
+fn matches(event: &Event, query: &EventQuery, now: DateTime<Utc>) -> bool {
+    query.status.is_none_or(|status| event.status == status)
+        && query
+            .organizer
+            .as_ref()
+            .is_none_or(|organizer| event.organizer.as_ref() == Some(organizer))
+        && query
+            .participant
+            .as_ref()
+            .is_none_or(|participant| event.participants.contains(participant))
+        && query
+            .text
+            .as_ref()
+            .is_none_or(|text| event.name.to_lowercase().contains(&text.to_lowercase()))
+        && query.when.is_none_or(|when| match (when, event.start) {
+            (EventTime::Upcoming, Some(start)) => start > now,
+            (EventTime::Past, Some(start)) => start <= now,
+            // without a start, an event is neither upcoming nor past
+            (_, None) => false,
+        })
+        && query.near.is_none_or(|near| {
+            event
+                .meeting_point
+                .as_ref()
+                .is_some_and(|meeting_point| near.contains(&meeting_point.coordinates()))
+        })
+        && query
+            .discipline
+            .is_none_or(|discipline| event.discipline == Some(discipline))
+        // the events without a pace or a difficulty may be too hard, they are left out
+        && query
+            .max_pace
+            .is_none_or(|max_pace| event.pace.is_some_and(|pace| pace <= max_pace))
+        && query.max_difficulty.is_none_or(|max_difficulty| {
+            event
+                .difficulty
+                .is_some_and(|difficulty| difficulty <= max_difficulty)
+        })
+        && query.no_drop.is_none_or(|no_drop| event.no_drop == no_drop)
+        && query.series.is_none_or(|id| {
+            event
+                .series
+                .as_ref()
+                .is_some_and(|series| series.id == id)
+        })
+}
+
+/// Distance from the center of the searched area to the meeting point of `event`, the events
+/// without a meeting point being farther than any other.
+fn distance_km(event: &Event, query: &EventQuery) -> f64 {
+    match (&query.near, &event.meeting_point) {
+        (Some(near), Some(meeting_point)) => near.center.distance_km(&meeting_point.coordinates()),
+        _ => f64::INFINITY,
+    }
+}
+
+/// Order of the events `a` and `b` in the results of `query`, each event coming with its position
+/// in the repository.
+fn compare(
+    query: &EventQuery,
+    (a_index, a): (usize, &Event),
+    (b_index, b): (usize, &Event),
+) -> Ordering {
+    let publication = (a.published_at, a_index).cmp(&(b.published_at, b_index));
+    let ordering = match query.sort {
+        EventSort::Oldest | EventSort::Newest => Ordering::Equal,
+        EventSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        EventSort::Start => (a.start.is_none(), a.start).cmp(&(b.start.is_none(), b.start)),
+        EventSort::Distance => distance_km(a, query).total_cmp(&distance_km(b, query)),
+    }
+    .then(publication);
+    if query.sort == EventSort::Newest {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Keeps the user accounts in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    accounts: Mutex<HashMap<String, Account>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> InMemoryUserRepository {
+        InMemoryUserRepository::default()
+    }
+}
+
+impl UserRepository for InMemoryUserRepository {
+    fn insert(&self, account: Account) -> Result<()> {
+        let mut accounts = lock(&self.accounts)?;
+        if accounts.contains_key(&account.username) {
+            return Err(Error::AlreadyExists);
+        }
+        accounts.insert(account.username.clone(), account);
+        Ok(())
+    }
+
+    fn get(&self, username: &str) -> Result<Option<Account>> {
+        Ok(lock(&self.accounts)?.get(username).cloned())
+    }
+}
+
+/// Keeps the issued tokens in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryTokenRepository {
+    tokens: Mutex<HashMap<String, Token>>,
+}
+
+impl InMemoryTokenRepository {
+    pub fn new() -> InMemoryTokenRepository {
+        InMemoryTokenRepository::default()
+    }
+}
+
+impl TokenRepository for InMemoryTokenRepository {
+    fn insert(&self, token: Token) -> Result<()> {
+        let mut tokens = lock(&self.tokens)?;
+        if tokens.contains_key(&token.value) {
+            return Err(Error::AlreadyExists);
+        }
+        tokens.insert(token.value.clone(), token);
+        Ok(())
+    }
+
+    fn get(&self, value: &str) -> Result<Option<Token>> {
+        Ok(lock(&self.tokens)?.get(value).cloned())
+    }
+
+    fn revoke(&self, value: &str, at: DateTime<Utc>) -> Result<()> {
+        let mut tokens = lock(&self.tokens)?;
+        let token = tokens.get_mut(value).ok_or(Error::NotFound)?;
+        token.revoked_at = Some(at);
+        Ok(())
+    }
+}
+
+/// Keeps the routes in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryRouteRepository {
+    routes: Mutex<HashMap<Id, Route>>,
+}
+
+impl InMemoryRouteRepository {
+    pub fn new() -> InMemoryRouteRepository {
+        InMemoryRouteRepository::default()
+    }
+}
+
+impl RouteRepository for InMemoryRouteRepository {
+    fn get(&self, event_id: Id) -> Result<Option<Route>> {
+        Ok(lock(&self.routes)?.get(&event_id).cloned())
+    }
+
+    fn set(&self, event_id: Id, route: Route) -> Result<()> {
+        lock(&self.routes)?.insert(event_id, route);
+        Ok(())
+    }
+}
+
+/// Keeps the calendar feed tokens in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryFeedTokenRepository {
+    /// Token of each user.
+    tokens: Mutex<HashMap<String, String>>,
+}
+
+impl InMemoryFeedTokenRepository {
+    pub fn new() -> InMemoryFeedTokenRepository {
+        InMemoryFeedTokenRepository::default()
+    }
+}
+
+impl FeedTokenRepository for InMemoryFeedTokenRepository {
+    fn get(&self, username: &str) -> Result<Option<String>> {
+        Ok(lock(&self.tokens)?.get(username).cloned())
+    }
+
+    fn set(&self, username: &str, token: String) -> Result<()> {
+        lock(&self.tokens)?.insert(username.to_owned(), token);
+        Ok(())
+    }
+
+    fn username(&self, token: &str) -> Result<Option<String>> {
+        Ok(lock(&self.tokens)?
+            .iter()
+            .find(|(_, value)| value.as_str() == token)
+            .map(|(username, _)| username.clone()))
+    }
+}
+
+/// Keeps the comments in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryCommentRepository {
+    /// Comments of all the events, in the order they were posted.
+    comments: Mutex<Vec<Comment>>,
+}
+
+impl InMemoryCommentRepository {
+    pub fn new() -> InMemoryCommentRepository {
+        InMemoryCommentRepository::default()
+    }
+}
+
+impl CommentRepository for InMemoryCommentRepository {
+    fn of_event(&self, event_id: Id) -> Result<Vec<Comment>> {
+        Ok(lock(&self.comments)?
+            .iter()
+            .filter(|comment| comment.event_id == event_id)
+            .cloned()
+            .collect())
+    }
+
+    fn get(&self, id: Id) -> Result<Option<Comment>> {
+        Ok(lock(&self.comments)?
+            .iter()
+            .find(|comment| comment.id == id)
+            .cloned())
+    }
+
+    fn insert(&self, comment: Comment) -> Result<()> {
+        let mut comments = lock(&self.comments)?;
+        if comments.iter().any(|other| other.id == comment.id) {
+            return Err(Error::AlreadyExists);
+        }
+        comments.push(comment);
+        Ok(())
+    }
+
+    fn update(&self, comment: Comment) -> Result<()> {
+        let mut comments = lock(&self.comments)?;
+        let stored = comments
+            .iter_mut()
+            .find(|other| other.id == comment.id)
+            .ok_or(Error::NotFound)?;
+        *stored = comment;
+        Ok(())
+    }
+
+    fn delete(&self, id: Id) -> Result<()> {
+        let mut comments = lock(&self.comments)?;
+        let index = comments
+            .iter()
+            .position(|comment| comment.id == id)
+            .ok_or(Error::NotFound)?;
+        comments.remove(index);
+        Ok(())
+    }
+}
+
+/// Keeps the email addresses in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryRecipientRepository {
+    /// Recipient of each user.
+    recipients: Mutex<HashMap<String, Recipient>>,
+}
+
+impl InMemoryRecipientRepository {
+    pub fn new() -> InMemoryRecipientRepository {
+        InMemoryRecipientRepository::default()
+    }
+}
+
+impl RecipientRepository for InMemoryRecipientRepository {
+    fn get(&self, username: &str) -> Result<Option<Recipient>> {
+        Ok(lock(&self.recipients)?.get(username).cloned())
+    }
+
+    fn set(&self, recipient: Recipient) -> Result<()> {
+        lock(&self.recipients)?.insert(recipient.username.clone(), recipient);
+        Ok(())
+    }
+
+    fn by_unsubscribe_token(&self, token: &str) -> Result<Option<Recipient>> {
+        Ok(lock(&self.recipients)?
+            .values()
+            .find(|recipient| recipient.unsubscribe_token == token)
+            .cloned())
+    }
+}
+
+/// Keeps the notifications in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryNotificationRepository {
+    /// Notifications of all the users with their recipient, oldest first.
+    notifications: Mutex<Vec<(String, Notification)>>,
+}
+
+impl InMemoryNotificationRepository {
+    pub fn new() -> InMemoryNotificationRepository {
+        InMemoryNotificationRepository::default()
+    }
+}
+
+impl NotificationRepository for InMemoryNotificationRepository {
+    fn of_user(&self, username: &str) -> Result<Vec<Notification>> {
+        Ok(lock(&self.notifications)?
+            .iter()
+            .rev()
+            .filter(|(recipient, _)| recipient == username)
+            .map(|(_, notification)| notification.clone())
+            .collect())
+    }
+
+    fn insert(&self, username: &str, notification: Notification) -> Result<()> {
+        lock(&self.notifications)?.push((username.to_owned(), notification));
+        Ok(())
+    }
+
+    fn mark_read(&self, username: &str, id: Id) -> Result<()> {
+        let mut notifications = lock(&self.notifications)?;
+        let (_, notification) = notifications
+            .iter_mut()
+            .find(|(recipient, notification)| recipient == username && notification.id == id)
+            .ok_or(Error::NotFound)?;
+        notification.read = true;
+        Ok(())
+    }
+
+    fn mark_all_read(&self, username: &str) -> Result<()> {
+        for (recipient, notification) in lock(&self.notifications)?.iter_mut() {
+            if recipient == username {
+                notification.read = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the reminders sent in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryReminderRepository {
+    reminders: Mutex<HashSet<Reminder>>,
+}
+
+impl InMemoryReminderRepository {
+    pub fn new() -> InMemoryReminderRepository {
+        InMemoryReminderRepository::default()
+    }
+}
+
+impl ReminderRepository for InMemoryReminderRepository {
+    fn insert(&self, reminder: Reminder) -> Result<()> {
+        if lock(&self.reminders)?.insert(reminder) {
+            Ok(())
+        } else {
+            Err(Error::AlreadyExists)
+        }
+    }
+}
+
+/// Keeps the emails to send in memory, everything is lost when the server stops.
+#[derive(Default)]
+pub struct InMemoryOutboxRepository {
+    emails: Mutex<Vec<QueuedEmail>>,
+}
+
+impl InMemoryOutboxRepository {
+    pub fn new() -> InMemoryOutboxRepository {
+        InMemoryOutboxRepository::default()
+    }
+}
+
+impl OutboxRepository for InMemoryOutboxRepository {
+    fn push(&self, email: Email) -> Result<()> {
+        lock(&self.emails)?.push(QueuedEmail {
+            id: Id::new_v4(),
+            email,
+        });
+        Ok(())
+    }
+
+    fn queued(&self) -> Result<Vec<QueuedEmail>> {
+        Ok(lock(&self.emails)?.clone())
+    }
+
+    fn remove(&self, id: Id) -> Result<()> {
+        lock(&self.emails)?.retain(|queued| queued.id != id);
+        Ok(())
+    }
+}
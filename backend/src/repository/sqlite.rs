@@ -0,0 +1,1343 @@
+use super::{
+    Account, CommentRepository, Error, EventRepository, FeedTokenRepository,
+    NotificationRepository, OutboxRepository, QueuedEmail, Recipient, RecipientRepository,
+    Reminder, ReminderRepository, Result, RouteRepository, Token, TokenRepository, UserRepository,
+};
+use crate::mail::Email;
+use crate::search::DEFAULT_PAGE_SIZE;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use common::{
+    Comment, Coordinates, Difficulty, Discipline, Event, EventQuery, EventSort, EventStatus,
+    EventTime, Id, MeetingPoint, Notification, Pace, Page, Route, Tz,
+};
+use rusqlite::{
+    functions::FunctionFlags,
+    params,
+    types::{ToSql, Value},
+    vtab::array::Array,
+    Connection, OptionalExtension, Row, Transaction,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::Path,
+    rc::Rc,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Schema migrations, the n-th migration brings the database from `user_version` n to n + 1.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE events (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE participants (
+        event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+        username TEXT NOT NULL,
+        PRIMARY KEY (event_id, username)
+    );
+",
+    "
+    CREATE TABLE users (
+        username TEXT PRIMARY KEY NOT NULL,
+        password_hash TEXT NOT NULL
+    );
+    CREATE TABLE tokens (
+        value TEXT PRIMARY KEY NOT NULL,
+        username TEXT NOT NULL REFERENCES users(username) ON DELETE CASCADE
+    );
+",
+    "
+    ALTER TABLE tokens ADD COLUMN expires_at INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE tokens ADD COLUMN revoked_at INTEGER;
+",
+    "
+    ALTER TABLE events ADD COLUMN organizer TEXT;
+",
+    "
+    ALTER TABLE events ADD COLUMN status TEXT NOT NULL DEFAULT 'published';
+    ALTER TABLE events ADD COLUMN cancellation_reason TEXT;
+",
+    "
+    ALTER TABLE events ADD COLUMN starts_at TEXT;
+    ALTER TABLE events ADD COLUMN ends_at TEXT;
+",
+    "
+    ALTER TABLE events ADD COLUMN meeting_address TEXT;
+    ALTER TABLE events ADD COLUMN meeting_latitude REAL;
+    ALTER TABLE events ADD COLUMN meeting_longitude REAL;
+",
+    "
+    ALTER TABLE events ADD COLUMN route_summary TEXT;
+    CREATE TABLE routes (
+        event_id TEXT PRIMARY KEY NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+        segments TEXT NOT NULL
+    );
+",
+    "
+    ALTER TABLE events ADD COLUMN capacity INTEGER;
+    CREATE TABLE waitlist (
+        event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+        username TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (event_id, username)
+    );
+",
+    "
+    ALTER TABLE events ADD COLUMN discipline TEXT;
+    ALTER TABLE events ADD COLUMN pace TEXT;
+    ALTER TABLE events ADD COLUMN difficulty TEXT;
+    ALTER TABLE events ADD COLUMN no_drop INTEGER NOT NULL DEFAULT 0;
+",
+    "
+    ALTER TABLE events ADD COLUMN series TEXT;
+",
+    "
+    CREATE TABLE feed_tokens (
+        username TEXT PRIMARY KEY NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+        token TEXT UNIQUE NOT NULL
+    );
+",
+    "
+    ALTER TABLE events ADD COLUMN published_at TEXT;
+",
+    "
+    CREATE TABLE comments (
+        id TEXT PRIMARY KEY NOT NULL,
+        event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+        author TEXT NOT NULL,
+        text TEXT NOT NULL,
+        posted_at TEXT NOT NULL,
+        edited_at TEXT
+    );
+    CREATE INDEX comments_event_id ON comments (event_id);
+",
+    "
+    CREATE TABLE recipients (
+        username TEXT PRIMARY KEY NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+        email TEXT NOT NULL,
+        unsubscribe_token TEXT UNIQUE NOT NULL,
+        unsubscribed INTEGER NOT NULL DEFAULT 0
+    );
+",
+    "
+    CREATE TABLE notifications (
+        id TEXT PRIMARY KEY NOT NULL,
+        username TEXT NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+        text TEXT NOT NULL,
+        event_id TEXT,
+        created_at TEXT NOT NULL,
+        read INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX notifications_username ON notifications (username);
+",
+    "
+    CREATE TABLE reminders (
+        event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+        username TEXT NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+        starts_at TEXT NOT NULL,
+        PRIMARY KEY (event_id, username, starts_at)
+    );
+",
+    "
+    ALTER TABLE events ADD COLUMN revision INTEGER NOT NULL DEFAULT 0;
+",
+    "
+    ALTER TABLE events ADD COLUMN timezone TEXT;
+",
+    "
+    CREATE TABLE outbox (
+        id TEXT PRIMARY KEY NOT NULL,
+        recipient TEXT NOT NULL,
+        subject TEXT NOT NULL,
+        body TEXT NOT NULL
+    );
+",
+];
+
+/// Migration giving the tokens an expiry, those issued before it expire after the configured
+/// lifetime from the upgrade on.
+const TOKEN_EXPIRY_MIGRATION: usize = 2;
+
+/// Connection to the SQLite database, shared by the SQLite repositories.
+#[derive(Clone)]
+pub struct Database {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl Database {
+    /// Open the database at `path` and bring its schema up to date, `token_lifetime` being the
+    /// lifetime of the tokens issued from now on.
+    pub fn open(path: impl AsRef<Path>, token_lifetime: Duration) -> Result<Database> {
+        Database::from_connection(
+            Connection::open(path).map_err(storage_error)?,
+            token_lifetime,
+        )
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Database> {
+        Database::from_connection(
+            Connection::open_in_memory().map_err(storage_error)?,
+            Duration::zero(),
+        )
+    }
+
+    fn from_connection(mut connection: Connection, token_lifetime: Duration) -> Result<Database> {
+        connection
+            .pragma_update(None, "foreign_keys", true)
+            .map_err(storage_error)?;
+        register_functions(&connection).map_err(storage_error)?;
+        migrate(&mut connection, token_lifetime)?;
+        Ok(Database {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|error| Error::Storage(error.to_string()))
+    }
+}
+
+/// Stores the events in an SQLite database.
+pub struct SqliteEventRepository {
+    database: Database,
+}
+
+impl SqliteEventRepository {
+    pub fn new(database: Database) -> SqliteEventRepository {
+        SqliteEventRepository { database }
+    }
+}
+
+/// Functions used to search the events: `rarray` to match a list of values, `lowercase` to
+/// ignore the case of any text, and `distance_km` between two points, infinite if one of them is
+/// unknown.
+fn register_functions(connection: &Connection) -> rusqlite::Result<()> {
+    rusqlite::vtab::array::load_module(connection)?;
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    connection.create_scalar_function("lowercase", 1, flags, |context| {
+        Ok(context.get::<String>(0)?.to_lowercase())
+    })?;
+    connection.create_scalar_function("distance_km", 4, flags, |context| {
+        let coordinates = |index| -> rusqlite::Result<Option<Coordinates>> {
+            Ok(match (context.get(index)?, context.get(index + 1)?) {
+                (Some(latitude), Some(longitude)) => Some(Coordinates {
+                    latitude,
+                    longitude,
+                }),
+                _ => None,
+            })
+        };
+        Ok(match (coordinates(0)?, coordinates(2)?) {
+            (Some(from), Some(to)) => from.distance_km(&to),
+            _ => f64::INFINITY,
+        })
+    })
+}
+
+fn storage_error(error: rusqlite::Error) -> Error {
+    Error::Storage(error.to_string())
+}
+
+fn migrate(connection: &mut Connection, token_lifetime: Duration) -> Result<()> {
+    let version: i64 = connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(storage_error)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let transaction = connection.transaction().map_err(storage_error)?;
+        transaction
+            .execute_batch(migration)
+            .map_err(storage_error)?;
+        if index == TOKEN_EXPIRY_MIGRATION {
+            transaction
+                .execute(
+                    "UPDATE tokens SET expires_at = ?1",
+                    params![(Utc::now() + token_lifetime).timestamp()],
+                )
+                .map_err(storage_error)?;
+        }
+        transaction
+            .pragma_update(None, "user_version", index as i64 + 1)
+            .map_err(storage_error)?;
+        transaction.commit().map_err(storage_error)?;
+    }
+    Ok(())
+}
+
+fn parse_id(value: String) -> rusqlite::Result<Id> {
+    Id::from_str(&value).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(error))
+    })
+}
+
+fn parse_timestamp(value: i64) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::from_timestamp(value, 0).ok_or(rusqlite::Error::IntegralValueOutOfRange(0, value))
+}
+
+/// Datetimes of the events are stored in RFC 3339 to keep the timezone of the organizer.
+fn parse_datetime(value: Option<String>) -> rusqlite::Result<Option<DateTime<FixedOffset>>> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value).map_err(|error| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(error),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Datetimes of the comments are stored in RFC 3339, in UTC.
+fn parse_utc(value: String) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .map_err(|error| {
+            rusqlite::Error::FromSqlConversionFailure(
+                0,
+                rusqlite::types::Type::Text,
+                Box::new(error),
+            )
+        })
+}
+
+fn parse_json<T>(value: String) -> rusqlite::Result<T>
+where
+    T: for<'a> rocket::serde::Deserialize<'a>,
+{
+    serde_json::from_str(&value).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(error))
+    })
+}
+
+fn to_json<T: rocket::serde::Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|error| Error::Storage(error.to_string()))
+}
+
+fn parse_status(value: String) -> rusqlite::Result<EventStatus> {
+    parse_enum(value)
+}
+
+/// Enums are stored as the strings of their `Display` implementation.
+fn parse_enum<T: FromStr>(value: String) -> rusqlite::Result<T>
+where
+    T::Err: Display,
+{
+    T::from_str(&value).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            error.to_string().into(),
+        )
+    })
+}
+
+/// Columns read by `event_from_row`.
+const EVENT_COLUMNS: &str = "id, name, organizer, status, cancellation_reason, starts_at, ends_at,
+    meeting_address, meeting_latitude, meeting_longitude, route_summary, capacity, discipline, pace,
+    difficulty, no_drop, series, published_at, revision, timezone";
+
+/// Event of a row of `EVENT_COLUMNS`, without its participants and waitlist.
+fn event_from_row(row: &Row) -> rusqlite::Result<Event> {
+    Ok(Event {
+        id: parse_id(row.get(0)?)?,
+        name: row.get(1)?,
+        participants: HashSet::new(),
+        organizer: row.get(2)?,
+        status: parse_status(row.get(3)?)?,
+        cancellation_reason: row.get(4)?,
+        start: parse_datetime(row.get(5)?)?,
+        end: parse_datetime(row.get(6)?)?,
+        meeting_point: match (row.get(7)?, row.get(8)?, row.get(9)?) {
+            (Some(address), Some(latitude), Some(longitude)) => Some(MeetingPoint {
+                address,
+                latitude,
+                longitude,
+            }),
+            _ => None,
+        },
+        route: row
+            .get::<_, Option<String>>(10)?
+            .map(parse_json)
+            .transpose()?,
+        capacity: row.get(11)?,
+        waitlist: vec![],
+        discipline: row
+            .get::<_, Option<String>>(12)?
+            .map(parse_enum::<Discipline>)
+            .transpose()?,
+        pace: row
+            .get::<_, Option<String>>(13)?
+            .map(parse_enum::<Pace>)
+            .transpose()?,
+        difficulty: row
+            .get::<_, Option<String>>(14)?
+            .map(parse_enum::<Difficulty>)
+            .transpose()?,
+        no_drop: row.get(15)?,
+        series: row
+            .get::<_, Option<String>>(16)?
+            .map(parse_json)
+            .transpose()?,
+        published_at: parse_datetime(row.get(17)?)?
+            .map(|published_at| published_at.with_timezone(&Utc)),
+        revision: row.get(18)?,
+        timezone: row
+            .get::<_, Option<String>>(19)?
+            .map(parse_enum::<Tz>)
+            .transpose()?,
+    })
+}
+
+/// Load the participants and the waitlists of `events`, with one query for each.
+fn load_participants(connection: &Connection, events: &mut [Event]) -> rusqlite::Result<()> {
+    let ids: Array = Rc::new(
+        events
+            .iter()
+            .map(|event| Value::from(event.id.to_string()))
+            .collect(),
+    );
+    let mut participants: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut statement = connection.prepare_cached(
+        "SELECT event_id, username FROM participants WHERE event_id IN rarray(?1)",
+    )?;
+    let mut rows = statement.query(params![ids])?;
+    while let Some(row) = rows.next()? {
+        participants
+            .entry(row.get(0)?)
+            .or_default()
+            .insert(row.get(1)?);
+    }
+    let mut waitlists: HashMap<String, Vec<String>> = HashMap::new();
+    let mut statement = connection.prepare_cached(
+        "SELECT event_id, username FROM waitlist WHERE event_id IN rarray(?1) ORDER BY position",
+    )?;
+    let mut rows = statement.query(params![ids])?;
+    while let Some(row) = rows.next()? {
+        waitlists.entry(row.get(0)?).or_default().push(row.get(1)?);
+    }
+    for event in events.iter_mut() {
+        let id = event.id.to_string();
+        event.participants = participants.remove(&id).unwrap_or_default();
+        event.waitlist = waitlists.remove(&id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+fn select_events(connection: &Connection, id: Option<Id>) -> rusqlite::Result<Vec<Event>> {
+    let mut statement = connection.prepare_cached(&format!(
+        "SELECT {} FROM events WHERE ?1 IS NULL OR id = ?1 ORDER BY rowid",
+        EVENT_COLUMNS
+    ))?;
+    let rows = statement.query_map(params![id.map(|id| id.to_string())], event_from_row)?;
+    let mut events = rows.collect::<rusqlite::Result<Vec<Event>>>()?;
+    load_participants(connection, &mut events)?;
+    Ok(events)
+}
+
+/// Expressions sorting the events for `sort`, the last ones breaking the ties in publication
+/// order. The events published before the publication date was recorded come first.
+fn sort_keys(sort: EventSort) -> Vec<&'static str> {
+    let mut keys = match sort {
+        EventSort::Oldest | EventSort::Newest => vec![],
+        EventSort::Name => vec!["lowercase(name)"],
+        // the datetimes are compared in UTC, the events without a start coming last
+        EventSort::Start => vec!["starts_at IS NULL", "coalesce(julianday(starts_at), 0)"],
+        EventSort::Distance => {
+            vec!["distance_km(:latitude, :longitude, meeting_latitude, meeting_longitude)"]
+        }
+    };
+    keys.extend(["coalesce(julianday(published_at), 0)", "rowid"]);
+    keys
+}
+
+/// Condition of the SQL query of `EventRepository::page` and its named parameters.
+#[derive(Default)]
+struct Filter {
+    conditions: Vec<String>,
+    parameters: Vec<(&'static str, Box<dyn ToSql>)>,
+}
+
+impl Filter {
+    fn new(query: &EventQuery, viewer: Option<&str>, now: DateTime<Utc>) -> Filter {
+        let mut filter = Filter::default();
+        match (query.status, viewer) {
+            (Some(_), Some(viewer)) => {
+                filter.add(
+                    "(status != 'draft' OR organizer = :viewer)",
+                    ":viewer",
+                    viewer.to_owned(),
+                );
+            }
+            _ => filter.conditions.push("status != 'draft'".to_owned()),
+        }
+        if let Some(status) = query.status {
+            filter.add("status = :status", ":status", status.as_str());
+        }
+        if let Some(organizer) = &query.organizer {
+            filter.add("organizer = :organizer", ":organizer", organizer.clone());
+        }
+        if let Some(participant) = &query.participant {
+            filter.add(
+                "EXISTS (SELECT 1 FROM participants
+                WHERE participants.event_id = events.id AND username = :participant)",
+                ":participant",
+                participant.clone(),
+            );
+        }
+        if let Some(text) = &query.text {
+            filter.add(
+                "instr(lowercase(name), :text) > 0",
+                ":text",
+                text.to_lowercase(),
+            );
+        }
+        if let Some(when) = query.when {
+            // without a start, an event is neither upcoming nor past
+            let condition = match when {
+                EventTime::Upcoming => "julianday(starts_at) > julianday(:now)",
+                EventTime::Past => "julianday(starts_at) <= julianday(:now)",
+            };
+            filter.add(condition, ":now", now.to_rfc3339());
+        }
+        if let Some(near) = &query.near {
+            filter.add(
+                "distance_km(:latitude, :longitude, meeting_latitude, meeting_longitude)
+                <= :radius_km",
+                ":radius_km",
+                near.radius_km,
+            );
+        }
+        if let Some(discipline) = query.discipline {
+            filter.add(
+                "discipline = :discipline",
+                ":discipline",
+                discipline.as_str(),
+            );
+        }
+        // the events without a pace or a difficulty may be too hard, they are left out
+        if let Some(max_pace) = query.max_pace {
+            let paces = Pace::ALL
+                .into_iter()
+                .filter(|pace| *pace <= max_pace)
+                .map(|pace| Value::from(pace.as_str().to_owned()));
+            filter.add(
+                "pace IN rarray(:paces)",
+                ":paces",
+                Rc::new(paces.collect::<Vec<Value>>()),
+            );
+        }
+        if let Some(max_difficulty) = query.max_difficulty {
+            let difficulties = Difficulty::ALL
+                .into_iter()
+                .filter(|difficulty| *difficulty <= max_difficulty)
+                .map(|difficulty| Value::from(difficulty.as_str().to_owned()));
+            filter.add(
+                "difficulty IN rarray(:difficulties)",
+                ":difficulties",
+                Rc::new(difficulties.collect::<Vec<Value>>()),
+            );
+        }
+        if let Some(no_drop) = query.no_drop {
+            filter.add("no_drop = :no_drop", ":no_drop", no_drop);
+        }
+        if let Some(series) = query.series {
+            filter.add(
+                "json_extract(series, '$.id') = :series",
+                ":series",
+                series.to_string(),
+            );
+        }
+        // the center is also used to sort by distance, which is only allowed around a point
+        if let Some(near) = &query.near {
+            filter
+                .parameters
+                .push((":latitude", Box::new(near.center.latitude)));
+            filter
+                .parameters
+                .push((":longitude", Box::new(near.center.longitude)));
+        }
+        filter
+    }
+
+    fn add(&mut self, condition: &str, name: &'static str, value: impl ToSql + 'static) {
+        self.conditions.push(condition.to_owned());
+        self.parameters.push((name, Box::new(value)));
+    }
+}
+
+/// Replace the stored participants and waitlist of `event` by the current ones.
+fn save_participants(transaction: &Transaction, event: &Event) -> rusqlite::Result<()> {
+    transaction.execute(
+        "DELETE FROM participants WHERE event_id = ?1",
+        params![event.id.to_string()],
+    )?;
+    let mut statement = transaction
+        .prepare_cached("INSERT INTO participants (event_id, username) VALUES (?1, ?2)")?;
+    for participant in &event.participants {
+        statement.execute(params![event.id.to_string(), participant])?;
+    }
+    transaction.execute(
+        "DELETE FROM waitlist WHERE event_id = ?1",
+        params![event.id.to_string()],
+    )?;
+    let mut statement = transaction.prepare_cached(
+        "INSERT INTO waitlist (event_id, username, position) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, username) in event.waitlist.iter().enumerate() {
+        statement.execute(params![event.id.to_string(), username, position as i64])?;
+    }
+    Ok(())
+}
+
+impl EventRepository for SqliteEventRepository {
+    fn all(&self) -> Result<Vec<Event>> {
+        select_events(&*self.database.lock()?, None).map_err(storage_error)
+    }
+
+    fn page(
+        &self,
+        query: &EventQuery,
+        viewer: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Page<Event>> {
+        let connection = self.database.lock()?;
+        let mut filter = Filter::new(query, viewer, now);
+        let keys = sort_keys(query.sort);
+        let (direction, following) = match query.sort {
+            EventSort::Newest => ("DESC", "<"),
+            _ => ("ASC", ">"),
+        };
+        if let Some(cursor) = &query.cursor {
+            connection
+                .query_row(
+                    "SELECT 1 FROM events WHERE id = ?1",
+                    params![cursor],
+                    |_| Ok(()),
+                )
+                .optional()
+                .map_err(storage_error)?
+                .ok_or(Error::NotFound)?;
+            // the events following the cursor in the sort order, which it is the last of
+            filter.add(
+                &format!(
+                    "({keys}) {following} (SELECT {keys} FROM events WHERE id = :cursor)",
+                    keys = keys.join(", "),
+                ),
+                ":cursor",
+                cursor.clone(),
+            );
+        }
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        filter
+            .parameters
+            .push((":limit", Box::new(limit as i64 + 1)));
+        let sql = format!(
+            "SELECT {} FROM events WHERE {} ORDER BY {} LIMIT :limit",
+            EVENT_COLUMNS,
+            filter.conditions.join(" AND "),
+            keys.iter()
+                .map(|key| format!("{} {}", key, direction))
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+        let parameters: Vec<(&str, &dyn ToSql)> = filter
+            .parameters
+            .iter()
+            .map(|(name, value)| (*name, value.as_ref()))
+            .collect();
+        let mut events = connection
+            .prepare(&sql)
+            .and_then(|mut statement| {
+                statement
+                    .query_map(parameters.as_slice(), event_from_row)?
+                    .collect::<rusqlite::Result<Vec<Event>>>()
+            })
+            .map_err(storage_error)?;
+        let next_cursor = if events.len() > limit {
+            events.truncate(limit);
+            events.last().map(|event| event.id.to_string())
+        } else {
+            None
+        };
+        load_participants(&connection, &mut events).map_err(storage_error)?;
+        Ok(Page {
+            items: events,
+            next_cursor,
+        })
+    }
+
+    fn get(&self, id: Id) -> Result<Option<Event>> {
+        select_events(&*self.database.lock()?, Some(id))
+            .map(|events| events.into_iter().next())
+            .map_err(storage_error)
+    }
+
+    fn insert_all(&self, events: Vec<Event>) -> Result<()> {
+        let mut connection = self.database.lock()?;
+        let transaction = connection.transaction().map_err(storage_error)?;
+        for event in &events {
+            let exists = transaction
+                .query_row(
+                    "SELECT 1 FROM events WHERE id = ?1",
+                    params![event.id.to_string()],
+                    |_| Ok(()),
+                )
+                .optional()
+                .map_err(storage_error)?
+                .is_some();
+            if exists {
+                return Err(Error::AlreadyExists);
+            }
+            transaction
+                .execute(
+                    "INSERT INTO events
+                    (id, name, organizer, status, cancellation_reason, starts_at, ends_at,
+                    meeting_address, meeting_latitude, meeting_longitude, route_summary, capacity,
+                    discipline, pace, difficulty, no_drop, series, published_at, revision, timezone)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                    ?18, ?19, ?20)",
+                    params![
+                        event.id.to_string(),
+                        event.name,
+                        event.organizer,
+                        event.status.as_str(),
+                        event.cancellation_reason,
+                        event.start.map(|start| start.to_rfc3339()),
+                        event.end.map(|end| end.to_rfc3339()),
+                        event.meeting_point.as_ref().map(|point| &point.address),
+                        event.meeting_point.as_ref().map(|point| point.latitude),
+                        event.meeting_point.as_ref().map(|point| point.longitude),
+                        event.route.as_ref().map(to_json).transpose()?,
+                        event.capacity,
+                        event.discipline.map(Discipline::as_str),
+                        event.pace.map(Pace::as_str),
+                        event.difficulty.map(Difficulty::as_str),
+                        event.no_drop,
+                        event.series.as_ref().map(to_json).transpose()?,
+                        event
+                            .published_at
+                            .map(|published_at| published_at.to_rfc3339()),
+                        event.revision,
+                        event.timezone.map(|timezone| timezone.name())
+                    ],
+                )
+                .map_err(storage_error)?;
+            save_participants(&transaction, event).map_err(storage_error)?;
+        }
+        transaction.commit().map_err(storage_error)
+    }
+
+    fn update(&self, id: Id, change: &mut dyn FnMut(&mut Event)) -> Result<Event> {
+        let mut connection = self.database.lock()?;
+        let transaction = connection.transaction().map_err(storage_error)?;
+        let mut event = select_events(&transaction, Some(id))
+            .map_err(storage_error)?
+            .into_iter()
+            .next()
+            .ok_or(Error::NotFound)?;
+        change(&mut event);
+        transaction
+            .execute(
+                "UPDATE events SET name = ?2, organizer = ?3, status = ?4, cancellation_reason = ?5,
+                starts_at = ?6, ends_at = ?7, meeting_address = ?8, meeting_latitude = ?9,
+                meeting_longitude = ?10, route_summary = ?11, capacity = ?12, discipline = ?13, pace = ?14,
+                difficulty = ?15, no_drop = ?16, series = ?17, published_at = ?18, revision = ?19,
+                timezone = ?20
+                WHERE id = ?1",
+                params![
+                    event.id.to_string(),
+                    event.name,
+                    event.organizer,
+                    event.status.as_str(),
+                    event.cancellation_reason,
+                    event.start.map(|start| start.to_rfc3339()),
+                    event.end.map(|end| end.to_rfc3339()),
+                    event.meeting_point.as_ref().map(|point| &point.address),
+                    event.meeting_point.as_ref().map(|point| point.latitude),
+                    event.meeting_point.as_ref().map(|point| point.longitude),
+                    event.route.as_ref().map(to_json).transpose()?,
+                    event.capacity,
+                    event.discipline.map(Discipline::as_str),
+                    event.pace.map(Pace::as_str),
+                    event.difficulty.map(Difficulty::as_str),
+                    event.no_drop,
+                    event.series.as_ref().map(to_json).transpose()?,
+                    event
+                        .published_at
+                        .map(|published_at| published_at.to_rfc3339()),
+                    event.revision,
+                    event.timezone.map(|timezone| timezone.name())
+                ],
+            )
+            .map_err(storage_error)?;
+        save_participants(&transaction, &event).map_err(storage_error)?;
+        transaction.commit().map_err(storage_error)?;
+        Ok(event)
+    }
+}
+
+/// Stores the user accounts in an SQLite database.
+pub struct SqliteUserRepository {
+    database: Database,
+}
+
+impl SqliteUserRepository {
+    pub fn new(database: Database) -> SqliteUserRepository {
+        SqliteUserRepository { database }
+    }
+}
+
+impl UserRepository for SqliteUserRepository {
+    fn insert(&self, account: Account) -> Result<()> {
+        let connection = self.database.lock()?;
+        let inserted = connection
+            .execute(
+                "INSERT OR IGNORE INTO users (username, password_hash) VALUES (?1, ?2)",
+                params![account.username, account.password_hash],
+            )
+            .map_err(storage_error)?;
+        if inserted == 0 {
+            Err(Error::AlreadyExists)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get(&self, username: &str) -> Result<Option<Account>> {
+        self.database
+            .lock()?
+            .query_row(
+                "SELECT username, password_hash FROM users WHERE username = ?1",
+                params![username],
+                |row| {
+                    Ok(Account {
+                        username: row.get(0)?,
+                        password_hash: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+}
+
+/// Stores the issued tokens in an SQLite database.
+pub struct SqliteTokenRepository {
+    database: Database,
+}
+
+impl SqliteTokenRepository {
+    pub fn new(database: Database) -> SqliteTokenRepository {
+        SqliteTokenRepository { database }
+    }
+}
+
+impl TokenRepository for SqliteTokenRepository {
+    fn insert(&self, token: Token) -> Result<()> {
+        let connection = self.database.lock()?;
+        let inserted = connection
+            .execute(
+                "INSERT OR IGNORE INTO tokens (value, username, expires_at, revoked_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    token.value,
+                    token.username,
+                    token.expires_at.timestamp(),
+                    token.revoked_at.map(|at| at.timestamp())
+                ],
+            )
+            .map_err(storage_error)?;
+        if inserted == 0 {
+            Err(Error::AlreadyExists)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get(&self, value: &str) -> Result<Option<Token>> {
+        self.database
+            .lock()?
+            .query_row(
+                "SELECT value, username, expires_at, revoked_at FROM tokens WHERE value = ?1",
+                params![value],
+                |row| {
+                    Ok(Token {
+                        value: row.get(0)?,
+                        username: row.get(1)?,
+                        expires_at: parse_timestamp(row.get(2)?)?,
+                        revoked_at: row
+                            .get::<_, Option<i64>>(3)?
+                            .map(parse_timestamp)
+                            .transpose()?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+
+    fn revoke(&self, value: &str, at: DateTime<Utc>) -> Result<()> {
+        let updated = self
+            .database
+            .lock()?
+            .execute(
+                "UPDATE tokens SET revoked_at = ?2 WHERE value = ?1",
+                params![value, at.timestamp()],
+            )
+            .map_err(storage_error)?;
+        if updated == 0 {
+            Err(Error::NotFound)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Stores the routes in an SQLite database, as JSON.
+pub struct SqliteRouteRepository {
+    database: Database,
+}
+
+impl SqliteRouteRepository {
+    pub fn new(database: Database) -> SqliteRouteRepository {
+        SqliteRouteRepository { database }
+    }
+}
+
+impl RouteRepository for SqliteRouteRepository {
+    fn get(&self, event_id: Id) -> Result<Option<Route>> {
+        self.database
+            .lock()?
+            .query_row(
+                "SELECT segments FROM routes WHERE event_id = ?1",
+                params![event_id.to_string()],
+                |row| {
+                    Ok(Route {
+                        segments: parse_json(row.get(0)?)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+
+    fn set(&self, event_id: Id, route: Route) -> Result<()> {
+        self.database
+            .lock()?
+            .execute(
+                "INSERT OR REPLACE INTO routes (event_id, segments) VALUES (?1, ?2)",
+                params![event_id.to_string(), to_json(&route.segments)?],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+}
+
+/// Stores the calendar feed tokens in an SQLite database.
+pub struct SqliteFeedTokenRepository {
+    database: Database,
+}
+
+impl SqliteFeedTokenRepository {
+    pub fn new(database: Database) -> SqliteFeedTokenRepository {
+        SqliteFeedTokenRepository { database }
+    }
+}
+
+impl FeedTokenRepository for SqliteFeedTokenRepository {
+    fn get(&self, username: &str) -> Result<Option<String>> {
+        self.database
+            .lock()?
+            .query_row(
+                "SELECT token FROM feed_tokens WHERE username = ?1",
+                params![username],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+
+    fn set(&self, username: &str, token: String) -> Result<()> {
+        self.database
+            .lock()?
+            .execute(
+                "INSERT OR REPLACE INTO feed_tokens (username, token) VALUES (?1, ?2)",
+                params![username, token],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn username(&self, token: &str) -> Result<Option<String>> {
+        self.database
+            .lock()?
+            .query_row(
+                "SELECT username FROM feed_tokens WHERE token = ?1",
+                params![token],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+}
+
+/// Stores the comments in an SQLite database.
+pub struct SqliteCommentRepository {
+    database: Database,
+}
+
+impl SqliteCommentRepository {
+    pub fn new(database: Database) -> SqliteCommentRepository {
+        SqliteCommentRepository { database }
+    }
+}
+
+/// Comments of the event `event_id`, or the comment `id`, in the order they were posted.
+fn select_comments(
+    connection: &Connection,
+    event_id: Option<Id>,
+    id: Option<Id>,
+) -> rusqlite::Result<Vec<Comment>> {
+    let mut statement = connection.prepare_cached(
+        "SELECT id, event_id, author, text, posted_at, edited_at FROM comments
+        WHERE (?1 IS NULL OR event_id = ?1) AND (?2 IS NULL OR id = ?2) ORDER BY rowid",
+    )?;
+    let rows = statement.query_map(
+        params![
+            event_id.map(|event_id| event_id.to_string()),
+            id.map(|id| id.to_string())
+        ],
+        |row| {
+            Ok(Comment {
+                id: parse_id(row.get(0)?)?,
+                event_id: parse_id(row.get(1)?)?,
+                author: row.get(2)?,
+                text: row.get(3)?,
+                posted_at: parse_utc(row.get(4)?)?,
+                edited_at: row
+                    .get::<_, Option<String>>(5)?
+                    .map(parse_utc)
+                    .transpose()?,
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+impl CommentRepository for SqliteCommentRepository {
+    fn of_event(&self, event_id: Id) -> Result<Vec<Comment>> {
+        select_comments(&*self.database.lock()?, Some(event_id), None).map_err(storage_error)
+    }
+
+    fn get(&self, id: Id) -> Result<Option<Comment>> {
+        select_comments(&*self.database.lock()?, None, Some(id))
+            .map(|comments| comments.into_iter().next())
+            .map_err(storage_error)
+    }
+
+    fn insert(&self, comment: Comment) -> Result<()> {
+        let inserted = self
+            .database
+            .lock()?
+            .execute(
+                "INSERT OR IGNORE INTO comments (id, event_id, author, text, posted_at, edited_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    comment.id.to_string(),
+                    comment.event_id.to_string(),
+                    comment.author,
+                    comment.text,
+                    comment.posted_at.to_rfc3339(),
+                    comment.edited_at.map(|edited_at| edited_at.to_rfc3339())
+                ],
+            )
+            .map_err(storage_error)?;
+        if inserted == 0 {
+            Err(Error::AlreadyExists)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn update(&self, comment: Comment) -> Result<()> {
+        let updated = self
+            .database
+            .lock()?
+            .execute(
+                "UPDATE comments SET text = ?2, edited_at = ?3 WHERE id = ?1",
+                params![
+                    comment.id.to_string(),
+                    comment.text,
+                    comment.edited_at.map(|edited_at| edited_at.to_rfc3339())
+                ],
+            )
+            .map_err(storage_error)?;
+        if updated == 0 {
+            Err(Error::NotFound)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn delete(&self, id: Id) -> Result<()> {
+        let deleted = self
+            .database
+            .lock()?
+            .execute(
+                "DELETE FROM comments WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .map_err(storage_error)?;
+        if deleted == 0 {
+            Err(Error::NotFound)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Stores the email addresses in an SQLite database.
+pub struct SqliteRecipientRepository {
+    database: Database,
+}
+
+impl SqliteRecipientRepository {
+    pub fn new(database: Database) -> SqliteRecipientRepository {
+        SqliteRecipientRepository { database }
+    }
+}
+
+fn recipient_of_row(row: &rusqlite::Row) -> rusqlite::Result<Recipient> {
+    Ok(Recipient {
+        username: row.get(0)?,
+        email: row.get(1)?,
+        unsubscribe_token: row.get(2)?,
+        unsubscribed: row.get(3)?,
+    })
+}
+
+impl RecipientRepository for SqliteRecipientRepository {
+    fn get(&self, username: &str) -> Result<Option<Recipient>> {
+        self.database
+            .lock()?
+            .query_row(
+                "SELECT username, email, unsubscribe_token, unsubscribed FROM recipients
+                WHERE username = ?1",
+                params![username],
+                recipient_of_row,
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+
+    fn set(&self, recipient: Recipient) -> Result<()> {
+        self.database
+            .lock()?
+            .execute(
+                "INSERT OR REPLACE INTO recipients (username, email, unsubscribe_token, unsubscribed)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    recipient.username,
+                    recipient.email,
+                    recipient.unsubscribe_token,
+                    recipient.unsubscribed
+                ],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn by_unsubscribe_token(&self, token: &str) -> Result<Option<Recipient>> {
+        self.database
+            .lock()?
+            .query_row(
+                "SELECT username, email, unsubscribe_token, unsubscribed FROM recipients
+                WHERE unsubscribe_token = ?1",
+                params![token],
+                recipient_of_row,
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+}
+
+/// Stores the notifications in an SQLite database.
+pub struct SqliteNotificationRepository {
+    database: Database,
+}
+
+impl SqliteNotificationRepository {
+    pub fn new(database: Database) -> SqliteNotificationRepository {
+        SqliteNotificationRepository { database }
+    }
+}
+
+impl NotificationRepository for SqliteNotificationRepository {
+    fn of_user(&self, username: &str) -> Result<Vec<Notification>> {
+        let connection = self.database.lock()?;
+        let mut statement = connection
+            .prepare_cached(
+                "SELECT id, text, event_id, created_at, read FROM notifications
+                WHERE username = ?1 ORDER BY rowid DESC",
+            )
+            .map_err(storage_error)?;
+        let rows = statement
+            .query_map(params![username], |row| {
+                Ok(Notification {
+                    id: parse_id(row.get(0)?)?,
+                    text: row.get(1)?,
+                    event_id: row.get::<_, Option<String>>(2)?.map(parse_id).transpose()?,
+                    created_at: parse_utc(row.get(3)?)?,
+                    read: row.get(4)?,
+                })
+            })
+            .map_err(storage_error)?;
+        rows.collect::<rusqlite::Result<Vec<Notification>>>()
+            .map_err(storage_error)
+    }
+
+    fn insert(&self, username: &str, notification: Notification) -> Result<()> {
+        self.database
+            .lock()?
+            .execute(
+                "INSERT INTO notifications (id, username, text, event_id, created_at, read)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    notification.id.to_string(),
+                    username,
+                    notification.text,
+                    notification.event_id.map(|event_id| event_id.to_string()),
+                    notification.created_at.to_rfc3339(),
+                    notification.read
+                ],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn mark_read(&self, username: &str, id: Id) -> Result<()> {
+        let updated = self
+            .database
+            .lock()?
+            .execute(
+                "UPDATE notifications SET read = 1 WHERE username = ?1 AND id = ?2",
+                params![username, id.to_string()],
+            )
+            .map_err(storage_error)?;
+        if updated == 0 {
+            Err(Error::NotFound)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn mark_all_read(&self, username: &str) -> Result<()> {
+        self.database
+            .lock()?
+            .execute(
+                "UPDATE notifications SET read = 1 WHERE username = ?1",
+                params![username],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+}
+
+/// Stores the reminders sent in an SQLite database.
+pub struct SqliteReminderRepository {
+    database: Database,
+}
+
+impl SqliteReminderRepository {
+    pub fn new(database: Database) -> SqliteReminderRepository {
+        SqliteReminderRepository { database }
+    }
+}
+
+impl ReminderRepository for SqliteReminderRepository {
+    fn insert(&self, reminder: Reminder) -> Result<()> {
+        let inserted = self
+            .database
+            .lock()?
+            .execute(
+                "INSERT OR IGNORE INTO reminders (event_id, username, starts_at) VALUES (?1, ?2, ?3)",
+                params![
+                    reminder.event_id.to_string(),
+                    reminder.username,
+                    reminder.start.to_rfc3339()
+                ],
+            )
+            .map_err(storage_error)?;
+        if inserted == 0 {
+            Err(Error::AlreadyExists)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Stores the emails to send in an SQLite database.
+pub struct SqliteOutboxRepository {
+    database: Database,
+}
+
+impl SqliteOutboxRepository {
+    pub fn new(database: Database) -> SqliteOutboxRepository {
+        SqliteOutboxRepository { database }
+    }
+}
+
+impl OutboxRepository for SqliteOutboxRepository {
+    fn push(&self, email: Email) -> Result<()> {
+        self.database
+            .lock()?
+            .execute(
+                "INSERT INTO outbox (id, recipient, subject, body) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    Id::new_v4().to_string(),
+                    email.to,
+                    email.subject,
+                    email.body
+                ],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn queued(&self) -> Result<Vec<QueuedEmail>> {
+        let connection = self.database.lock()?;
+        let mut statement = connection
+            .prepare("SELECT id, recipient, subject, body FROM outbox ORDER BY rowid")
+            .map_err(storage_error)?;
+        let emails = statement
+            .query_map([], |row| {
+                Ok(QueuedEmail {
+                    id: parse_id(row.get(0)?)?,
+                    email: Email {
+                        to: row.get(1)?,
+                        subject: row.get(2)?,
+                        body: row.get(3)?,
+                    },
+                })
+            })
+            .map_err(storage_error)?
+            .collect::<rusqlite::Result<Vec<QueuedEmail>>>()
+            .map_err(storage_error)?;
+        Ok(emails)
+    }
+
+    fn remove(&self, id: Id) -> Result<()> {
+        self.database
+            .lock()?
+            .execute("DELETE FROM outbox WHERE id = ?1", params![id.to_string()])
+            .map_err(storage_error)?;
+        Ok(())
+    }
+}
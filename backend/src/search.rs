@@ -0,0 +1,200 @@
+use crate::{failure::Failure, repository};
+use common::{
+    Area, Coordinates, Difficulty, Discipline, EventQuery, EventSort, EventStatus, EventTime, Id,
+    Pace, Page,
+};
+use rocket::FromForm;
+use std::str::FromStr;
+
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Query parameters of `/api/events` as sent by the client, see `EventQuery`.
+#[derive(FromForm, Default)]
+pub struct EventQueryParams {
+    status: Option<String>,
+    organizer: Option<String>,
+    participant: Option<String>,
+    text: Option<String>,
+    when: Option<String>,
+    latitude: Option<String>,
+    longitude: Option<String>,
+    radius_km: Option<String>,
+    discipline: Option<String>,
+    max_pace: Option<String>,
+    max_difficulty: Option<String>,
+    no_drop: Option<String>,
+    series: Option<String>,
+    sort: Option<String>,
+    cursor: Option<String>,
+    limit: Option<String>,
+}
+
+impl EventQueryParams {
+    pub fn parse(self) -> Result<EventQuery, Failure> {
+        let status = match self.status {
+            Some(status) => Some(
+                EventStatus::from_str(&status)
+                    .map_err(|error| Failure::invalid_field("status", error))?,
+            ),
+            None => None,
+        };
+        let when = match self.when {
+            Some(when) => Some(
+                EventTime::from_str(&when)
+                    .map_err(|error| Failure::invalid_field("when", error))?,
+            ),
+            None => None,
+        };
+        let discipline = parse_optional("discipline", self.discipline, Discipline::from_str)?;
+        let max_pace = parse_optional("max_pace", self.max_pace, Pace::from_str)?;
+        let max_difficulty =
+            parse_optional("max_difficulty", self.max_difficulty, Difficulty::from_str)?;
+        let no_drop = parse_optional("no_drop", self.no_drop, |no_drop| {
+            bool::from_str(no_drop).map_err(|_| "no_drop must be true or false".to_owned())
+        })?;
+        let series = parse_optional("series", self.series, |series| {
+            Id::from_str(series).map_err(|error| error.to_string())
+        })?;
+        let near = match (self.latitude, self.longitude, self.radius_km) {
+            (None, None, None) => None,
+            (latitude, longitude, radius_km) => {
+                let center = Coordinates {
+                    latitude: parse_number("latitude", latitude)?,
+                    longitude: parse_number("longitude", longitude)?,
+                };
+                check_coordinates(&center, "latitude", "longitude")?;
+                let radius_km = parse_number("radius_km", radius_km)?;
+                if radius_km <= 0.0 {
+                    return Err(Failure::invalid_field(
+                        "radius_km",
+                        "the radius must be positive",
+                    ));
+                }
+                Some(Area { center, radius_km })
+            }
+        };
+        let sort = match self.sort {
+            Some(sort) => {
+                EventSort::from_str(&sort).map_err(|error| Failure::invalid_field("sort", error))?
+            }
+            // searching around a point gives the nearest events first
+            None if near.is_some() => EventSort::Distance,
+            None => EventSort::default(),
+        };
+        if sort == EventSort::Distance && near.is_none() {
+            return Err(Failure::invalid_field(
+                "sort",
+                "sorting by distance requires a latitude, a longitude and a radius",
+            ));
+        }
+        let limit = parse_limit(self.limit)?;
+        Ok(EventQuery {
+            status,
+            organizer: self.organizer,
+            participant: self.participant,
+            text: self.text,
+            when,
+            near,
+            discipline,
+            max_pace,
+            max_difficulty,
+            no_drop,
+            series,
+            sort,
+            cursor: self.cursor,
+            limit,
+        })
+    }
+}
+
+/// Parse the `limit` parameter of a paginated list, `None` letting the backend choose.
+pub fn parse_limit(limit: Option<String>) -> Result<Option<usize>, Failure> {
+    parse_optional("limit", limit, |limit| {
+        usize::from_str(limit)
+            .ok()
+            .filter(|limit| (1..=MAX_PAGE_SIZE).contains(limit))
+            .ok_or(format!("the limit must be between 1 and {}", MAX_PAGE_SIZE))
+    })
+}
+
+/// Parse the parameter `field` if it was given, failing with a field error if it is invalid.
+fn parse_optional<T>(
+    field: &str,
+    value: Option<String>,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, Failure> {
+    value
+        .map(|value| parse(&value).map_err(|error| Failure::invalid_field(field, error)))
+        .transpose()
+}
+
+fn parse_number(field: &str, value: Option<String>) -> Result<f64, Failure> {
+    value
+        .and_then(|value| f64::from_str(&value).ok())
+        .filter(|value| value.is_finite())
+        .ok_or(Failure::invalid_field(
+            field,
+            format!("the {} must be a number", field),
+        ))
+}
+
+/// Fail with a field error if the coordinates are not on the Earth.
+pub fn check_coordinates(
+    coordinates: &Coordinates,
+    latitude_field: &str,
+    longitude_field: &str,
+) -> Result<(), Failure> {
+    if !(-90.0..=90.0).contains(&coordinates.latitude) {
+        return Err(Failure::invalid_field(
+            latitude_field,
+            "the latitude must be between -90 and 90",
+        ));
+    }
+    if !(-180.0..=180.0).contains(&coordinates.longitude) {
+        return Err(Failure::invalid_field(
+            longitude_field,
+            "the longitude must be between -180 and 180",
+        ));
+    }
+    Ok(())
+}
+
+/// Failure of a request for a page of events, the repository not finding the event of the cursor.
+pub fn cursor_failure(error: repository::Error) -> Failure {
+    match error {
+        repository::Error::NotFound => Failure::invalid_field("cursor", "unknown cursor"),
+        error => error.into(),
+    }
+}
+
+/// Select the page of `items` following the item which ID is `cursor`, with at most `limit`
+/// items.
+pub fn paginate<T>(
+    items: Vec<T>,
+    cursor: Option<&str>,
+    limit: Option<usize>,
+    id: impl Fn(&T) -> Id,
+) -> Result<Page<T>, Failure> {
+    let start = match cursor {
+        Some(cursor) => {
+            items
+                .iter()
+                .position(|item| id(item).to_string() == cursor)
+                .ok_or(Failure::invalid_field("cursor", "unknown cursor"))?
+                + 1
+        }
+        None => 0,
+    };
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let items: Vec<T> = items.into_iter().skip(start).collect();
+    let next_cursor = if items.len() > limit {
+        Some(id(&items[limit - 1]).to_string())
+    } else {
+        None
+    };
+    Ok(Page {
+        items: items.into_iter().take(limit).collect(),
+        next_cursor,
+    })
+}
@@ -0,0 +1,41 @@
+use common::{EventStatus, EventUpdate};
+use rocket::tokio::sync::broadcast;
+
+/// Number of updates kept for the clients reading them slowly, the oldest ones are skipped when a
+/// client lags further behind.
+const CAPACITY: usize = 64;
+
+/// Sends the changes of the events to the clients following them live, the clones send to the
+/// same clients.
+#[derive(Clone)]
+pub struct Broadcaster {
+    sender: broadcast::Sender<EventUpdate>,
+}
+
+impl Broadcaster {
+    pub fn new() -> Broadcaster {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Broadcaster { sender }
+    }
+
+    /// Send `update` to the current subscribers, unless the event is a draft which only its
+    /// organizer can see.
+    pub fn send(&self, update: EventUpdate) {
+        if update.event().status == EventStatus::Draft {
+            return;
+        }
+        // sending only fails when nobody follows the events, the update is then dropped
+        let _ = self.sender.send(update);
+    }
+
+    /// Receiver of the updates sent from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<EventUpdate> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for Broadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
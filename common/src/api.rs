@@ -1,13 +1,61 @@
-use crate::{Event, Id};
+use crate::{
+    ApiError, CalendarFeed, Comment, Credentials, Event, EventPatch, EventQuery, EventUpdate, Id,
+    NewEvent, Notification, Page, Participation, Route, Session,
+};
 use async_trait::async_trait;
-use mockall::*;
 use mockall::predicate::*;
+use mockall::*;
 
 #[automock]
 #[async_trait(?Send)]
 pub trait BackendApi {
-    async fn get_events(self: &Self) -> Result<Vec<Event>, String>;
-    async fn get_event(self: &Self, id: Id) -> Result<Event, String>;
-    async fn publish_event(self: &Self, name: String) -> Result<(), String>;
-    async fn join_event(self: &Self, id: Id) -> Result<(), String>;
+    async fn get_events(&self, query: EventQuery) -> Result<Page<Event>, ApiError>;
+    async fn get_event(&self, id: Id) -> Result<Event, ApiError>;
+    /// Wait for the next change of an event pushed by the backend, the connection to the stream
+    /// of updates being opened on the first call.
+    async fn next_event_update(&self) -> Result<EventUpdate, ApiError>;
+    /// Publish a new event, the returned event holds the ID chosen by the backend.
+    async fn publish_event(&self, event: NewEvent) -> Result<Event, ApiError>;
+    /// Change some fields of an event, only allowed to its organizer.
+    async fn update_event(&self, id: Id, patch: EventPatch) -> Result<Event, ApiError>;
+    /// Cancel an event giving a reason, only allowed to its organizer.
+    async fn cancel_event(&self, id: Id, reason: String) -> Result<Event, ApiError>;
+    /// Points of the route attached to an event, see `Event::route` for its summary.
+    async fn get_route(&self, id: Id) -> Result<Route, ApiError>;
+    /// Attach the route of a GPX file to an event, only allowed to its organizer.
+    async fn upload_route(&self, id: Id, gpx: String) -> Result<Event, ApiError>;
+    /// Join an event, or its waitlist if the event is full.
+    async fn join_event(&self, id: Id) -> Result<Participation, ApiError>;
+    /// Leave an event or its waitlist, the first waitlisted user takes the freed spot.
+    async fn leave_event(&self, id: Id) -> Result<(), ApiError>;
+    /// Comments of an event oldest first, `cursor` being the `Page::next_cursor` of the previous
+    /// page.
+    async fn get_comments(
+        &self,
+        event_id: Id,
+        cursor: Option<String>,
+    ) -> Result<Page<Comment>, ApiError>;
+    /// Comment an event as the logged-in user.
+    async fn post_comment(&self, event_id: Id, text: String) -> Result<Comment, ApiError>;
+    /// Change the text of a comment, only allowed to its author.
+    async fn edit_comment(&self, id: Id, text: String) -> Result<Comment, ApiError>;
+    /// Delete a comment, only allowed to the organizer of the event.
+    async fn delete_comment(&self, id: Id) -> Result<(), ApiError>;
+    /// Calendar feed of the logged-in user, created on the first request.
+    async fn get_calendar_feed(&self) -> Result<CalendarFeed, ApiError>;
+    /// Replace the calendar feed of the logged-in user, the previous one stops working.
+    async fn reset_calendar_feed(&self) -> Result<CalendarFeed, ApiError>;
+    /// Notify the logged-in user by email at `email` from now on, even if they unsubscribed.
+    async fn set_email(&self, email: String) -> Result<(), ApiError>;
+    /// Notifications of the logged-in user, newest first.
+    async fn get_notifications(&self) -> Result<Vec<Notification>, ApiError>;
+    async fn mark_notification_read(&self, id: Id) -> Result<(), ApiError>;
+    async fn mark_all_notifications_read(&self) -> Result<(), ApiError>;
+    async fn signup(&self, credentials: Credentials) -> Result<(), ApiError>;
+    /// Log in and authenticate the next requests with the returned session.
+    async fn login(&self, credentials: Credentials) -> Result<Session, ApiError>;
+    /// Revoke the current session on the server.
+    async fn logout(&self) -> Result<(), ApiError>;
+    /// Replace the current session with a new one, before it expires.
+    async fn refresh_session(&self) -> Result<Session, ApiError>;
 }
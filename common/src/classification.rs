@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// Kind of bike and of terrain of a ride.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Discipline {
+    Road,
+    Gravel,
+    Mtb,
+}
+
+impl Discipline {
+    pub const ALL: [Discipline; 3] = [Discipline::Road, Discipline::Gravel, Discipline::Mtb];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Discipline::Road => "road",
+            Discipline::Gravel => "gravel",
+            Discipline::Mtb => "mtb",
+        }
+    }
+
+    /// Name shown to the users.
+    pub fn label(self) -> &'static str {
+        match self {
+            Discipline::Road => "road",
+            Discipline::Gravel => "gravel",
+            Discipline::Mtb => "MTB",
+        }
+    }
+}
+
+impl Display for Discipline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Discipline {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Discipline, String> {
+        match value {
+            "road" => Ok(Discipline::Road),
+            "gravel" => Ok(Discipline::Gravel),
+            "mtb" => Ok(Discipline::Mtb),
+            _ => Err(format!("unknown discipline {}", value)),
+        }
+    }
+}
+
+/// Band of the average speed of a ride, from the slowest to the fastest.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Pace {
+    /// Below 20 km/h.
+    Social,
+    /// From 20 to 25 km/h.
+    Moderate,
+    /// From 25 to 30 km/h.
+    Brisk,
+    /// Above 30 km/h.
+    Fast,
+}
+
+impl Pace {
+    pub const ALL: [Pace; 4] = [Pace::Social, Pace::Moderate, Pace::Brisk, Pace::Fast];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Pace::Social => "social",
+            Pace::Moderate => "moderate",
+            Pace::Brisk => "brisk",
+            Pace::Fast => "fast",
+        }
+    }
+
+    /// Name and average speeds of the band shown to the users.
+    pub fn label(self) -> &'static str {
+        match self {
+            Pace::Social => "social (below 20 km/h)",
+            Pace::Moderate => "moderate (20 to 25 km/h)",
+            Pace::Brisk => "brisk (25 to 30 km/h)",
+            Pace::Fast => "fast (above 30 km/h)",
+        }
+    }
+}
+
+impl Display for Pace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Pace {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Pace, String> {
+        match value {
+            "social" => Ok(Pace::Social),
+            "moderate" => Ok(Pace::Moderate),
+            "brisk" => Ok(Pace::Brisk),
+            "fast" => Ok(Pace::Fast),
+            _ => Err(format!("unknown pace {}", value)),
+        }
+    }
+}
+
+/// How demanding a ride is, from the easiest to the hardest.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Intermediate,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Intermediate,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Intermediate => "intermediate",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Difficulty, String> {
+        match value {
+            "easy" => Ok(Difficulty::Easy),
+            "intermediate" => Ok(Difficulty::Intermediate),
+            "hard" => Ok(Difficulty::Hard),
+            "expert" => Ok(Difficulty::Expert),
+            _ => Err(format!("unknown difficulty {}", value)),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Body of every failed API response, also returned by the client when the server cannot be
+/// reached.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    /// Human readable explanation, can be shown to the user.
+    pub message: String,
+    /// Fields of the request body that were rejected, if any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// Why a given field of the request body was rejected.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request is malformed, e.g. the body is not valid JSON.
+    InvalidRequest,
+    /// Some fields of the request body have invalid values, see `ApiError::fields`.
+    Validation,
+    /// No bearer token was sent.
+    MissingToken,
+    /// The token was never issued by the server.
+    InvalidToken,
+    /// The token is too old, the user needs to log in again.
+    ExpiredToken,
+    /// The user logged out, the user needs to log in again.
+    RevokedToken,
+    InvalidCredentials,
+    Forbidden,
+    NotFound,
+    /// The request conflicts with the current state, e.g. joining a cancelled event.
+    Conflict,
+    /// The client sent too many requests, it can retry after the delay of the `Retry-After`
+    /// header.
+    TooManyRequests,
+    Internal,
+    /// The client could not reach the server or could not understand its response.
+    Network,
+}
+
+impl ErrorCode {
+    /// HTTP status of the responses carrying this code.
+    pub fn status(self) -> u16 {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::Validation => 400,
+            ErrorCode::MissingToken
+            | ErrorCode::InvalidToken
+            | ErrorCode::ExpiredToken
+            | ErrorCode::RevokedToken
+            | ErrorCode::InvalidCredentials => 401,
+            ErrorCode::Forbidden => 403,
+            ErrorCode::NotFound => 404,
+            ErrorCode::Conflict => 409,
+            ErrorCode::TooManyRequests => 429,
+            ErrorCode::Internal => 500,
+            ErrorCode::Network => 502,
+        }
+    }
+
+    /// Best guess of the code of a response which body is not an `ApiError`.
+    pub fn from_status(status: u16) -> ErrorCode {
+        match status {
+            401 => ErrorCode::InvalidToken,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            409 => ErrorCode::Conflict,
+            429 => ErrorCode::TooManyRequests,
+            400..=499 => ErrorCode::InvalidRequest,
+            _ => ErrorCode::Internal,
+        }
+    }
+
+    /// Whether the user needs to log in (again) before retrying the request.
+    pub fn requires_login(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::MissingToken
+                | ErrorCode::InvalidToken
+                | ErrorCode::ExpiredToken
+                | ErrorCode::RevokedToken
+        )
+    }
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> ApiError {
+        ApiError {
+            code,
+            message: message.into(),
+            fields: vec![],
+        }
+    }
+
+    /// Validation error of a single field of the request body.
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> ApiError {
+        let message = message.into();
+        ApiError {
+            code: ErrorCode::Validation,
+            message: message.clone(),
+            fields: vec![FieldError {
+                field: field.to_owned(),
+                message,
+            }],
+        }
+    }
+
+    pub fn requires_login(&self) -> bool {
+        self.code.requires_login()
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
@@ -1,15 +1,13 @@
-pub fn parse_json<T>(json_str: &str) -> Result<T, String>
+use crate::{ApiError, ErrorCode};
+
+pub fn parse_json<T>(json_str: &str) -> Result<T, ApiError>
 where
     T: for<'a> serde::de::Deserialize<'a>,
 {
     // we store the result in a type annotated variable so that serde_json deserialized a T and not a Result<T, String>
     let events: T = serde_json::from_str(json_str).map_err(|error| {
-        let error = format!(
-            "could not parse json response: {}\n{}",
-            error.to_string(),
-            json_str
-        );
-        error
+        let error = format!("could not parse json response: {}\n{}", error, json_str);
+        ApiError::new(ErrorCode::Network, error)
     })?;
     Ok(events)
 }
@@ -1,12 +1,24 @@
+use chrono::{DateTime, FixedOffset, Utc};
 use derivative::Derivative;
-use serde;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt::Display, str::FromStr};
 use uuid::Uuid;
 
 pub mod api;
+mod classification;
+mod error;
 mod json;
+mod query;
+mod recurrence;
 pub mod rest;
+mod route;
+
+pub use chrono_tz::Tz;
+pub use classification::{Difficulty, Discipline, Pace};
+pub use error::{ApiError, ErrorCode, FieldError};
+pub use query::{Area, EventQuery, EventSort, EventTime, Page};
+pub use recurrence::{Frequency, Recurrence, Series};
+pub use route::{Bounds, Route, RoutePoint, RouteSummary};
 
 pub type Id = Uuid;
 
@@ -18,18 +30,541 @@ pub struct Event {
     pub id: Id,
     pub name: String,
     pub participants: HashSet<String>,
+    /// Username of the logged-in user who published the event, set by the backend.
+    #[serde(default)]
+    pub organizer: Option<String>,
+    #[serde(default)]
+    pub status: EventStatus,
+    /// Reason given by the organizer when cancelling the event.
+    #[serde(default)]
+    pub cancellation_reason: Option<String>,
+    /// Start of the ride in the timezone of the organizer, `None` for the events published before
+    /// the start was required.
+    #[serde(default)]
+    pub start: Option<DateTime<FixedOffset>>,
+    /// IANA timezone in which the ride takes place, `None` if the organizer did not give it.
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+    /// Expected end of the ride, if the organizer gave one.
+    #[serde(default)]
+    pub end: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub meeting_point: Option<MeetingPoint>,
+    /// Statistics of the route uploaded by the organizer, if any.
+    #[serde(default)]
+    pub route: Option<RouteSummary>,
+    /// Maximum number of participants, `None` if anyone can join.
+    #[serde(default)]
+    pub capacity: Option<u32>,
+    /// Users who joined the event while it was full, in the order they joined.
+    #[serde(default)]
+    pub waitlist: Vec<String>,
+    #[serde(default)]
+    pub discipline: Option<Discipline>,
+    #[serde(default)]
+    pub pace: Option<Pace>,
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// Whether the group waits for the slowest riders.
+    #[serde(default)]
+    pub no_drop: bool,
+    /// Series this event is an occurrence of, `None` for a one-off ride.
+    #[serde(default)]
+    pub series: Option<Series>,
+    /// When the event was published, `None` for the drafts and for the events published before
+    /// the date was recorded.
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    /// Number of times the organizer or the backend changed the event, for the calendar
+    /// applications to know which version of the event is the latest.
+    #[serde(default)]
+    pub revision: u32,
 }
 
 impl Event {
     pub fn new(name: String) -> Event {
         Event {
             id: Id::new_v4(),
-            name: name,
+            name,
             participants: HashSet::new(),
+            organizer: None,
+            status: EventStatus::Published,
+            cancellation_reason: None,
+            start: None,
+            timezone: None,
+            end: None,
+            meeting_point: None,
+            route: None,
+            capacity: None,
+            waitlist: vec![],
+            discipline: None,
+            pace: None,
+            difficulty: None,
+            no_drop: false,
+            series: None,
+            published_at: None,
+            revision: 0,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.participants.len() >= capacity as usize)
+    }
+
+    /// Whether `username` is going or waitlisted, `None` if the user did not join the event.
+    pub fn participation(&self, username: &str) -> Option<Participation> {
+        if self.participants.contains(username) {
+            return Some(Participation::Going);
+        }
+        self.waitlist
+            .iter()
+            .position(|waitlisted| waitlisted == username)
+            .map(|index| Participation::Waitlisted {
+                position: index + 1,
+            })
+    }
+
+    /// Add `username` to the participants, or at the end of the waitlist if the event is full.
+    /// Joining twice does not change anything.
+    pub fn join(&mut self, username: &str) -> Participation {
+        if let Some(participation) = self.participation(username) {
+            return participation;
+        }
+        if self.is_full() {
+            self.waitlist.push(username.to_owned());
+        } else {
+            self.participants.insert(username.to_owned());
+        }
+        self.participation(username).unwrap_or(Participation::Going)
+    }
+
+    /// Remove `username` from the participants or from the waitlist, returns the waitlisted users
+    /// promoted to participants. Nobody is promoted unless the event is published, the ride being
+    /// cancelled or over otherwise.
+    pub fn leave(&mut self, username: &str) -> Vec<String> {
+        self.participants.remove(username);
+        self.waitlist.retain(|waitlisted| waitlisted != username);
+        if self.status != EventStatus::Published {
+            return vec![];
+        }
+        self.promote_waitlisted()
+    }
+
+    /// Move the first waitlisted users to the participants while the event is not full, returns
+    /// the promoted users.
+    pub fn promote_waitlisted(&mut self) -> Vec<String> {
+        let mut promoted = vec![];
+        while !self.is_full() && !self.waitlist.is_empty() {
+            let username = self.waitlist.remove(0);
+            self.participants.insert(username.clone());
+            promoted.push(username);
+        }
+        promoted
+    }
+}
+
+/// How a user takes part in an event.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Participation {
+    Going,
+    /// The user will be going when enough participants leave.
+    Waitlisted {
+        /// Starts at 1 for the next user to be promoted.
+        position: usize,
+    },
+}
+
+impl Display for Participation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Participation::Going => write!(f, "going"),
+            Participation::Waitlisted { position } => {
+                write!(f, "waitlisted at position {}", position)
+            }
+        }
+    }
+}
+
+/// Body of the request publishing an event, only holds the fields chosen by the organizer. The
+/// backend generates the ID and fills in the other fields.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NewEvent {
+    pub name: String,
+    /// Either `Published` or `Draft`.
+    #[serde(default)]
+    pub status: EventStatus,
+    /// Must be in the future, the offset is the timezone in which the ride takes place.
+    pub start: DateTime<FixedOffset>,
+    /// IANA timezone of `start`, required to repeat the ride so that the occurrences follow the
+    /// changes of daylight saving time.
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+    /// Expected end, at most one of `end` and `duration_minutes` can be given.
+    #[serde(default)]
+    pub end: Option<DateTime<FixedOffset>>,
+    /// Expected duration, the backend turns it into the end of the event.
+    #[serde(default)]
+    pub duration_minutes: Option<u32>,
+    #[serde(default)]
+    pub meeting_point: Option<MeetingPoint>,
+    /// Maximum number of participants, the next users are waitlisted.
+    #[serde(default)]
+    pub capacity: Option<u32>,
+    #[serde(default)]
+    pub discipline: Option<Discipline>,
+    #[serde(default)]
+    pub pace: Option<Pace>,
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    #[serde(default)]
+    pub no_drop: bool,
+    /// Publish a series of events starting at `start` and repeating with this recurrence.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+}
+
+impl NewEvent {
+    pub fn new(name: impl Into<String>, start: DateTime<FixedOffset>) -> NewEvent {
+        NewEvent {
+            name: name.into(),
+            status: EventStatus::Published,
+            start,
+            timezone: None,
+            end: None,
+            duration_minutes: None,
+            meeting_point: None,
+            capacity: None,
+            discipline: None,
+            pace: None,
+            difficulty: None,
+            no_drop: false,
+            recurrence: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    /// Not announced yet, cannot be joined.
+    Draft,
+    #[default]
+    Published,
+    Cancelled,
+    /// The ride took place.
+    Completed,
+}
+
+impl EventStatus {
+    /// Whether an event can go from this status to `next`.
+    pub fn can_become(self, next: EventStatus) -> bool {
+        matches!(
+            (self, next),
+            (EventStatus::Draft, EventStatus::Published)
+                | (EventStatus::Draft, EventStatus::Cancelled)
+                | (EventStatus::Published, EventStatus::Cancelled)
+                | (EventStatus::Published, EventStatus::Completed)
+        )
+    }
+
+    pub fn can_be_joined(self) -> bool {
+        self == EventStatus::Published
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventStatus::Draft => "draft",
+            EventStatus::Published => "published",
+            EventStatus::Cancelled => "cancelled",
+            EventStatus::Completed => "completed",
+        }
+    }
+}
+
+impl Display for EventStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for EventStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<EventStatus, String> {
+        match value {
+            "draft" => Ok(EventStatus::Draft),
+            "published" => Ok(EventStatus::Published),
+            "cancelled" => Ok(EventStatus::Cancelled),
+            "completed" => Ok(EventStatus::Completed),
+            _ => Err(format!("unknown event status {}", value)),
         }
     }
 }
 
+/// Where the participants meet before the ride.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MeetingPoint {
+    pub address: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl MeetingPoint {
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+}
+
+/// Position on the Earth, in degrees.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// Mean radius of the Earth used to compute the distances.
+    pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+    /// Great-circle distance to `other`, computed with the haversine formula.
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let latitude = self.latitude.to_radians();
+        let other_latitude = other.latitude.to_radians();
+        let half_latitude_delta = (other_latitude - latitude) / 2.0;
+        let half_longitude_delta = (other.longitude - self.longitude).to_radians() / 2.0;
+        let haversine = half_latitude_delta.sin().powi(2)
+            + latitude.cos() * other_latitude.cos() * half_longitude_delta.sin().powi(2);
+        2.0 * Coordinates::EARTH_RADIUS_KM * haversine.sqrt().min(1.0).asin()
+    }
+}
+
+/// Sent by the organizer to cancel an event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Cancellation {
+    pub reason: String,
+}
+
+/// Changes made by the organizer to a published event, fields left to `None` are not modified.
+/// The optional attributes of the event are cleared by a `Some(None)`, sent as `null`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EventPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// New status of the event, e.g. to publish a draft. Cancelling goes through the dedicated
+    /// endpoint to give a reason.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<EventStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<FixedOffset>>,
+    #[serde(
+        default,
+        deserialize_with = "clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end: Option<Option<DateTime<FixedOffset>>>,
+    #[serde(
+        default,
+        deserialize_with = "clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub meeting_point: Option<Option<MeetingPoint>>,
+    /// Raising or removing the capacity promotes waitlisted users.
+    #[serde(
+        default,
+        deserialize_with = "clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub capacity: Option<Option<u32>>,
+    #[serde(
+        default,
+        deserialize_with = "clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub discipline: Option<Option<Discipline>>,
+    #[serde(
+        default,
+        deserialize_with = "clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pace: Option<Option<Pace>>,
+    #[serde(
+        default,
+        deserialize_with = "clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub difficulty: Option<Option<Difficulty>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_drop: Option<bool>,
+}
+
+/// Field of a patch present in the JSON, a `null` clearing the attribute rather than leaving it
+/// unmodified like a missing field.
+fn clearable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl EventPatch {
+    /// Patch containing the fields of `updated` that differ from `original`.
+    pub fn between(original: &Event, updated: &Event) -> EventPatch {
+        EventPatch {
+            name: (original.name != updated.name).then(|| updated.name.clone()),
+            status: (original.status != updated.status).then_some(updated.status),
+            start: (original.start != updated.start)
+                .then_some(updated.start)
+                .flatten(),
+            end: (original.end != updated.end).then_some(updated.end),
+            meeting_point: (original.meeting_point != updated.meeting_point)
+                .then(|| updated.meeting_point.clone()),
+            capacity: (original.capacity != updated.capacity).then_some(updated.capacity),
+            discipline: (original.discipline != updated.discipline).then_some(updated.discipline),
+            pace: (original.pace != updated.pace).then_some(updated.pace),
+            difficulty: (original.difficulty != updated.difficulty).then_some(updated.difficulty),
+            no_drop: (original.no_drop != updated.no_drop).then_some(updated.no_drop),
+        }
+    }
+
+    pub fn apply(&self, event: &mut Event) {
+        if let Some(name) = &self.name {
+            event.name = name.clone();
+        }
+        if let Some(status) = self.status {
+            event.status = status;
+        }
+        if let Some(start) = self.start {
+            event.start = Some(start);
+        }
+        if let Some(end) = self.end {
+            event.end = end;
+        }
+        if let Some(meeting_point) = &self.meeting_point {
+            event.meeting_point = meeting_point.clone();
+        }
+        if let Some(capacity) = self.capacity {
+            event.capacity = capacity;
+            event.promote_waitlisted();
+        }
+        if let Some(discipline) = self.discipline {
+            event.discipline = discipline;
+        }
+        if let Some(pace) = self.pace {
+            event.pace = pace;
+        }
+        if let Some(difficulty) = self.difficulty {
+            event.difficulty = difficulty;
+        }
+        if let Some(no_drop) = self.no_drop {
+            event.no_drop = no_drop;
+        }
+    }
+}
+
+/// Username and password typed by a user to sign up or to log in.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Returned by the backend when a user logs in, `token` authenticates the next requests.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Session {
+    pub username: String,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Personal calendar feed listing the events joined or organized by a user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CalendarFeed {
+    /// Path of the iCalendar feed on the backend, it contains a secret token so that calendar
+    /// applications can fetch it without logging in.
+    pub path: String,
+}
+
+/// Change of an event pushed by the backend to the clients following the events live, with the
+/// event as it is after the change.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventUpdate {
+    Created { event: Event },
+    Updated { event: Event },
+    Joined { event: Event, username: String },
+    Left { event: Event, username: String },
+}
+
+impl EventUpdate {
+    pub fn event(&self) -> &Event {
+        match self {
+            EventUpdate::Created { event }
+            | EventUpdate::Updated { event }
+            | EventUpdate::Joined { event, .. }
+            | EventUpdate::Left { event, .. } => event,
+        }
+    }
+
+    pub fn into_event(self) -> Event {
+        match self {
+            EventUpdate::Created { event }
+            | EventUpdate::Updated { event }
+            | EventUpdate::Joined { event, .. }
+            | EventUpdate::Left { event, .. } => event,
+        }
+    }
+}
+
+/// Comment posted by a user under an event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Comment {
+    #[serde(with = "uuid_codec")]
+    pub id: Id,
+    #[serde(with = "uuid_codec")]
+    pub event_id: Id,
+    /// Username of the logged-in user who posted the comment, set by the backend.
+    pub author: String,
+    pub text: String,
+    pub posted_at: DateTime<Utc>,
+    /// When the author last changed the text, `None` if they never did.
+    #[serde(default)]
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+/// Sent by a user to post a comment or to change the text of their comment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommentText {
+    pub text: String,
+}
+
+/// Address at which a user is notified of the changes of the events they joined.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EmailAddress {
+    pub email: String,
+}
+
+/// Item of the notification inbox of a user, e.g. a change of an event they joined.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Notification {
+    #[serde(with = "uuid_codec")]
+    pub id: Id,
+    pub text: String,
+    /// Event the notification is about, if any.
+    #[serde(default, with = "uuid_codec::option")]
+    pub event_id: Option<Id>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub read: bool,
+}
+
 mod uuid_codec {
     use serde::{
         de::{Error, Unexpected},
@@ -55,11 +590,42 @@ mod uuid_codec {
             Err(_) => Err(Error::invalid_value(Unexpected::Str(&value), &"UUID")),
         }
     }
+
+    /// Same encoding for optional UUIDs, `None` being `null`.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use uuid::Uuid;
+
+        pub fn serialize<S>(uuid: &Option<Uuid>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match uuid {
+                Some(uuid) => super::serialize(uuid, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Uuid>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super")] Uuid);
+
+            let value = Option::<Wrapper>::deserialize(deserializer)?;
+            Ok(value.map(|Wrapper(uuid)| uuid))
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::Event;
+    use crate::{
+        ApiError, Coordinates, ErrorCode, Event, EventPatch, Frequency, Participation, Recurrence,
+        Route, RoutePoint, Tz,
+    };
+    use chrono::DateTime;
 
     #[test]
     fn serializing_an_event() {
@@ -68,4 +634,201 @@ mod test {
         let deserialized: Event = serde_json::from_str(&json).unwrap();
         assert_eq!(event, deserialized);
     }
+
+    #[test]
+    fn serializing_an_api_error() {
+        let error = ApiError::new(ErrorCode::ExpiredToken, "expired");
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(json, r#"{"code":"expired_token","message":"expired"}"#);
+        let deserialized: ApiError = serde_json::from_str(&json).unwrap();
+        assert_eq!(error, deserialized);
+    }
+
+    #[test]
+    fn serializing_a_patch_clearing_a_field() {
+        let mut original = Event::new("name".to_owned());
+        original.capacity = Some(10);
+        let mut updated = original.clone();
+        updated.capacity = None;
+
+        let patch = EventPatch::between(&original, &updated);
+        let json = serde_json::to_string(&patch).unwrap();
+        assert_eq!(json, r#"{"capacity":null}"#);
+        let deserialized: EventPatch = serde_json::from_str(&json).unwrap();
+        assert_eq!(patch, deserialized);
+        assert_eq!(
+            serde_json::from_str::<EventPatch>("{}").unwrap(),
+            EventPatch::default()
+        );
+
+        deserialized.apply(&mut original);
+        assert_eq!(original.capacity, None);
+    }
+
+    #[test]
+    fn distance_between_coordinates() {
+        let paris = Coordinates {
+            latitude: 48.8566,
+            longitude: 2.3522,
+        };
+        let lyon = Coordinates {
+            latitude: 45.764,
+            longitude: 4.8357,
+        };
+        assert_eq!(paris.distance_km(&paris), 0.0);
+        assert!((paris.distance_km(&lyon) - 392.0).abs() < 1.0);
+        assert_eq!(paris.distance_km(&lyon), lyon.distance_km(&paris));
+    }
+
+    #[test]
+    fn summarizing_a_route() {
+        let point = |latitude, elevation| RoutePoint {
+            latitude,
+            longitude: 2.0,
+            elevation,
+        };
+        let route = Route {
+            segments: vec![
+                vec![point(48.0, Some(100.0)), point(48.1, Some(150.0))],
+                vec![
+                    point(48.5, Some(120.0)),
+                    point(48.6, None),
+                    point(48.7, Some(90.0)),
+                ],
+            ],
+        };
+        let summary = route.summary().unwrap();
+        // 0.1 degree of latitude is about 11.1 km, the gap between the segments is not counted
+        assert!((summary.distance_km - 3.0 * 11.12).abs() < 0.1);
+        assert_eq!(summary.elevation_gain_m, 50.0);
+        assert_eq!(summary.elevation_loss_m, 30.0);
+        assert_eq!(summary.bounds.min_latitude, 48.0);
+        assert_eq!(summary.bounds.max_latitude, 48.7);
+        assert_eq!(Route::default().summary(), None);
+    }
+
+    #[test]
+    fn joining_a_full_event_and_leaving_it() {
+        let mut event = Event::new("name".to_owned());
+        event.capacity = Some(1);
+        assert_eq!(event.join("alice"), Participation::Going);
+        assert_eq!(event.join("bob"), Participation::Waitlisted { position: 1 });
+        assert_eq!(
+            event.join("carol"),
+            Participation::Waitlisted { position: 2 }
+        );
+        assert_eq!(event.leave("alice"), vec!["bob".to_owned()]);
+        assert_eq!(event.participation("bob"), Some(Participation::Going));
+        assert_eq!(
+            event.participation("carol"),
+            Some(Participation::Waitlisted { position: 1 })
+        );
+        assert_eq!(event.participation("alice"), None);
+    }
+
+    #[test]
+    fn expanding_a_recurrence() {
+        let start = DateTime::parse_from_rfc3339("2030-01-31T18:30:00+01:00").unwrap();
+        let dates = |recurrence: &Recurrence| -> Vec<String> {
+            recurrence
+                .occurrences(start, Tz::Europe__Paris)
+                .iter()
+                .map(|occurrence| occurrence.format("%Y-%m-%d %H:%M").to_string())
+                .collect()
+        };
+
+        let weekly = Recurrence {
+            count: Some(3),
+            ..Recurrence::new(Frequency::Weekly)
+        };
+        assert_eq!(
+            dates(&weekly),
+            vec!["2030-01-31 18:30", "2030-02-07 18:30", "2030-02-14 18:30"]
+        );
+
+        // the months without a 31st are skipped, the rides keep their time after the clocks change
+        let monthly = Recurrence {
+            until: Some(DateTime::parse_from_rfc3339("2030-06-01T00:00:00+01:00").unwrap()),
+            ..Recurrence::new(Frequency::Monthly)
+        };
+        assert_eq!(
+            dates(&monthly),
+            vec!["2030-01-31 18:30", "2030-03-31 18:30", "2030-05-31 18:30"]
+        );
+
+        // without an end, the occurrences stop at the horizon
+        assert_eq!(
+            Recurrence::new(Frequency::Weekly)
+                .occurrences(start, Tz::Europe__Paris)
+                .len(),
+            53
+        );
+        assert_eq!(
+            Recurrence::new(Frequency::Monthly)
+                .occurrences(start, Tz::Europe__Paris)
+                .len(),
+            8
+        );
+
+        // an end after the horizon is reported, the occurrences stopping at the horizon
+        let weekly = Recurrence {
+            count: Some(54),
+            ..Recurrence::new(Frequency::Weekly)
+        };
+        assert!(weekly.ends_after_horizon(start, Tz::Europe__Paris));
+        assert_eq!(weekly.occurrences(start, Tz::Europe__Paris).len(), 53);
+        assert!(!Recurrence {
+            count: Some(53),
+            ..weekly
+        }
+        .ends_after_horizon(start, Tz::Europe__Paris));
+        assert!(!monthly.ends_after_horizon(start, Tz::Europe__Paris));
+        assert!(!Recurrence::new(Frequency::Weekly).ends_after_horizon(start, Tz::Europe__Paris));
+    }
+
+    #[test]
+    fn expanding_a_recurrence_across_daylight_saving_time() {
+        let weekly = Recurrence {
+            count: Some(3),
+            ..Recurrence::new(Frequency::Weekly)
+        };
+        let starts = |start: &str| -> Vec<String> {
+            weekly
+                .occurrences(
+                    DateTime::parse_from_rfc3339(start).unwrap(),
+                    Tz::Europe__Paris,
+                )
+                .iter()
+                .map(|occurrence| occurrence.to_rfc3339())
+                .collect()
+        };
+
+        // the clocks go forward on 2030-03-31 and back on 2030-10-27 in Paris
+        assert_eq!(
+            starts("2030-03-26T18:30:00+01:00"),
+            vec![
+                "2030-03-26T18:30:00+01:00",
+                "2030-04-02T18:30:00+02:00",
+                "2030-04-09T18:30:00+02:00"
+            ]
+        );
+        assert_eq!(
+            starts("2030-10-22T18:30:00+02:00"),
+            vec![
+                "2030-10-22T18:30:00+02:00",
+                "2030-10-29T18:30:00+01:00",
+                "2030-11-05T18:30:00+01:00"
+            ]
+        );
+
+        // a time skipped by the clocks is moved forward by the length of the gap
+        assert_eq!(
+            starts("2030-03-24T02:30:00+01:00"),
+            vec![
+                "2030-03-24T02:30:00+01:00",
+                "2030-03-31T03:30:00+02:00",
+                "2030-04-07T02:30:00+02:00"
+            ]
+        );
+    }
 }
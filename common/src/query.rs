@@ -0,0 +1,189 @@
+use crate::{Coordinates, Difficulty, Discipline, EventStatus, Id, Pace};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// Filters, sort order and position of a page of events, sent as the query parameters of
+/// `/api/events`. Fields left to `None` do not filter anything.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct EventQuery {
+    pub status: Option<EventStatus>,
+    pub organizer: Option<String>,
+    pub participant: Option<String>,
+    /// Only the events which name contains this text, ignoring the case.
+    pub text: Option<String>,
+    /// Only the events starting after or before the current time of the backend.
+    pub when: Option<EventTime>,
+    /// Only the events which meeting point is in this area.
+    pub near: Option<Area>,
+    pub discipline: Option<Discipline>,
+    /// Only the events at this pace or slower.
+    pub max_pace: Option<Pace>,
+    /// Only the events of this difficulty or easier.
+    pub max_difficulty: Option<Difficulty>,
+    /// Only the no-drop events if `true`, only the others if `false`.
+    pub no_drop: Option<bool>,
+    /// Only the occurrences of this series.
+    #[serde(default, with = "crate::uuid_codec::option")]
+    pub series: Option<Id>,
+    pub sort: EventSort,
+    /// `Page::next_cursor` of the previous page, `None` to get the first page.
+    pub cursor: Option<String>,
+    /// Maximum number of events in the page, the backend chooses if `None`.
+    pub limit: Option<usize>,
+}
+
+impl EventQuery {
+    /// Query parameters of the request, the fields left to `None` are omitted.
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("sort", self.sort.to_string())];
+        if let Some(status) = self.status {
+            params.push(("status", status.to_string()));
+        }
+        if let Some(organizer) = &self.organizer {
+            params.push(("organizer", organizer.clone()));
+        }
+        if let Some(participant) = &self.participant {
+            params.push(("participant", participant.clone()));
+        }
+        if let Some(text) = &self.text {
+            params.push(("text", text.clone()));
+        }
+        if let Some(when) = self.when {
+            params.push(("when", when.to_string()));
+        }
+        if let Some(near) = &self.near {
+            params.push(("latitude", near.center.latitude.to_string()));
+            params.push(("longitude", near.center.longitude.to_string()));
+            params.push(("radius_km", near.radius_km.to_string()));
+        }
+        if let Some(discipline) = self.discipline {
+            params.push(("discipline", discipline.to_string()));
+        }
+        if let Some(max_pace) = self.max_pace {
+            params.push(("max_pace", max_pace.to_string()));
+        }
+        if let Some(max_difficulty) = self.max_difficulty {
+            params.push(("max_difficulty", max_difficulty.to_string()));
+        }
+        if let Some(no_drop) = self.no_drop {
+            params.push(("no_drop", no_drop.to_string()));
+        }
+        if let Some(series) = self.series {
+            params.push(("series", series.to_string()));
+        }
+        if let Some(cursor) = &self.cursor {
+            params.push(("cursor", cursor.clone()));
+        }
+        if let Some(limit) = self.limit {
+            params.push(("limit", limit.to_string()));
+        }
+        params
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSort {
+    /// In publication order.
+    #[default]
+    Oldest,
+    /// In reverse publication order.
+    Newest,
+    /// In alphabetical order of the names.
+    Name,
+    /// In chronological order of the starts, the events without a start come last.
+    Start,
+    /// From the nearest to the farthest meeting point from the center of `EventQuery::near`.
+    Distance,
+}
+
+impl EventSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventSort::Oldest => "oldest",
+            EventSort::Newest => "newest",
+            EventSort::Name => "name",
+            EventSort::Start => "start",
+            EventSort::Distance => "distance",
+        }
+    }
+}
+
+impl Display for EventSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for EventSort {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<EventSort, String> {
+        match value {
+            "oldest" => Ok(EventSort::Oldest),
+            "newest" => Ok(EventSort::Newest),
+            "name" => Ok(EventSort::Name),
+            "start" => Ok(EventSort::Start),
+            "distance" => Ok(EventSort::Distance),
+            _ => Err(format!("unknown sort order {}", value)),
+        }
+    }
+}
+
+/// Disc around a point, distances being measured along the surface of the Earth.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub center: Coordinates,
+    pub radius_km: f64,
+}
+
+impl Area {
+    pub fn contains(&self, coordinates: &Coordinates) -> bool {
+        self.center.distance_km(coordinates) <= self.radius_km
+    }
+}
+
+/// Position of the events relative to the current time, based on their start.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTime {
+    /// Not started yet.
+    Upcoming,
+    /// Already started.
+    Past,
+}
+
+impl EventTime {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventTime::Upcoming => "upcoming",
+            EventTime::Past => "past",
+        }
+    }
+}
+
+impl Display for EventTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for EventTime {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<EventTime, String> {
+        match value {
+            "upcoming" => Ok(EventTime::Upcoming),
+            "past" => Ok(EventTime::Past),
+            _ => Err(format!("unknown event time {}", value)),
+        }
+    }
+}
+
+/// Part of a list of items, the next part is requested with `next_cursor`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// `None` if this is the last page.
+    pub next_cursor: Option<String>,
+}
@@ -0,0 +1,150 @@
+use crate::Id;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, Months, NaiveDateTime, Offset, TimeZone,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// Series of events repeating the same ride, each occurrence being an event of its own.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Series {
+    #[serde(with = "crate::uuid_codec")]
+    pub id: Id,
+    pub recurrence: Recurrence,
+}
+
+/// When a ride repeats, starting from the first occurrence.
+///
+/// A series lasts at most `HORIZON_MONTHS`: a series without an end stops there, and the backend
+/// refuses an `until` or a `count` going beyond it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// No occurrence starts after this date.
+    #[serde(default)]
+    pub until: Option<DateTime<FixedOffset>>,
+    /// Maximum number of occurrences, the first one included.
+    #[serde(default)]
+    pub count: Option<u32>,
+}
+
+impl Recurrence {
+    /// Occurrences are only created up to this number of months after the first one, so that
+    /// series without an end stay bounded.
+    pub const HORIZON_MONTHS: u32 = 12;
+
+    pub fn new(frequency: Frequency) -> Recurrence {
+        Recurrence {
+            frequency,
+            until: None,
+            count: None,
+        }
+    }
+
+    /// Starts of the occurrences of a series which first occurrence starts at `start`, the ride
+    /// taking place in `timezone`.
+    ///
+    /// The rule is followed in the local time of `timezone`, so that the rides keep their time of
+    /// the day when the clocks change, each occurrence getting the offset in force at its date.
+    /// Monthly occurrences happen on the same day of the month, the months without this day are
+    /// skipped.
+    pub fn occurrences(
+        &self,
+        start: DateTime<FixedOffset>,
+        timezone: Tz,
+    ) -> Vec<DateTime<FixedOffset>> {
+        let horizon = Recurrence::horizon(start);
+        self.starts(start, timezone)
+            .take_while(|occurrence| *occurrence <= horizon)
+            .collect()
+    }
+
+    /// Whether the end given by `until` or `count` comes after the horizon, some occurrences
+    /// then being left out of `occurrences`.
+    pub fn ends_after_horizon(&self, start: DateTime<FixedOffset>, timezone: Tz) -> bool {
+        let horizon = Recurrence::horizon(start);
+        (self.until.is_some() || self.count.is_some())
+            && self
+                .starts(start, timezone)
+                .any(|occurrence| occurrence > horizon)
+    }
+
+    /// Last possible start of an occurrence of a series starting at `start`.
+    pub fn horizon(start: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        start + Months::new(Recurrence::HORIZON_MONTHS)
+    }
+
+    /// Starts of the occurrences until the end of the series, which is never reached without
+    /// `until` or `count`.
+    fn starts(
+        &self,
+        start: DateTime<FixedOffset>,
+        timezone: Tz,
+    ) -> impl Iterator<Item = DateTime<FixedOffset>> + '_ {
+        let local_start = start.with_timezone(&timezone).naive_local();
+        (0u32..)
+            .filter_map(move |index| match self.frequency {
+                Frequency::Weekly => Some(local_start + Duration::weeks(index.into())),
+                Frequency::Monthly => local_start
+                    .checked_add_months(Months::new(index))
+                    .filter(|occurrence| occurrence.day() == local_start.day()),
+            })
+            .map(move |occurrence| local_to_fixed(occurrence, timezone))
+            .take_while(|occurrence| self.until.is_none_or(|until| *occurrence <= until))
+            .take(self.count.map_or(usize::MAX, |count| count as usize))
+    }
+}
+
+/// `local` time in `timezone`. The earliest of the two instants is taken when the clocks go back,
+/// and a time skipped when the clocks go forward is moved forward by the length of the gap.
+fn local_to_fixed(local: NaiveDateTime, timezone: Tz) -> DateTime<FixedOffset> {
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => {
+            datetime.fixed_offset()
+        }
+        LocalResult::None => {
+            // the offset in force before the gap, no gap lasting more than a day
+            let offset = timezone
+                .offset_from_utc_datetime(&(local - Duration::days(1)))
+                .fix();
+            timezone
+                .from_utc_datetime(&(local - Duration::seconds(offset.local_minus_utc().into())))
+                .fixed_offset()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Frequency::Weekly => "weekly",
+            Frequency::Monthly => "monthly",
+        }
+    }
+}
+
+impl Display for Frequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Frequency, String> {
+        match value {
+            "weekly" => Ok(Frequency::Weekly),
+            "monthly" => Ok(Frequency::Monthly),
+            _ => Err(format!("unknown frequency {}", value)),
+        }
+    }
+}
@@ -1,82 +1,387 @@
-use crate::{json::parse_json, api::BackendApi, Event, Id};
+use crate::{
+    api::BackendApi, json::parse_json, ApiError, CalendarFeed, Cancellation, Comment, CommentText,
+    Credentials, EmailAddress, ErrorCode, Event, EventPatch, EventQuery, EventUpdate, Id, NewEvent,
+    Notification, Page, Participation, Route, Session,
+};
 use async_trait::async_trait;
-use gloo_net::http::Request;
+use futures::StreamExt;
+use gloo_net::{
+    eventsource::{
+        futures::{EventSource, EventSourceSubscription},
+        State,
+    },
+    http::Request,
+};
+use std::{cell::RefCell, pin::Pin};
 
-pub struct RestBackend {}
+#[derive(Default)]
+pub struct RestBackend {
+    /// Bearer token of the logged-in user, sent with the requests requiring authentication.
+    token: RefCell<Option<String>>,
+    /// Connection to the stream of updates, taken while waiting for the next update.
+    updates: RefCell<Option<Updates>>,
+}
+
+/// Connection to the server-sent events of `/api/events/updates`.
+struct Updates {
+    source: EventSource,
+    messages: Pin<Box<EventSourceSubscription>>,
+}
+
+impl Updates {
+    fn open() -> Result<Updates, ApiError> {
+        let mut source = EventSource::new("/api/events/updates")
+            .map_err(|error| ApiError::new(ErrorCode::Network, error.to_string()))?;
+        let messages = source
+            .subscribe("message")
+            .map_err(|error| ApiError::new(ErrorCode::Network, error.to_string()))?;
+        Ok(Updates {
+            source,
+            messages: Box::pin(messages),
+        })
+    }
+}
+
+impl RestBackend {
+    pub fn new() -> RestBackend {
+        RestBackend::default()
+    }
+
+    fn authorization(&self) -> Option<String> {
+        self.token
+            .borrow()
+            .as_ref()
+            .map(|token| format!("Bearer {}", token))
+    }
+}
 
 #[async_trait(?Send)]
 impl BackendApi for RestBackend {
-    async fn get_events(self: &Self) -> Result<Vec<Event>, String> {
-        get_json::<Vec<Event>>("/api/events").await
+    async fn get_events(&self, query: EventQuery) -> Result<Page<Event>, ApiError> {
+        // the organizer is sent their drafts when requesting them
+        let mut request = Request::new("/api/events")
+            .query(query.params().iter().map(|(name, value)| (*name, value)));
+        if let Some(authorization) = self.authorization() {
+            request = request.header("Authorization", &authorization);
+        }
+        let response = request.send().await.map_err(network_error)?;
+        parse_json(&text(check_is_ok(response).await?).await?)
+    }
+    async fn get_event(&self, id: Id) -> Result<Event, ApiError> {
+        let response = send(
+            gloo_net::http::Method::GET,
+            &format!("/api/event/{}", id),
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn next_event_update(&self) -> Result<EventUpdate, ApiError> {
+        let mut updates = match self.updates.take() {
+            Some(updates) => updates,
+            None => Updates::open()?,
+        };
+        loop {
+            match updates.messages.next().await {
+                Some(Ok((_, message))) => {
+                    self.updates.replace(Some(updates));
+                    return parse_json(&message.data().as_string().unwrap_or_default());
+                }
+                // the browser reconnects by itself after a connection error
+                Some(Err(_)) if matches!(updates.source.state(), State::Connecting) => {}
+                // the connection is dropped, the next call opens a new one
+                Some(Err(error)) => {
+                    return Err(ApiError::new(ErrorCode::Network, error.to_string()))
+                }
+                None => {
+                    return Err(ApiError::new(
+                        ErrorCode::Network,
+                        "the stream of updates was closed",
+                    ))
+                }
+            }
+        }
+    }
+    async fn publish_event(&self, event: NewEvent) -> Result<Event, ApiError> {
+        let response = send_json(
+            gloo_net::http::Method::POST,
+            "/api/events",
+            &event,
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn update_event(&self, id: Id, patch: EventPatch) -> Result<Event, ApiError> {
+        let response = send_json(
+            gloo_net::http::Method::PATCH,
+            &format!("/api/event/{}", id),
+            &patch,
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn cancel_event(&self, id: Id, reason: String) -> Result<Event, ApiError> {
+        let response = send_json(
+            gloo_net::http::Method::PUT,
+            &format!("/api/event/{}/cancel", id),
+            &Cancellation { reason },
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn get_route(&self, id: Id) -> Result<Route, ApiError> {
+        let response = send(
+            gloo_net::http::Method::GET,
+            &format!("/api/event/{}/route", id),
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn upload_route(&self, id: Id, gpx: String) -> Result<Event, ApiError> {
+        let mut request = Request::new(&format!("/api/event/{}/route", id))
+            .method(gloo_net::http::Method::PUT)
+            .header("Content-Type", "application/gpx+xml");
+        if let Some(authorization) = self.authorization() {
+            request = request.header("Authorization", &authorization);
+        }
+        let response = request.body(gpx).send().await.map_err(network_error)?;
+        parse_json(&text(check_is_ok(response).await?).await?)
+    }
+    async fn join_event(&self, id: Id) -> Result<Participation, ApiError> {
+        let response = send(
+            gloo_net::http::Method::PUT,
+            &format!("/api/join/{}", id),
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn leave_event(&self, id: Id) -> Result<(), ApiError> {
+        put(&format!("/api/leave/{}", id), self.authorization()).await
+    }
+    async fn get_comments(
+        &self,
+        event_id: Id,
+        cursor: Option<String>,
+    ) -> Result<Page<Comment>, ApiError> {
+        // the organizer is sent the comments of their drafts
+        let mut request = Request::new(&format!("/api/event/{}/comments", event_id))
+            .query(cursor.iter().map(|cursor| ("cursor", cursor)));
+        if let Some(authorization) = self.authorization() {
+            request = request.header("Authorization", &authorization);
+        }
+        let response = request.send().await.map_err(network_error)?;
+        parse_json(&text(check_is_ok(response).await?).await?)
+    }
+    async fn post_comment(&self, event_id: Id, comment_text: String) -> Result<Comment, ApiError> {
+        let response = send_json(
+            gloo_net::http::Method::POST,
+            &format!("/api/event/{}/comments", event_id),
+            &CommentText { text: comment_text },
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn edit_comment(&self, id: Id, comment_text: String) -> Result<Comment, ApiError> {
+        let response = send_json(
+            gloo_net::http::Method::PUT,
+            &format!("/api/comment/{}", id),
+            &CommentText { text: comment_text },
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn delete_comment(&self, id: Id) -> Result<(), ApiError> {
+        delete(&format!("/api/comment/{}", id), self.authorization()).await
+    }
+    async fn get_calendar_feed(&self) -> Result<CalendarFeed, ApiError> {
+        let response = send(
+            gloo_net::http::Method::GET,
+            "/api/calendar",
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn reset_calendar_feed(&self) -> Result<CalendarFeed, ApiError> {
+        let response = send(
+            gloo_net::http::Method::POST,
+            "/api/calendar/reset",
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
+    }
+    async fn set_email(&self, email: String) -> Result<(), ApiError> {
+        send_json(
+            gloo_net::http::Method::PUT,
+            "/api/account/email",
+            &EmailAddress { email },
+            self.authorization(),
+        )
+        .await?;
+        Ok(())
+    }
+    async fn get_notifications(&self) -> Result<Vec<Notification>, ApiError> {
+        let response = send(
+            gloo_net::http::Method::GET,
+            "/api/notifications",
+            self.authorization(),
+        )
+        .await?;
+        parse_json(&text(response).await?)
     }
-    async fn get_event(self: &Self, id: Id) -> Result<Event, String> {
-        get_json::<Event>(&format!("/api/event/{}", id)).await
+    async fn mark_notification_read(&self, id: Id) -> Result<(), ApiError> {
+        put(
+            &format!("/api/notification/{}/read", id),
+            self.authorization(),
+        )
+        .await
+    }
+    async fn mark_all_notifications_read(&self) -> Result<(), ApiError> {
+        put("/api/notifications/read", self.authorization()).await
+    }
+    async fn signup(&self, credentials: Credentials) -> Result<(), ApiError> {
+        post_json("/api/signup", &credentials).await?;
+        Ok(())
+    }
+    async fn login(&self, credentials: Credentials) -> Result<Session, ApiError> {
+        let response = post_json("/api/login", &credentials).await?;
+        let session: Session = parse_json(&text(response).await?)?;
+        self.token.replace(Some(session.token.clone()));
+        Ok(session)
     }
-    async fn publish_event(self: &Self, name: String) -> Result<(), String> {
-        let event = Event::new(name);
-        put_json("/api/event", &event).await
+    async fn logout(&self) -> Result<(), ApiError> {
+        let result = delete("/api/session", self.authorization()).await;
+        self.token.replace(None);
+        result
     }
-    async fn join_event(self: &Self, id: Id) -> Result<(), String> {
-        put(&format!("/api/join/{}", id)).await
+    async fn refresh_session(&self) -> Result<Session, ApiError> {
+        let response = send(
+            gloo_net::http::Method::POST,
+            "/api/session/refresh",
+            self.authorization(),
+        )
+        .await?;
+        let session: Session = parse_json(&text(response).await?)?;
+        self.token.replace(Some(session.token.clone()));
+        Ok(session)
     }
 }
 
-fn check_is_ok(response: gloo_net::http::Response) -> Result<gloo_net::http::Response, String> {
+/// Turn a failed response into the `ApiError` sent by the server.
+async fn check_is_ok(
+    response: gloo_net::http::Response,
+) -> Result<gloo_net::http::Response, ApiError> {
     if response.ok() {
         Ok(response)
     } else {
-        let error = format!("server responded with code {}", response.status());
-        Err(error)
+        let status = response.status();
+        let body = text(response).await?;
+        Err(parse_json::<ApiError>(&body).unwrap_or_else(|_| {
+            ApiError::new(
+                ErrorCode::from_status(status),
+                format!("server responded with code {}", status),
+            )
+        }))
     }
 }
 
-async fn text(response: gloo_net::http::Response) -> Result<String, String> {
+fn network_error(error: gloo_net::Error) -> ApiError {
+    ApiError::new(
+        ErrorCode::Network,
+        format!("could not send request {}", error),
+    )
+}
+
+async fn text(response: gloo_net::http::Response) -> Result<String, ApiError> {
     response.text().await.map_err(|error| {
-        let error = format!(
-            "cannot get text from response {}\n{:?}",
-            error.to_string(),
-            response
-        );
-        error
+        let error = format!("cannot get text from response {}\n{:?}", error, response);
+        ApiError::new(ErrorCode::Network, error)
     })
 }
 
-async fn get(url: &str) -> Result<gloo_net::http::Response, String> {
+async fn get(url: &str) -> Result<gloo_net::http::Response, ApiError> {
     let request = Request::new(url);
-    request.send().await.map_err(|error| {
-        let error = format!("could not send request {}", error.to_string());
-        error
-    })
+    request.send().await.map_err(network_error)
 }
 
-pub async fn get_json<T>(url: &str) -> Result<T, String>
+pub async fn get_json<T>(url: &str) -> Result<T, ApiError>
 where
     T: for<'a> serde::de::Deserialize<'a>,
 {
-    let response = get(url).await.and_then(check_is_ok)?;
+    let response = check_is_ok(get(url).await?).await?;
     parse_json(&text(response).await?)
 }
 
-pub async fn put(url: &str) -> Result<(), String> {
-    let request = Request::new(url)
-        .method(gloo_net::http::Method::PUT)
-        .header("Authorization", "Bearer valid_token");
-    request
-        .send()
-        .await
-        .map_err(|error| error.to_string())
-        .and_then(check_is_ok)?;
+/// Send a request without body, authenticated with the `authorization` header if any.
+async fn send(
+    method: gloo_net::http::Method,
+    url: &str,
+    authorization: Option<String>,
+) -> Result<gloo_net::http::Response, ApiError> {
+    let mut request = Request::new(url).method(method);
+    if let Some(authorization) = authorization {
+        request = request.header("Authorization", &authorization);
+    }
+    let response = request.send().await.map_err(network_error)?;
+    check_is_ok(response).await
+}
+
+pub async fn put(url: &str, authorization: Option<String>) -> Result<(), ApiError> {
+    send(gloo_net::http::Method::PUT, url, authorization).await?;
+    Ok(())
+}
+
+pub async fn delete(url: &str, authorization: Option<String>) -> Result<(), ApiError> {
+    send(gloo_net::http::Method::DELETE, url, authorization).await?;
     Ok(())
 }
 
-pub async fn put_json<T>(url: &str, value: &T) -> Result<(), String>
+/// Send a request with a JSON body, authenticated with the `authorization` header if any.
+async fn send_json<T>(
+    method: gloo_net::http::Method,
+    url: &str,
+    value: &T,
+    authorization: Option<String>,
+) -> Result<gloo_net::http::Response, ApiError>
 where
     T: serde::Serialize,
 {
-    let request = Request::new(url)
-        .method(gloo_net::http::Method::PUT)
+    let mut request = Request::new(url).method(method);
+    if let Some(authorization) = authorization {
+        request = request.header("Authorization", &authorization);
+    }
+    let response = request
         .json(value)
-        .map_err(|error| error.to_string())?;
-    request.send().await.map_err(|error| error.to_string())?;
+        .map_err(network_error)?
+        .send()
+        .await
+        .map_err(network_error)?;
+    check_is_ok(response).await
+}
+
+pub async fn put_json<T>(
+    url: &str,
+    value: &T,
+    authorization: Option<String>,
+) -> Result<(), ApiError>
+where
+    T: serde::Serialize,
+{
+    send_json(gloo_net::http::Method::PUT, url, value, authorization).await?;
     Ok(())
 }
+
+pub async fn post_json<T>(url: &str, value: &T) -> Result<gloo_net::http::Response, ApiError>
+where
+    T: serde::Serialize,
+{
+    send_json(gloo_net::http::Method::POST, url, value, None).await
+}
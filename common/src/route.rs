@@ -0,0 +1,92 @@
+use crate::Coordinates;
+use serde::{Deserialize, Serialize};
+
+/// Planned route of a ride, parsed from the GPX file uploaded by the organizer.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Route {
+    /// Tracks segments and routes of the file, in the order of the file. The route is not
+    /// continuous between two segments.
+    pub segments: Vec<Vec<RoutePoint>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RoutePoint {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Elevation in meters, GPX files do not always have one.
+    #[serde(default)]
+    pub elevation: Option<f64>,
+}
+
+impl RoutePoint {
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+}
+
+/// Statistics of a route, sent with the event instead of the whole route.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RouteSummary {
+    pub distance_km: f64,
+    /// Sum of the climbs in meters, ignoring the points without elevation.
+    pub elevation_gain_m: f64,
+    /// Sum of the descents in meters, ignoring the points without elevation.
+    pub elevation_loss_m: f64,
+    pub bounds: Bounds,
+}
+
+/// Smallest rectangle of latitudes and longitudes containing the route.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_latitude: f64,
+    pub min_longitude: f64,
+    pub max_latitude: f64,
+    pub max_longitude: f64,
+}
+
+impl Route {
+    pub fn points(&self) -> impl Iterator<Item = &RoutePoint> {
+        self.segments.iter().flatten()
+    }
+
+    /// `None` if the route has no point.
+    pub fn summary(&self) -> Option<RouteSummary> {
+        let first = self.points().next()?;
+        let mut summary = RouteSummary {
+            distance_km: 0.0,
+            elevation_gain_m: 0.0,
+            elevation_loss_m: 0.0,
+            bounds: Bounds {
+                min_latitude: first.latitude,
+                min_longitude: first.longitude,
+                max_latitude: first.latitude,
+                max_longitude: first.longitude,
+            },
+        };
+        for point in self.points() {
+            let bounds = &mut summary.bounds;
+            bounds.min_latitude = bounds.min_latitude.min(point.latitude);
+            bounds.min_longitude = bounds.min_longitude.min(point.longitude);
+            bounds.max_latitude = bounds.max_latitude.max(point.latitude);
+            bounds.max_longitude = bounds.max_longitude.max(point.longitude);
+        }
+        for segment in &self.segments {
+            for pair in segment.windows(2) {
+                summary.distance_km += pair[0].coordinates().distance_km(&pair[1].coordinates());
+            }
+            let elevations: Vec<f64> = segment.iter().filter_map(|point| point.elevation).collect();
+            for pair in elevations.windows(2) {
+                let climb = pair[1] - pair[0];
+                if climb > 0.0 {
+                    summary.elevation_gain_m += climb;
+                } else {
+                    summary.elevation_loss_m -= climb;
+                }
+            }
+        }
+        Some(summary)
+    }
+}
@@ -1,10 +1,10 @@
 use std::rc::Rc;
 
 use crate::{
-    orders::{IMyOrders, MyOrders, OrdersImplementation},
+    orders::{perform_cmd, IMyOrders, MyOrders, OrdersImplementation},
     pages,
 };
-use common::{api::BackendApi, rest::RestBackend};
+use common::{api::BackendApi, rest::RestBackend, ApiError, EventUpdate};
 use seed::{
     app::OrdersContainer,
     prelude::{subs::UrlChanged, *},
@@ -16,10 +16,18 @@ pub fn init(url: Url, orders: &mut OrdersContainer<Msg, Model, Node<Msg>>) -> Mo
             Msg::UrlChanged(msg) => Msg::UrlChanged(msg),
             Msg::Main(msg) => Msg::Main(msg),
             Msg::Event(msg) => Msg::Event(msg),
+            Msg::EditEvent(msg) => Msg::EditEvent(msg),
             Msg::Login(msg) => Msg::Login(msg),
+            Msg::Notifications(msg) => Msg::Notifications(msg),
+            Msg::OnLiveUpdate(update) => Msg::OnLiveUpdate(update),
+            Msg::LiveUpdatesFailed(error) => Msg::LiveUpdatesFailed(error),
+            Msg::EventUpdated(update) => Msg::EventUpdated(update),
         },
     )));
-    testable_init(url, &mut my_orders, Rc::new(RestBackend {}))
+    let backend: Rc<dyn BackendApi> = Rc::new(RestBackend::new());
+    let model = testable_init(url, &mut my_orders, backend.clone());
+    follow_events_live(&mut my_orders, backend);
+    model
 }
 
 pub fn testable_init(
@@ -28,22 +36,29 @@ pub fn testable_init(
     backend: Rc<dyn BackendApi>,
 ) -> Model {
     orders.subscribe(Msg::UrlChanged);
+    orders.subscribe(|update: EventUpdate| Msg::EventUpdated(Box::new(update)));
     let context = Context {
         username: None,
-        backend: backend,
+        backend,
     };
     let current_url = url.clone();
     Model {
         page: page_from_url(&mut url, None, &context, orders),
-        current_url: current_url,
-        context: context,
+        current_url,
+        context,
+        live_updates_failures: 0,
     }
 }
 
 fn parse_url(url: &mut Url) -> Route {
     match url.next_path_part() {
-        Some("event") => Route::Event,
+        // the edit page of an event is at /event/<id>/edit
+        Some("event") => match url.path().get(2).map(String::as_str) {
+            Some("edit") => Route::EditEvent,
+            _ => Route::Event,
+        },
         Some("login") => Route::Login,
+        Some("notifications") => Route::Notifications,
         _ => Route::Main,
     }
 }
@@ -65,19 +80,31 @@ fn page_from_url(
             context,
             &mut orders.proxy(Msg::Event),
         )),
+        Route::EditEvent => Page::EditEvent(pages::edit_event::init(
+            url,
+            context,
+            &mut orders.proxy(Msg::EditEvent),
+        )),
         Route::Login => Page::Login(pages::login::init(
             url,
             previous_url,
             context,
             &mut orders.proxy(Msg::Login),
         )),
+        Route::Notifications => Page::Notifications(pages::notifications::init(
+            url,
+            context,
+            &mut orders.proxy(Msg::Notifications),
+        )),
     }
 }
 
 pub enum Page {
     Main(pages::main::Model),
     Event(pages::event::Model),
+    EditEvent(pages::edit_event::Model),
     Login(pages::login::Model),
+    Notifications(pages::notifications::Model),
 }
 
 pub struct Context {
@@ -89,6 +116,9 @@ pub struct Model {
     pub page: Page,
     pub current_url: Url,
     pub context: Context,
+    /// Number of times in a row following the events live failed, to wait longer before each
+    /// new attempt.
+    live_updates_failures: u32,
 }
 
 #[derive(Clone, Debug)]
@@ -96,13 +126,22 @@ pub enum Msg {
     UrlChanged(subs::UrlChanged),
     Main(pages::main::Msg),
     Event(pages::event::Msg),
+    EditEvent(pages::edit_event::Msg),
     Login(pages::login::Msg),
+    Notifications(pages::notifications::Msg),
+    /// Change of an event received from the backend.
+    OnLiveUpdate(Box<EventUpdate>),
+    LiveUpdatesFailed(ApiError),
+    /// Change of an event notified to the subscribers, to show it on the current page.
+    EventUpdated(Box<EventUpdate>),
 }
 
 enum Route {
     Main,
     Event,
+    EditEvent,
     Login,
+    Notifications,
 }
 
 pub fn update(msg: Msg, model: &mut Model, orders: &mut OrdersContainer<Msg, Model, Node<Msg>>) {
@@ -111,7 +150,12 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut OrdersContainer<Msg, Mod
             Msg::UrlChanged(msg) => Msg::UrlChanged(msg),
             Msg::Main(msg) => Msg::Main(msg),
             Msg::Event(msg) => Msg::Event(msg),
+            Msg::EditEvent(msg) => Msg::EditEvent(msg),
             Msg::Login(msg) => Msg::Login(msg),
+            Msg::Notifications(msg) => Msg::Notifications(msg),
+            Msg::OnLiveUpdate(update) => Msg::OnLiveUpdate(update),
+            Msg::LiveUpdatesFailed(error) => Msg::LiveUpdatesFailed(error),
+            Msg::EventUpdated(update) => Msg::EventUpdated(update),
         },
     )));
     testable_update(msg, model, &mut my_orders);
@@ -119,6 +163,35 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut OrdersContainer<Msg, Mod
 
 pub fn testable_update(msg: Msg, model: &mut Model, orders: &mut impl IMyOrders<Msg>) {
     match msg {
+        Msg::OnLiveUpdate(update) => {
+            model.live_updates_failures = 0;
+            orders.notify(*update);
+            follow_events_live(orders, model.context.backend.clone());
+        }
+        // the connection is lost, e.g. the network is down or the server restarts
+        Msg::LiveUpdatesFailed(_) => {
+            let delay_ms = live_updates_retry_delay(model.live_updates_failures);
+            model.live_updates_failures += 1;
+            let backend = model.context.backend.clone();
+            orders.perform_cmd_after(delay_ms, next_event_update(backend));
+        }
+        Msg::EventUpdated(update) => match &mut model.page {
+            Page::Main(main_model) => pages::main::update(
+                pages::main::Msg::EventUpdated(update),
+                main_model,
+                &mut model.context,
+                &mut orders.proxy(Msg::Main),
+            ),
+            Page::Event(event_model) => pages::event::update(
+                pages::event::Msg::EventUpdated(update),
+                event_model,
+                &mut model.context,
+                &mut orders.proxy(Msg::Event),
+            ),
+            Page::EditEvent(_) | Page::Login(_) | Page::Notifications(_) => {
+                /* nothing to update */
+            }
+        },
         Msg::UrlChanged(url_changed) => {
             let mut new_url = url_changed.0;
             let previous_url = Some(model.current_url.clone());
@@ -151,6 +224,24 @@ pub fn testable_update(msg: Msg, model: &mut Model, orders: &mut impl IMyOrders<
                 );
             }
         }
+        Msg::EditEvent(edit_event_msg) => {
+            if let Page::EditEvent(edit_event_model) = &mut model.page {
+                match edit_event_msg {
+                    pages::edit_event::Msg::Public(msg) => match msg {
+                        pages::edit_event::PublicMsg::EventUpdated(id) => change_url(
+                            Url::new().set_path(&["event".to_owned(), id.to_string()]),
+                            orders,
+                        ),
+                    },
+                    pages::edit_event::Msg::Private(msg) => pages::edit_event::update(
+                        msg,
+                        edit_event_model,
+                        &mut model.context,
+                        &mut orders.proxy(Msg::EditEvent),
+                    ),
+                }
+            }
+        }
         Msg::Login(login_msg) => {
             if let Page::Login(login_model) = &mut model.page {
                 match login_msg {
@@ -174,6 +265,16 @@ pub fn testable_update(msg: Msg, model: &mut Model, orders: &mut impl IMyOrders<
                 }
             }
         }
+        Msg::Notifications(notifications_msg) => {
+            if let Page::Notifications(notifications_model) = &mut model.page {
+                pages::notifications::update(
+                    notifications_msg,
+                    notifications_model,
+                    &mut model.context,
+                    &mut orders.proxy(Msg::Notifications),
+                );
+            }
+        }
     }
 }
 
@@ -181,10 +282,42 @@ pub fn view(model: &Model) -> Node<Msg> {
     match &model.page {
         Page::Main(model) => pages::main::view(model).map_msg(Msg::Main),
         Page::Event(model) => pages::event::view(model).map_msg(Msg::Event),
+        Page::EditEvent(model) => pages::edit_event::view(model).map_msg(Msg::EditEvent),
         Page::Login(model) => pages::login::view(model).map_msg(Msg::Login),
+        Page::Notifications(model) => pages::notifications::view(model).map_msg(Msg::Notifications),
+    }
+}
+
+/// Forget the session refused by the backend and go to the login page, which brings the user
+/// back to the current page once logged in.
+pub fn redirect_to_login<Ms>(context: &mut Context, orders: &mut impl IMyOrders<Ms>) {
+    context.username = None;
+    orders.notify(UrlChanged(Url::new().set_path(["login"])));
+}
+
+/// Wait for the next change of an event, which is notified to the subscribers of `EventUpdate`
+/// before waiting for the following one.
+fn follow_events_live(orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, next_event_update(backend));
+}
+
+async fn next_event_update(backend: Rc<dyn BackendApi>) -> Msg {
+    match backend.next_event_update().await {
+        Ok(update) => Msg::OnLiveUpdate(Box::new(update)),
+        Err(error) => Msg::LiveUpdatesFailed(error),
     }
 }
 
+/// Delay before following the events live again after `failures` failures in a row, doubling
+/// from a second up to a minute.
+fn live_updates_retry_delay(failures: u32) -> u32 {
+    const FIRST_DELAY_MS: u32 = 1000;
+    const MAX_DELAY_MS: u32 = 60_000;
+    FIRST_DELAY_MS
+        .saturating_mul(2u32.saturating_pow(failures))
+        .min(MAX_DELAY_MS)
+}
+
 fn change_url(url: Url, orders: &mut impl IMyOrders<Msg>) {
     // TODO: update address bar
     orders.notify(UrlChanged(url));
@@ -1,7 +1,7 @@
 use seed::{prelude::*, *};
 
 pub fn init(name: String) -> Model {
-    Model { name: name }
+    Model { name }
 }
 
 pub struct Model {
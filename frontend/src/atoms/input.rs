@@ -1,14 +1,21 @@
 use seed::{prelude::*, *};
 
 pub fn init(placeholder: String) -> Model {
+    init_with_type(placeholder, "text")
+}
+
+/// Create an input of the given html type, e.g. "password" or "date".
+pub fn init_with_type(placeholder: String, input_type: &str) -> Model {
     Model {
-        placeholder: placeholder,
+        placeholder,
+        input_type: input_type.to_owned(),
         value: "".into(),
     }
 }
 
 pub struct Model {
     placeholder: String,
+    input_type: String,
     pub value: String,
 }
 
@@ -21,6 +28,7 @@ pub fn update(model: &Model, msg: &Msg) -> Model {
     match msg {
         Msg::ValueChange(new_value) => Model {
             placeholder: model.placeholder.clone(),
+            input_type: model.input_type.clone(),
             value: new_value.clone(),
         },
     }
@@ -28,7 +36,11 @@ pub fn update(model: &Model, msg: &Msg) -> Model {
 
 pub fn view(model: &Model) -> Node<Msg> {
     input![
-        attrs![At::Value => model.value; At::Placeholder => model.placeholder],
+        attrs![
+            At::Type => model.input_type;
+            At::Value => model.value;
+            At::Placeholder => model.placeholder
+        ],
         input_ev(Ev::Input, |value| { Msg::ValueChange(value) })
     ]
 }
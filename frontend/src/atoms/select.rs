@@ -0,0 +1,53 @@
+use seed::{prelude::*, *};
+
+/// Create a select offering `options`, given as pairs of value and label. The first option is
+/// selected by default.
+pub fn init(label: String, options: Vec<(String, String)>) -> Model {
+    let value = options
+        .first()
+        .map(|(value, _)| value.clone())
+        .unwrap_or_default();
+    Model {
+        label,
+        options,
+        value,
+    }
+}
+
+pub struct Model {
+    label: String,
+    options: Vec<(String, String)>,
+    pub value: String,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    ValueChange(String),
+}
+
+pub fn update(model: &Model, msg: &Msg) -> Model {
+    match msg {
+        Msg::ValueChange(new_value) => Model {
+            label: model.label.clone(),
+            options: model.options.clone(),
+            value: new_value.clone(),
+        },
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    label![
+        &model.label,
+        " ",
+        select![
+            model.options.iter().map(|(value, label)| option![
+                attrs![
+                    At::Value => value;
+                    At::Selected => (value == &model.value).as_at_value()
+                ],
+                label
+            ]),
+            input_ev(Ev::Change, Msg::ValueChange)
+        ]
+    ]
+}
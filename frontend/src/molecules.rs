@@ -1,4 +1,6 @@
+pub mod comment_thread;
 pub mod event_details;
 pub mod event_publication_form;
 pub mod events_list;
 pub mod login_bar;
+pub mod route_charts;
@@ -0,0 +1,258 @@
+use crate::{
+    app::{self, Context},
+    atoms::{button, input},
+    molecules::event_details::local_time,
+    orders::{perform_cmd, IMyOrders},
+};
+use common::{api::BackendApi, ApiError, Comment, Id, Page};
+use seed::{prelude::*, *};
+use std::rc::Rc;
+
+pub fn init(event_id: Id) -> Model {
+    Model {
+        event_id,
+        comments: vec![],
+        next_cursor: None,
+        new_comment: input::init("write a comment".into()),
+        post_button: button::init("comment".into()),
+        more_button: button::init("more comments".into()),
+        edit_button: button::init("edit".into()),
+        save_button: button::init("save".into()),
+        delete_button: button::init("delete".into()),
+        editing: None,
+        error: None,
+    }
+}
+
+pub struct Model {
+    event_id: Id,
+    /// Comments loaded so far, oldest first.
+    comments: Vec<Comment>,
+    /// Cursor of the next page of comments, `None` once the whole thread is loaded.
+    next_cursor: Option<String>,
+    new_comment: input::Model,
+    post_button: button::Model,
+    more_button: button::Model,
+    edit_button: button::Model,
+    save_button: button::Model,
+    delete_button: button::Model,
+    /// Comment being edited by its author, with its new text.
+    editing: Option<(Id, input::Model)>,
+    /// Why the last request failed.
+    error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    OnGetComments(Page<Comment>),
+    MoreButton(button::Msg),
+    NewComment(input::Msg),
+    PostButton(button::Msg),
+    OnCommentPosted(Comment),
+    EditButton(Id),
+    EditedText(input::Msg),
+    SaveButton(button::Msg),
+    OnCommentEdited(Comment),
+    DeleteButton(Id),
+    OnCommentDeleted(Id),
+    RequestFailed(ApiError),
+}
+
+/// Request the page of comments following `cursor`, the first page if `None`.
+pub fn request_comments(
+    event_id: Id,
+    cursor: Option<String>,
+    orders: &mut impl IMyOrders<Msg>,
+    backend: Rc<dyn BackendApi>,
+) {
+    perform_cmd(orders, async move {
+        match backend.get_comments(event_id, cursor).await {
+            Ok(page) => Msg::OnGetComments(page),
+            Err(error) => Msg::RequestFailed(error),
+        }
+    });
+}
+
+fn post_comment(
+    event_id: Id,
+    text: String,
+    orders: &mut impl IMyOrders<Msg>,
+    backend: Rc<dyn BackendApi>,
+) {
+    perform_cmd(orders, async move {
+        match backend.post_comment(event_id, text).await {
+            Ok(comment) => Msg::OnCommentPosted(comment),
+            Err(error) => Msg::RequestFailed(error),
+        }
+    });
+}
+
+fn edit_comment(
+    id: Id,
+    text: String,
+    orders: &mut impl IMyOrders<Msg>,
+    backend: Rc<dyn BackendApi>,
+) {
+    perform_cmd(orders, async move {
+        match backend.edit_comment(id, text).await {
+            Ok(comment) => Msg::OnCommentEdited(comment),
+            Err(error) => Msg::RequestFailed(error),
+        }
+    });
+}
+
+fn delete_comment(id: Id, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.delete_comment(id).await {
+            Ok(()) => Msg::OnCommentDeleted(id),
+            Err(error) => Msg::RequestFailed(error),
+        }
+    });
+}
+
+pub fn update(
+    msg: Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match msg {
+        Msg::OnGetComments(page) => {
+            // a comment posted by the user may already be listed
+            let new_comments: Vec<Comment> = page
+                .items
+                .into_iter()
+                .filter(|comment| model.comments.iter().all(|other| other.id != comment.id))
+                .collect();
+            model.comments.extend(new_comments);
+            model.next_cursor = page.next_cursor;
+        }
+        Msg::MoreButton(button::Msg::Click) => {
+            if let Some(cursor) = &model.next_cursor {
+                request_comments(
+                    model.event_id,
+                    Some(cursor.clone()),
+                    orders,
+                    context.backend.clone(),
+                )
+            }
+        }
+        Msg::NewComment(msg) => model.new_comment = input::update(&model.new_comment, &msg),
+        Msg::PostButton(button::Msg::Click) => post_comment(
+            model.event_id,
+            model.new_comment.value.clone(),
+            orders,
+            context.backend.clone(),
+        ),
+        Msg::OnCommentPosted(comment) => {
+            model.new_comment.value.clear();
+            model.error = None;
+            // until the whole thread is loaded, the comment comes with the last page
+            if model.next_cursor.is_none() {
+                model.comments.push(comment);
+            }
+        }
+        Msg::EditButton(id) => {
+            if let Some(comment) = model.comments.iter().find(|comment| comment.id == id) {
+                let mut text = input::init("comment".into());
+                text.value = comment.text.clone();
+                model.editing = Some((id, text));
+            }
+        }
+        Msg::EditedText(msg) => {
+            if let Some((_, text)) = &mut model.editing {
+                *text = input::update(text, &msg);
+            }
+        }
+        Msg::SaveButton(button::Msg::Click) => {
+            if let Some((id, text)) = &model.editing {
+                edit_comment(*id, text.value.clone(), orders, context.backend.clone())
+            }
+        }
+        Msg::OnCommentEdited(edited) => {
+            model.editing = None;
+            model.error = None;
+            if let Some(comment) = model
+                .comments
+                .iter_mut()
+                .find(|comment| comment.id == edited.id)
+            {
+                *comment = edited;
+            }
+        }
+        Msg::DeleteButton(id) => delete_comment(id, orders, context.backend.clone()),
+        Msg::OnCommentDeleted(id) => model.comments.retain(|comment| comment.id != id),
+        Msg::RequestFailed(error) => {
+            if error.requires_login() {
+                app::redirect_to_login(context, orders);
+            }
+            model.error = Some(error.message)
+        }
+    }
+}
+
+/// Thread of comments, `username` being the logged-in user who can comment and edit their
+/// comments, the organizer of the event can also delete any comment.
+pub fn view(model: &Model, username: Option<&str>, is_organizer: bool) -> Node<Msg> {
+    let comment_divs: Vec<Node<Msg>> = model
+        .comments
+        .iter()
+        .map(|comment| view_comment(model, comment, username, is_organizer))
+        .collect();
+    div![
+        h2!("comments"),
+        comment_divs,
+        model
+            .next_cursor
+            .is_some()
+            .then(|| button::view(&model.more_button, true).map_msg(Msg::MoreButton)),
+        username.is_some().then(|| div![
+            input::view(&model.new_comment).map_msg(Msg::NewComment),
+            button::view(
+                &model.post_button,
+                !model.new_comment.value.trim().is_empty()
+            )
+            .map_msg(Msg::PostButton)
+        ]),
+        model.error.as_ref().map(|error| div![error])
+    ]
+}
+
+fn view_comment(
+    model: &Model,
+    comment: &Comment,
+    username: Option<&str>,
+    is_organizer: bool,
+) -> Node<Msg> {
+    let id = comment.id;
+    let header = format!(
+        "{}, {}{}",
+        comment.author,
+        local_time(&comment.posted_at),
+        if comment.edited_at.is_some() {
+            " (edited)"
+        } else {
+            ""
+        }
+    );
+    match &model.editing {
+        Some((editing, text)) if *editing == id => div![
+            div![header],
+            input::view(text).map_msg(Msg::EditedText),
+            button::view(&model.save_button, !text.value.trim().is_empty())
+                .map_msg(Msg::SaveButton)
+        ],
+        _ => div![
+            div![header],
+            div![&comment.text],
+            (username == Some(comment.author.as_str())).then(|| button::view(
+                &model.edit_button,
+                true
+            )
+            .map_msg(move |_| Msg::EditButton(id))),
+            is_organizer
+                .then(|| button::view(&model.delete_button, true)
+                    .map_msg(move |_| Msg::DeleteButton(id)))
+        ],
+    }
+}
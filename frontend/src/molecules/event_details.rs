@@ -1,12 +1,30 @@
-use common::Event;
+use crate::molecules::route_charts;
+use chrono::{DateTime, Local, TimeZone};
+use common::{Event, Frequency, Route};
 use seed::{prelude::*, *};
 
-pub fn init(event: Event) -> Model {
-    Model { event: event }
+/// Date and time of `datetime` in the timezone of the browser.
+pub fn local_time<Tz: TimeZone>(datetime: &DateTime<Tz>) -> String {
+    datetime
+        .with_timezone(&Local)
+        .format("%A %-d %B %Y, %H:%M")
+        .to_string()
+}
+
+pub fn init(event: Event, username: Option<String>) -> Model {
+    Model {
+        event,
+        username,
+        route: None,
+    }
 }
 
 pub struct Model {
     event: Event,
+    /// Logged-in user, shown whether they are going or waitlisted.
+    username: Option<String>,
+    /// Points of the route of the event, requested separately since the event only has a summary.
+    pub route: Option<Route>,
 }
 
 pub fn view(model: &Model) -> Node<()> {
@@ -16,9 +34,97 @@ pub fn view(model: &Model) -> Node<()> {
         .iter()
         .map(|participant| div![participant.clone()])
         .collect();
+    let waitlist_divs: Vec<Node<()>> = model
+        .event
+        .waitlist
+        .iter()
+        .enumerate()
+        .map(|(index, username)| div![format!("{}. {}", index + 1, username)])
+        .collect();
     div![
         h2!("event"),
         div![&model.event.name],
+        div![model.event.status.to_string()],
+        model
+            .event
+            .start
+            .as_ref()
+            .map(|start| div![format!("starts {}", local_time(start))]),
+        model
+            .event
+            .end
+            .as_ref()
+            .map(|end| div![format!("ends {}", local_time(end))]),
+        view_classification(&model.event),
+        model.event.series.as_ref().map(|series| {
+            div![match series.recurrence.frequency {
+                Frequency::Weekly => "repeats every week",
+                Frequency::Monthly => "repeats every month",
+            }]
+        }),
+        model.event.meeting_point.as_ref().map(|meeting_point| {
+            div![
+                "meeting point: ",
+                a![
+                    attrs! {At::Href => format!(
+                        "https://www.openstreetmap.org/?mlat={0}&mlon={1}#map=16/{0}/{1}",
+                        meeting_point.latitude, meeting_point.longitude
+                    )},
+                    &meeting_point.address
+                ]
+            ]
+        }),
+        model
+            .event
+            .cancellation_reason
+            .as_ref()
+            .map(|reason| div![format!("cancelled: {}", reason)]),
+        model.event.route.as_ref().map(|route| {
+            div![format!(
+                "route: {:.1} km, +{:.0} m, -{:.0} m",
+                route.distance_km, route.elevation_gain_m, route.elevation_loss_m
+            )]
+        }),
+        model.route.as_ref().map(|route| {
+            div![
+                route_charts::view_elevation_profile(route),
+                route_charts::view_shape(route),
+            ]
+        }),
+        model
+            .event
+            .organizer
+            .as_ref()
+            .map(|organizer| div![format!("organized by {}", organizer)]),
+        model.event.capacity.map(|capacity| div![format!(
+            "participants: {}/{}",
+            model.event.participants.len(),
+            capacity
+        )]),
+        model
+            .username
+            .as_ref()
+            .and_then(|username| model.event.participation(username))
+            .map(|participation| div![format!("you are {}", participation)]),
         div![participants_divs],
+        (!waitlist_divs.is_empty()).then(|| div![div!["waitlist:"], waitlist_divs]),
+    ]
+}
+
+/// Discipline, pace, difficulty and no-drop policy of the ride, `None` if none was given.
+fn view_classification(event: &Event) -> Option<Node<()>> {
+    let attributes: Vec<String> = vec![
+        event
+            .discipline
+            .map(|discipline| discipline.label().to_owned()),
+        event.pace.map(|pace| format!("pace: {}", pace.label())),
+        event
+            .difficulty
+            .map(|difficulty| format!("difficulty: {}", difficulty)),
+        event.no_drop.then(|| "no-drop".to_owned()),
     ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!attributes.is_empty()).then(|| div![attributes.join(", ")])
 }
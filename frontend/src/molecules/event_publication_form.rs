@@ -1,25 +1,271 @@
 use std::rc::Rc;
+use std::str::FromStr;
 
-use crate::app::Context;
-use crate::atoms::{button, input};
+use crate::app::{self, Context};
+use crate::atoms::{button, input, select};
 use crate::orders::{perform_cmd, IMyOrders};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveTime, TimeZone};
 use common::api::BackendApi;
+use common::{
+    ApiError, Difficulty, Discipline, Event, EventPatch, Frequency, MeetingPoint, NewEvent, Pace,
+    Recurrence, Tz,
+};
 use seed::{prelude::*, *};
 
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+
 pub fn init() -> Model {
     Model {
         state: State::Typing,
+        mode: Mode::Publish,
         event_name: input::init("name".into()),
+        start_date: input::init_with_type("date".into(), "date"),
+        start_time: input::init_with_type("time".into(), "time"),
+        duration: input::init_with_type("duration in minutes (optional)".into(), "number"),
+        meeting_address: input::init("meeting point (optional)".into()),
+        meeting_latitude: input::init_with_type("latitude".into(), "number"),
+        meeting_longitude: input::init_with_type("longitude".into(), "number"),
+        capacity: input::init_with_type("maximum participants (optional)".into(), "number"),
+        discipline: select::init(
+            "discipline".into(),
+            options(Discipline::ALL.map(|discipline| (discipline.as_str(), discipline.label()))),
+        ),
+        pace: select::init(
+            "pace".into(),
+            options(Pace::ALL.map(|pace| (pace.as_str(), pace.label()))),
+        ),
+        difficulty: select::init(
+            "difficulty".into(),
+            options(Difficulty::ALL.map(|difficulty| (difficulty.as_str(), difficulty.as_str()))),
+        ),
+        no_drop: select::init(
+            "group".into(),
+            vec![
+                ("false".into(), "riders may be dropped".into()),
+                ("true".into(), "no-drop".into()),
+            ],
+        ),
+        repeat: select::init(
+            "repeat".into(),
+            vec![
+                ("".into(), "does not repeat".into()),
+                (Frequency::Weekly.to_string(), "every week".into()),
+                (Frequency::Monthly.to_string(), "every month".into()),
+            ],
+        ),
+        repeat_count: input::init_with_type("number of rides (optional)".into(), "number"),
+        repeat_until: input::init_with_type("last date (optional)".into(), "date"),
         publish_button: button::init("publish".to_owned()),
     }
 }
 
+/// Options of a select of an optional attribute, the first one leaving it unspecified.
+fn options<const N: usize>(values: [(&str, &str); N]) -> Vec<(String, String)> {
+    std::iter::once(("", "not specified"))
+        .chain(values)
+        .map(|(value, label)| (value.to_owned(), label.to_owned()))
+        .collect()
+}
+
+/// Form pre-filled with the current values of `event`, saving it updates the event.
+pub fn init_for_edit(event: Event) -> Model {
+    let mut model = init();
+    model.event_name.value = event.name.clone();
+    if let Some(start) = event.start {
+        let start = start.with_timezone(&Local);
+        model.start_date.value = start.format(DATE_FORMAT).to_string();
+        model.start_time.value = start.format(TIME_FORMAT).to_string();
+        if let Some(end) = event.end {
+            model.duration.value = (end.with_timezone(&Local) - start)
+                .num_minutes()
+                .to_string();
+        }
+    }
+    if let Some(meeting_point) = &event.meeting_point {
+        model.meeting_address.value = meeting_point.address.clone();
+        model.meeting_latitude.value = meeting_point.latitude.to_string();
+        model.meeting_longitude.value = meeting_point.longitude.to_string();
+    }
+    if let Some(capacity) = event.capacity {
+        model.capacity.value = capacity.to_string();
+    }
+    if let Some(discipline) = event.discipline {
+        model.discipline.value = discipline.to_string();
+    }
+    if let Some(pace) = event.pace {
+        model.pace.value = pace.to_string();
+    }
+    if let Some(difficulty) = event.difficulty {
+        model.difficulty.value = difficulty.to_string();
+    }
+    model.no_drop.value = event.no_drop.to_string();
+    model.mode = Mode::Edit(Box::new(event));
+    model.publish_button = button::init("save".to_owned());
+    model
+}
+
 pub struct Model {
     state: State,
+    mode: Mode,
     event_name: input::Model,
+    /// Start of the event in the timezone of the browser.
+    start_date: input::Model,
+    start_time: input::Model,
+    duration: input::Model,
+    meeting_address: input::Model,
+    meeting_latitude: input::Model,
+    meeting_longitude: input::Model,
+    capacity: input::Model,
+    discipline: select::Model,
+    pace: select::Model,
+    difficulty: select::Model,
+    no_drop: select::Model,
+    /// Only shown when publishing, the occurrences of a series are edited one by one.
+    repeat: select::Model,
+    repeat_count: input::Model,
+    repeat_until: input::Model,
     publish_button: button::Model,
 }
 
+impl Model {
+    /// Start and duration typed in the form, or the error to show.
+    fn schedule(&self) -> Result<(DateTime<FixedOffset>, Option<u32>), String> {
+        let date = NaiveDate::parse_from_str(&self.start_date.value, DATE_FORMAT)
+            .map_err(|_| "The date is required")?;
+        let time = NaiveTime::parse_from_str(&self.start_time.value, TIME_FORMAT)
+            .map_err(|_| "The time is required")?;
+        let start = Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .ok_or("This time does not exist in your timezone")?
+            .fixed_offset();
+        let duration = match self.duration.value.trim() {
+            "" => None,
+            duration => match duration.parse::<u32>() {
+                Ok(duration) if duration > 0 => Some(duration),
+                _ => return Err("The duration must be a number of minutes".into()),
+            },
+        };
+        Ok((start, duration))
+    }
+
+    /// Meeting point typed in the form, `None` if the address is empty.
+    fn meeting_point(&self) -> Result<Option<MeetingPoint>, String> {
+        let address = self.meeting_address.value.trim();
+        if address.is_empty() {
+            return Ok(None);
+        }
+        let latitude = self
+            .meeting_latitude
+            .value
+            .parse::<f64>()
+            .map_err(|_| "The latitude of the meeting point is required")?;
+        let longitude = self
+            .meeting_longitude
+            .value
+            .parse::<f64>()
+            .map_err(|_| "The longitude of the meeting point is required")?;
+        Ok(Some(MeetingPoint {
+            address: address.to_owned(),
+            latitude,
+            longitude,
+        }))
+    }
+
+    /// Maximum number of participants typed in the form, `None` if empty.
+    fn capacity(&self) -> Result<Option<u32>, String> {
+        match self.capacity.value.trim() {
+            "" => Ok(None),
+            capacity => match capacity.parse::<u32>() {
+                Ok(capacity) if capacity > 0 => Ok(Some(capacity)),
+                _ => Err("The maximum number of participants must be a positive number".into()),
+            },
+        }
+    }
+
+    /// Recurrence chosen in the form, `None` if the event does not repeat.
+    fn recurrence(&self) -> Result<Option<Recurrence>, String> {
+        let frequency = match selected::<Frequency>(&self.repeat) {
+            Some(frequency) => frequency,
+            None => return Ok(None),
+        };
+        let count = match self.repeat_count.value.trim() {
+            "" => None,
+            count => match count.parse::<u32>() {
+                Ok(count) if count > 0 => Some(count),
+                _ => return Err("The number of rides must be a positive number".into()),
+            },
+        };
+        let until = match self.repeat_until.value.trim() {
+            "" => None,
+            until => {
+                let date = NaiveDate::parse_from_str(until, DATE_FORMAT)
+                    .map_err(|_| "The last date is invalid")?;
+                // the last day is included
+                Local
+                    .from_local_datetime(&date.and_hms_opt(23, 59, 59).unwrap_or_default())
+                    .latest()
+                    .map(|until| until.fixed_offset())
+            }
+        };
+        Ok(Some(Recurrence {
+            frequency,
+            until,
+            count,
+        }))
+    }
+
+    /// Everything typed in the form but the name, or the error to show.
+    fn details(&self) -> Result<Details, String> {
+        let (start, duration) = self.schedule()?;
+        Ok(Details {
+            start,
+            duration,
+            meeting_point: self.meeting_point()?,
+            capacity: self.capacity()?,
+            discipline: selected(&self.discipline),
+            pace: selected(&self.pace),
+            difficulty: selected(&self.difficulty),
+            no_drop: selected(&self.no_drop).unwrap_or(false),
+            recurrence: self.recurrence()?,
+            timezone: local_timezone(),
+        })
+    }
+}
+
+/// IANA timezone of the browser, in which the start is typed. `None` if the browser does not tell
+/// it or if it is unknown to the frontend.
+fn local_timezone() -> Option<Tz> {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+}
+
+/// Value of a select of an optional attribute, `None` if it is left unspecified.
+fn selected<T: FromStr>(select: &select::Model) -> Option<T> {
+    T::from_str(&select.value).ok()
+}
+
+struct Details {
+    start: DateTime<FixedOffset>,
+    duration: Option<u32>,
+    meeting_point: Option<MeetingPoint>,
+    capacity: Option<u32>,
+    discipline: Option<Discipline>,
+    pace: Option<Pace>,
+    difficulty: Option<Difficulty>,
+    no_drop: bool,
+    recurrence: Option<Recurrence>,
+    timezone: Option<Tz>,
+}
+
+enum Mode {
+    Publish,
+    /// Edit the given event, as it was before the changes.
+    Edit(Box<Event>),
+}
+
 pub enum State {
     Typing,
     Publishing,
@@ -35,27 +281,91 @@ pub enum Msg {
 #[derive(Clone, Debug)]
 pub enum PublicMsg {
     EventPublished,
+    EventUpdated(Box<Event>),
 }
 
 #[derive(Clone, Debug)]
 pub enum PrivateMsg {
     EventName(input::Msg),
+    StartDate(input::Msg),
+    StartTime(input::Msg),
+    Duration(input::Msg),
+    MeetingAddress(input::Msg),
+    MeetingLatitude(input::Msg),
+    MeetingLongitude(input::Msg),
+    Capacity(input::Msg),
+    Discipline(select::Msg),
+    Pace(select::Msg),
+    Difficulty(select::Msg),
+    NoDrop(select::Msg),
+    Repeat(select::Msg),
+    RepeatCount(input::Msg),
+    RepeatUntil(input::Msg),
     PublishButton(button::Msg),
+    PublishFailed(ApiError),
 }
 
-fn publish_event(model: &Model, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+fn publish_event(
+    model: &Model,
+    details: Details,
+    orders: &mut impl IMyOrders<Msg>,
+    backend: Rc<dyn BackendApi>,
+) {
     // TODO: refactor this to use a logging service
     // log!("publish event");
     let name = model.event_name.value.clone();
-    perform_cmd(orders, async move {
-        match backend.publish_event(name).await {
-            Ok(_) => Msg::Public(PublicMsg::EventPublished),
-            Err(error) => {
-                error!(error);
-                todo!()
+    let Details {
+        start,
+        duration,
+        meeting_point,
+        capacity,
+        discipline,
+        pace,
+        difficulty,
+        no_drop,
+        recurrence,
+        timezone,
+    } = details;
+    match &model.mode {
+        Mode::Publish => perform_cmd(orders, async move {
+            let event = NewEvent {
+                duration_minutes: duration,
+                meeting_point,
+                capacity,
+                discipline,
+                pace,
+                difficulty,
+                no_drop,
+                recurrence,
+                timezone,
+                ..NewEvent::new(name, start)
+            };
+            match backend.publish_event(event).await {
+                Ok(_) => Msg::Public(PublicMsg::EventPublished),
+                Err(error) => Msg::Private(PrivateMsg::PublishFailed(error)),
             }
+        }),
+        Mode::Edit(original) => {
+            let mut updated = original.clone();
+            updated.name = name;
+            updated.start = Some(start);
+            updated.end = duration.map(|duration| start + Duration::minutes(duration.into()));
+            updated.meeting_point = meeting_point;
+            updated.capacity = capacity;
+            updated.discipline = discipline;
+            updated.pace = pace;
+            updated.difficulty = difficulty;
+            updated.no_drop = no_drop;
+            let patch = EventPatch::between(original, &updated);
+            let id = original.id;
+            perform_cmd(orders, async move {
+                match backend.update_event(id, patch).await {
+                    Ok(event) => Msg::Public(PublicMsg::EventUpdated(Box::new(event))),
+                    Err(error) => Msg::Private(PrivateMsg::PublishFailed(error)),
+                }
+            })
         }
-    });
+    }
 }
 
 pub fn update(
@@ -71,40 +381,88 @@ pub fn update(
     }
 }
 
+/// Update the input or the select targeted by `msg`, returns `false` if `msg` is not an input
+/// or a select msg.
+fn update_input(msg: &PrivateMsg, model: &mut Model) -> bool {
+    let select = match msg {
+        PrivateMsg::Discipline(msg) => Some((&mut model.discipline, msg)),
+        PrivateMsg::Pace(msg) => Some((&mut model.pace, msg)),
+        PrivateMsg::Difficulty(msg) => Some((&mut model.difficulty, msg)),
+        PrivateMsg::NoDrop(msg) => Some((&mut model.no_drop, msg)),
+        PrivateMsg::Repeat(msg) => Some((&mut model.repeat, msg)),
+        _ => None,
+    };
+    if let Some((select, msg)) = select {
+        *select = select::update(select, msg);
+        return true;
+    }
+    let (input, msg) = match msg {
+        PrivateMsg::EventName(msg) => (&mut model.event_name, msg),
+        PrivateMsg::StartDate(msg) => (&mut model.start_date, msg),
+        PrivateMsg::StartTime(msg) => (&mut model.start_time, msg),
+        PrivateMsg::Duration(msg) => (&mut model.duration, msg),
+        PrivateMsg::MeetingAddress(msg) => (&mut model.meeting_address, msg),
+        PrivateMsg::MeetingLatitude(msg) => (&mut model.meeting_latitude, msg),
+        PrivateMsg::MeetingLongitude(msg) => (&mut model.meeting_longitude, msg),
+        PrivateMsg::Capacity(msg) => (&mut model.capacity, msg),
+        PrivateMsg::RepeatCount(msg) => (&mut model.repeat_count, msg),
+        PrivateMsg::RepeatUntil(msg) => (&mut model.repeat_until, msg),
+        _ => return false,
+    };
+    *input = input::update(input, msg);
+    true
+}
+
 fn update_typing(
     msg: PrivateMsg,
     model: &mut Model,
     context: &mut Context,
     orders: &mut impl IMyOrders<Msg>,
 ) {
+    if update_input(&msg, model) {
+        return;
+    }
     match msg {
-        PrivateMsg::EventName(msg) => {
-            model.event_name = input::update(&model.event_name, &msg);
-        }
         PrivateMsg::PublishButton(button::Msg::Click) => {
             if model.event_name.value.is_empty() {
                 model.state = State::Invalid("The name is required".into());
             } else {
-                publish_event(model, orders, context.backend.clone());
-                model.state = State::Publishing;
+                match model.details() {
+                    Ok(details) => {
+                        publish_event(model, details, orders, context.backend.clone());
+                        model.state = State::Publishing;
+                    }
+                    Err(error) => model.state = State::Invalid(error),
+                }
             }
         }
+        PrivateMsg::PublishFailed(_) => {
+            error!("received a publish failed msg while typing")
+        }
+        _ => {}
     }
 }
 
 fn update_publishing(
     msg: PrivateMsg,
     model: &mut Model,
-    _context: &mut Context,
-    _orders: &mut impl IMyOrders<Msg>,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
 ) {
+    if update_input(&msg, model) {
+        return;
+    }
     match msg {
-        PrivateMsg::EventName(msg) => {
-            model.event_name = input::update(&model.event_name, &msg);
-        }
         PrivateMsg::PublishButton(button::Msg::Click) => {
             error!("received a publish button click msg while publishing")
         }
+        PrivateMsg::PublishFailed(error) => {
+            if error.requires_login() {
+                app::redirect_to_login(context, orders);
+            }
+            model.state = State::Invalid(error.message)
+        }
+        _ => {}
     }
 }
 
@@ -114,31 +472,61 @@ fn update_invalid(
     _context: &mut Context,
     _orders: &mut impl IMyOrders<Msg>,
 ) {
+    if update_input(&msg, model) {
+        model.state = State::Typing;
+        return;
+    }
     match msg {
-        PrivateMsg::EventName(msg) => {
-            model.state = State::Typing;
-            model.event_name = input::update(&model.event_name, &msg);
-        }
         PrivateMsg::PublishButton(button::Msg::Click) => {
             error!("received a publish button click msg while being an invalid form")
         }
+        PrivateMsg::PublishFailed(_) => {
+            error!("received a publish failed msg while being an invalid form")
+        }
+        _ => {}
     }
 }
 
 pub fn view(model: &Model) -> Node<Msg> {
-    let input = input::view(&model.event_name)
-        .map_msg(PrivateMsg::EventName)
-        .map_msg(Msg::Private);
+    let inputs = div![
+        input::view(&model.event_name).map_msg(PrivateMsg::EventName),
+        input::view(&model.start_date).map_msg(PrivateMsg::StartDate),
+        input::view(&model.start_time).map_msg(PrivateMsg::StartTime),
+        input::view(&model.duration).map_msg(PrivateMsg::Duration),
+        input::view(&model.meeting_address).map_msg(PrivateMsg::MeetingAddress),
+        input::view(&model.meeting_latitude).map_msg(PrivateMsg::MeetingLatitude),
+        input::view(&model.meeting_longitude).map_msg(PrivateMsg::MeetingLongitude),
+        input::view(&model.capacity).map_msg(PrivateMsg::Capacity),
+        select::view(&model.discipline).map_msg(PrivateMsg::Discipline),
+        select::view(&model.pace).map_msg(PrivateMsg::Pace),
+        select::view(&model.difficulty).map_msg(PrivateMsg::Difficulty),
+        select::view(&model.no_drop).map_msg(PrivateMsg::NoDrop),
+        matches!(model.mode, Mode::Publish).then(|| div![
+            select::view(&model.repeat).map_msg(PrivateMsg::Repeat),
+            (!model.repeat.value.is_empty()).then(|| div![
+                input::view(&model.repeat_count).map_msg(PrivateMsg::RepeatCount),
+                input::view(&model.repeat_until).map_msg(PrivateMsg::RepeatUntil),
+            ]),
+        ]),
+    ]
+    .map_msg(Msg::Private);
     let is_form_ready_for_publishing = match model.state {
-        State::Typing => !model.event_name.value.is_empty(),
+        State::Typing => {
+            !model.event_name.value.is_empty()
+                && !model.start_date.value.is_empty()
+                && !model.start_time.value.is_empty()
+        }
         State::Publishing => false,
         State::Invalid(_) => false,
     };
     div![
-        h2!("publish an event"),
+        h2!(match model.mode {
+            Mode::Publish => "publish an event",
+            Mode::Edit(_) => "edit the event",
+        }),
         match &model.state {
-            State::Invalid(error) => div![input, error],
-            _ => div![input],
+            State::Invalid(error) => div![inputs, error],
+            _ => div![inputs],
         },
         button::view(&model.publish_button, is_form_ready_for_publishing)
             .map_msg(PrivateMsg::PublishButton)
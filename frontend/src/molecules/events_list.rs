@@ -2,11 +2,18 @@ use common::Event;
 use seed::{prelude::*, *};
 
 pub fn init(events: Vec<Event>) -> Model {
-    Model { events: events }
+    Model { events }
 }
 
 pub struct Model {
-    events: Vec<Event>,
+    pub events: Vec<Event>,
+}
+
+/// Show the new state of `event` if it is listed.
+pub fn update_event(model: &mut Model, event: Event) {
+    if let Some(listed) = model.events.iter_mut().find(|listed| listed.id == event.id) {
+        *listed = event;
+    }
 }
 
 pub fn view(model: &Model) -> Node<()> {
@@ -14,10 +21,13 @@ pub fn view(model: &Model) -> Node<()> {
         .events
         .iter()
         .map(|event| {
-            div![a![
-                attrs! {At::Href => format!("/event/{}", event.id.to_string())},
-                event.name.clone()
-            ]]
+            div![
+                a![
+                    attrs! {At::Href => format!("/event/{}", event.id.to_string())},
+                    event.name.clone()
+                ],
+                format!(" ({})", event.status)
+            ]
         })
         .collect();
     div![h2!("all events"), event_divs,]
@@ -1,17 +1,26 @@
 use crate::{
     app::Context,
-    atoms::button,
+    atoms::{button, input},
     orders::{perform_cmd, IMyOrders},
 };
+use common::{api::BackendApi, ApiError, CalendarFeed, Notification};
 use seed::{prelude::*, *};
+use std::rc::Rc;
 
 pub fn init(username: Option<String>) -> Model {
     Model {
         state: match username {
-            Some(username) => State::SignedIn(SignedIn {
-                username: username,
+            Some(username) => State::SignedIn(Box::new(SignedIn {
+                username,
                 logout_button: button::init("logout".into()),
-            }),
+                calendar_button: button::init("calendar feed".into()),
+                reset_calendar_button: button::init("new feed link".into()),
+                calendar_feed: None,
+                email_input: input::init_with_type("email address".into(), "email"),
+                email_button: button::init("email me".into()),
+                email_status: None,
+                unread: 0,
+            })),
             None => State::SignedOut,
         },
     }
@@ -22,13 +31,23 @@ pub struct Model {
 }
 
 enum State {
-    SignedIn(SignedIn),
+    SignedIn(Box<SignedIn>),
     SignedOut,
 }
 
 struct SignedIn {
     username: String,
     logout_button: button::Model,
+    calendar_button: button::Model,
+    reset_calendar_button: button::Model,
+    /// Shown once requested with the calendar button.
+    calendar_feed: Option<CalendarFeed>,
+    email_input: input::Model,
+    email_button: button::Model,
+    /// Outcome of the last change of the email address.
+    email_status: Option<String>,
+    /// Number of unread notifications, shown on the bell.
+    unread: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -46,6 +65,38 @@ pub enum PublicMsg {
 pub enum PrivateMsg {
     LogoutButton(button::Msg),
     SignedOut,
+    CalendarButton(button::Msg),
+    ResetCalendarButton(button::Msg),
+    OnGetCalendarFeed(CalendarFeed),
+    CalendarFeedFailed(ApiError),
+    OnGetNotifications(Vec<Notification>),
+    NotificationsFailed(ApiError),
+    EmailInput(input::Msg),
+    EmailButton(button::Msg),
+    OnEmailSet(String),
+    EmailFailed(ApiError),
+}
+
+/// Request the notifications of the logged-in user to count the unread ones, nothing to do if
+/// no user is logged in.
+pub fn request_unread_count(context: &Context, orders: &mut impl IMyOrders<Msg>) {
+    if context.username.is_none() {
+        return;
+    }
+    let backend = context.backend.clone();
+    perform_cmd(orders, async move {
+        match backend.get_notifications().await {
+            Ok(notifications) => Msg::Private(PrivateMsg::OnGetNotifications(notifications)),
+            Err(error) => Msg::Private(PrivateMsg::NotificationsFailed(error)),
+        }
+    });
+}
+
+/// Show `unread` on the bell, e.g. once the user read notifications.
+pub fn set_unread(model: &mut Model, unread: usize) {
+    if let State::SignedIn(signed_in) = &mut model.state {
+        signed_in.unread = unread;
+    }
 }
 
 pub fn update(
@@ -55,12 +106,54 @@ pub fn update(
     orders: &mut impl IMyOrders<Msg>,
 ) {
     match msg {
-        PrivateMsg::LogoutButton(button::Msg::Click) => logout(orders),
+        PrivateMsg::LogoutButton(button::Msg::Click) => logout(orders, context.backend.clone()),
         PrivateMsg::SignedOut => {
             context.username = None;
             model.state = State::SignedOut;
             notify_logout(orders)
         }
+        PrivateMsg::CalendarButton(button::Msg::Click) => {
+            get_calendar_feed(orders, context.backend.clone())
+        }
+        PrivateMsg::ResetCalendarButton(button::Msg::Click) => {
+            reset_calendar_feed(orders, context.backend.clone())
+        }
+        PrivateMsg::OnGetCalendarFeed(feed) => match &mut model.state {
+            State::SignedIn(signed_in) => signed_in.calendar_feed = Some(feed),
+            State::SignedOut => error!("received a calendar feed while signed out"),
+        },
+        PrivateMsg::CalendarFeedFailed(error) => error!(error.message),
+        PrivateMsg::OnGetNotifications(notifications) => set_unread(
+            model,
+            notifications
+                .iter()
+                .filter(|notification| !notification.read)
+                .count(),
+        ),
+        PrivateMsg::NotificationsFailed(error) => error!(error.message),
+        PrivateMsg::EmailInput(input::Msg::ValueChange(value)) => match &mut model.state {
+            State::SignedIn(signed_in) => signed_in.email_input.value = value,
+            State::SignedOut => error!("received email input msg while signed out"),
+        },
+        PrivateMsg::EmailButton(button::Msg::Click) => match &model.state {
+            State::SignedIn(signed_in) => set_email(
+                signed_in.email_input.value.trim().to_owned(),
+                orders,
+                context.backend.clone(),
+            ),
+            State::SignedOut => error!("received email button msg while signed out"),
+        },
+        PrivateMsg::OnEmailSet(email) => match &mut model.state {
+            State::SignedIn(signed_in) => {
+                signed_in.email_input.value = "".into();
+                signed_in.email_status = Some(format!("changes emailed to {}", email));
+            }
+            State::SignedOut => error!("received an email address while signed out"),
+        },
+        PrivateMsg::EmailFailed(error) => match &mut model.state {
+            State::SignedIn(signed_in) => signed_in.email_status = Some(error.message),
+            State::SignedOut => error!(error.message),
+        },
     }
 }
 
@@ -68,17 +161,51 @@ pub fn view(model: &Model) -> Node<Msg> {
     match &model.state {
         State::SignedIn(model) => div![
             model.username.clone(),
+            a![
+                attrs![At::Href => "/notifications"],
+                if model.unread > 0 {
+                    format!("🔔 {}", model.unread)
+                } else {
+                    "🔔".to_owned()
+                }
+            ],
             button::view(&model.logout_button, true)
                 .map_msg(PrivateMsg::LogoutButton)
-                .map_msg(Msg::Private)
+                .map_msg(Msg::Private),
+            match &model.calendar_feed {
+                Some(feed) => div![
+                    a![attrs![At::Href => feed.path], "subscribe to your rides"],
+                    button::view(&model.reset_calendar_button, true)
+                        .map_msg(PrivateMsg::ResetCalendarButton)
+                        .map_msg(Msg::Private)
+                ],
+                None => button::view(&model.calendar_button, true)
+                    .map_msg(PrivateMsg::CalendarButton)
+                    .map_msg(Msg::Private),
+            },
+            div![
+                input::view(&model.email_input)
+                    .map_msg(PrivateMsg::EmailInput)
+                    .map_msg(Msg::Private),
+                button::view(
+                    &model.email_button,
+                    !model.email_input.value.trim().is_empty()
+                )
+                .map_msg(PrivateMsg::EmailButton)
+                .map_msg(Msg::Private),
+                model.email_status.as_ref().map(|status| span![status])
+            ]
         ],
         State::SignedOut => div![a![attrs![At::Href => "/login"], "login"]],
     }
 }
 
-fn logout(orders: &mut impl IMyOrders<Msg>) {
-    perform_cmd(orders, async {
-        // TODO: logout
+fn logout(orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        // the session is forgotten locally even if the server could not revoke it
+        if let Err(error) = backend.logout().await {
+            error!(error);
+        }
         Msg::Private(PrivateMsg::SignedOut)
     });
 }
@@ -86,3 +213,30 @@ fn logout(orders: &mut impl IMyOrders<Msg>) {
 fn notify_logout(orders: &mut impl IMyOrders<Msg>) {
     perform_cmd(orders, async { Msg::Public(PublicMsg::SignedOut) });
 }
+
+fn get_calendar_feed(orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.get_calendar_feed().await {
+            Ok(feed) => Msg::Private(PrivateMsg::OnGetCalendarFeed(feed)),
+            Err(error) => Msg::Private(PrivateMsg::CalendarFeedFailed(error)),
+        }
+    });
+}
+
+fn reset_calendar_feed(orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.reset_calendar_feed().await {
+            Ok(feed) => Msg::Private(PrivateMsg::OnGetCalendarFeed(feed)),
+            Err(error) => Msg::Private(PrivateMsg::CalendarFeedFailed(error)),
+        }
+    });
+}
+
+fn set_email(email: String, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.set_email(email.clone()).await {
+            Ok(()) => Msg::Private(PrivateMsg::OnEmailSet(email)),
+            Err(error) => Msg::Private(PrivateMsg::EmailFailed(error)),
+        }
+    });
+}
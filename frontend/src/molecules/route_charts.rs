@@ -0,0 +1,138 @@
+use common::{Coordinates, Route};
+use seed::{prelude::*, *};
+
+const PROFILE_WIDTH: f64 = 400.0;
+const PROFILE_HEIGHT: f64 = 100.0;
+/// Size of the longest side of the drawing of the route shape.
+const SHAPE_SIZE: f64 = 300.0;
+/// Room left around the lines so that their stroke is not cut.
+const MARGIN: f64 = 2.0;
+
+/// Distance from the start in kilometers and elevation in meters of the points of `route`
+/// having an elevation. The gaps between the segments are not counted in the distance.
+pub fn elevation_profile(route: &Route) -> Vec<(f64, f64)> {
+    let mut distance_km = 0.0;
+    let mut profile = vec![];
+    for segment in &route.segments {
+        for (index, point) in segment.iter().enumerate() {
+            if index > 0 {
+                distance_km += segment[index - 1]
+                    .coordinates()
+                    .distance_km(&point.coordinates());
+            }
+            if let Some(elevation) = point.elevation {
+                profile.push((distance_km, elevation));
+            }
+        }
+    }
+    profile
+}
+
+/// Segments of `route` projected on a plane with an equirectangular projection centered on the
+/// route, in kilometers to the east and to the south of the north-west corner of the route.
+pub fn projected_segments(route: &Route) -> Vec<Vec<(f64, f64)>> {
+    let bounds = match route.summary() {
+        Some(summary) => summary.bounds,
+        None => return vec![],
+    };
+    let km_per_degree = Coordinates::EARTH_RADIUS_KM.to_radians();
+    let longitude_scale = ((bounds.min_latitude + bounds.max_latitude) / 2.0)
+        .to_radians()
+        .cos();
+    route
+        .segments
+        .iter()
+        .map(|segment| {
+            segment
+                .iter()
+                .map(|point| {
+                    (
+                        (point.longitude - bounds.min_longitude) * km_per_degree * longitude_scale,
+                        (bounds.max_latitude - point.latitude) * km_per_degree,
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Value of the `points` attribute of an SVG polyline.
+fn svg_points(points: impl Iterator<Item = (f64, f64)>) -> String {
+    points
+        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn line<Ms>(points: String) -> Node<Ms> {
+    polyline![attrs! {
+        At::Points => points,
+        At::Fill => "none",
+        At::Stroke => "currentColor",
+        At::StrokeWidth => "2",
+    }]
+}
+
+/// Elevation along the route, `None` if the route does not have enough elevations.
+pub fn view_elevation_profile<Ms>(route: &Route) -> Option<Node<Ms>> {
+    let profile = elevation_profile(route);
+    let distance_km = profile.last()?.0;
+    if profile.len() < 2 || distance_km <= 0.0 {
+        return None;
+    }
+    let elevations = profile.iter().map(|(_, elevation)| *elevation);
+    let min_elevation = elevations.clone().fold(f64::INFINITY, f64::min);
+    let max_elevation = elevations.fold(f64::NEG_INFINITY, f64::max);
+    // a flat route is drawn in the middle
+    let elevation_range = (max_elevation - min_elevation).max(1.0);
+    let points = svg_points(profile.iter().map(|(distance, elevation)| {
+        (
+            MARGIN + distance / distance_km * (PROFILE_WIDTH - 2.0 * MARGIN),
+            PROFILE_HEIGHT
+                - MARGIN
+                - (elevation - min_elevation) / elevation_range * (PROFILE_HEIGHT - 2.0 * MARGIN),
+        )
+    }));
+    Some(div![
+        svg![
+            attrs! {
+                At::ViewBox => format!("0 0 {} {}", PROFILE_WIDTH, PROFILE_HEIGHT),
+                At::Width => PROFILE_WIDTH,
+                At::Height => PROFILE_HEIGHT,
+            },
+            line(points),
+        ],
+        div![format!(
+            "elevation between {:.0} m and {:.0} m over {:.1} km",
+            min_elevation, max_elevation, distance_km
+        )],
+    ])
+}
+
+/// Shape of the route as seen from above, north being up, `None` if the route is a single point.
+pub fn view_shape<Ms>(route: &Route) -> Option<Node<Ms>> {
+    let segments = projected_segments(route);
+    let points = segments.iter().flatten();
+    let width = points.clone().map(|(x, _)| *x).fold(0.0, f64::max);
+    let height = points.map(|(_, y)| *y).fold(0.0, f64::max);
+    let scale = (SHAPE_SIZE - 2.0 * MARGIN) / width.max(height);
+    if !scale.is_finite() {
+        return None;
+    }
+    let (svg_width, svg_height) = (width * scale + 2.0 * MARGIN, height * scale + 2.0 * MARGIN);
+    Some(svg![
+        attrs! {
+            At::ViewBox => format!("0 0 {:.1} {:.1}", svg_width, svg_height),
+            At::Width => format!("{:.1}", svg_width),
+            At::Height => format!("{:.1}", svg_height),
+        },
+        segments
+            .iter()
+            .map(|segment| line(svg_points(
+                segment
+                    .iter()
+                    .map(|(x, y)| (MARGIN + x * scale, MARGIN + y * scale))
+            )))
+            .collect::<Vec<Node<Ms>>>(),
+    ])
+}
@@ -2,7 +2,7 @@ use std::{any::Any, convert::identity};
 
 use futures::{executor::block_on, Future};
 use seed::{
-    app::{OrdersContainer, OrdersProxy},
+    app::{cmds, OrdersContainer, OrdersProxy},
     prelude::Orders,
     virtual_dom::Node,
 };
@@ -11,15 +11,24 @@ use crate::app::Model;
 
 pub struct OrdersMock<Ms, Model, Node> {
     messages: Vec<Ms>,
+    /// Delays of the commands performed with `perform_cmd_after`, which are performed at once.
+    delays: Vec<u32>,
     _model: Vec<Model>,
     _node: Vec<Node>,
 }
 
+impl<Ms: 'static, Model, Node> Default for OrdersMock<Ms, Model, Node> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // methods called in MyOrders
 impl<Ms: 'static, Model, Node> OrdersMock<Ms, Model, Node> {
     pub fn new() -> Self {
         OrdersMock {
             messages: vec![],
+            delays: vec![],
             _model: vec![],
             _node: vec![],
         }
@@ -32,7 +41,7 @@ impl<Ms: 'static, Model, Node> OrdersMock<Ms, Model, Node> {
         OrdersMock::new() // TODO: return a child orders mock that wraps messages and stores them in its parent
     }
 
-    fn perform_cmd<MsU: 'static>(self: &mut Self, cmd: impl Future<Output = MsU> + 'static) {
+    fn perform_cmd<MsU: 'static>(&mut self, cmd: impl Future<Output = MsU> + 'static) {
         let t_type = std::any::TypeId::of::<MsU>();
         let handler: Box<dyn Fn(MsU) -> Option<Ms>> = if t_type == std::any::TypeId::of::<Ms>() {
             Box::new(move |value| {
@@ -61,6 +70,15 @@ impl<Ms: 'static, Model, Node> OrdersMock<Ms, Model, Node> {
         }
     }
 
+    fn perform_cmd_after<MsU: 'static>(
+        &mut self,
+        delay_ms: u32,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) {
+        self.delays.push(delay_ms);
+        self.perform_cmd(cmd);
+    }
+
     fn subscribe<MsU: 'static, SubMs: 'static + Clone>(
         &mut self,
         _handler: impl FnOnce(SubMs) -> MsU + Clone + 'static,
@@ -75,9 +93,13 @@ impl<Ms: 'static, Model, Node> OrdersMock<Ms, Model, Node> {
 
 // methods used for testing purposes
 impl<Ms, Model, Node> OrdersMock<Ms, Model, Node> {
-    pub fn messages(self: &Self) -> &Vec<Ms> {
+    pub fn messages(&self) -> &Vec<Ms> {
         &self.messages
     }
+
+    pub fn delays(&self) -> &Vec<u32> {
+        &self.delays
+    }
 }
 
 pub enum OrdersImplementation<'a, Ms: 'static, AppMs: 'static> {
@@ -92,10 +114,17 @@ pub trait IMyOrders<Ms> {
     fn proxy<ChildMs: 'static>(
         &mut self,
         f: impl FnOnce(ChildMs) -> Ms + 'static + Clone,
-    ) -> MyOrders<ChildMs, Self::AppMs>;
+    ) -> MyOrders<'_, ChildMs, Self::AppMs>;
 
     fn perform_cmd<MsU: 'static>(&mut self, cmd: impl Future<Output = MsU> + 'static) -> &mut Self;
 
+    /// Perform `cmd` once `delay_ms` milliseconds have passed.
+    fn perform_cmd_after<MsU: 'static>(
+        &mut self,
+        delay_ms: u32,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self;
+
     fn subscribe<MsU: 'static, SubMs: 'static + Clone>(
         &mut self,
         handler: impl FnOnce(SubMs) -> MsU + Clone + 'static,
@@ -110,12 +139,10 @@ pub struct MyOrders<'a, Ms: 'static, AppMs: 'static> {
 
 impl<'a, Ms: 'static, AppMs: 'static> MyOrders<'a, Ms, AppMs> {
     pub fn new(implementation: OrdersImplementation<Ms, AppMs>) -> MyOrders<Ms, AppMs> {
-        MyOrders {
-            implementation: implementation,
-        }
+        MyOrders { implementation }
     }
 
-    pub fn mock(self: &'a Self) -> Option<&'a OrdersMock<Ms, Model, Node<AppMs>>> {
+    pub fn mock(&'a self) -> Option<&'a OrdersMock<Ms, Model, Node<AppMs>>> {
         match self.implementation {
             OrdersImplementation::Container(_) => None,
             OrdersImplementation::Proxy(_) => None,
@@ -130,7 +157,7 @@ impl<'a, Ms, AppMs> IMyOrders<Ms> for MyOrders<'a, Ms, AppMs> {
     fn proxy<ChildMs: 'static>(
         &mut self,
         f: impl FnOnce(ChildMs) -> Ms + 'static + Clone,
-    ) -> MyOrders<ChildMs, AppMs> {
+    ) -> MyOrders<'_, ChildMs, AppMs> {
         match self.implementation {
             OrdersImplementation::Container(ref mut _orders) => {
                 todo!()
@@ -163,6 +190,31 @@ impl<'a, Ms, AppMs> IMyOrders<Ms> for MyOrders<'a, Ms, AppMs> {
         self
     }
 
+    fn perform_cmd_after<MsU: 'static>(
+        &mut self,
+        delay_ms: u32,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self {
+        match self.implementation {
+            OrdersImplementation::Container(ref mut orders) => {
+                orders.perform_cmd(async move {
+                    cmds::timeout(delay_ms, || ()).await;
+                    cmd.await
+                });
+            }
+            OrdersImplementation::Proxy(ref mut orders) => {
+                orders.perform_cmd(async move {
+                    cmds::timeout(delay_ms, || ()).await;
+                    cmd.await
+                });
+            }
+            OrdersImplementation::Mock(ref mut orders) => {
+                orders.perform_cmd_after(delay_ms, cmd);
+            }
+        }
+        self
+    }
+
     fn subscribe<MsU: 'static, SubMs: 'static + Clone>(
         &mut self,
         handler: impl FnOnce(SubMs) -> MsU + Clone + 'static,
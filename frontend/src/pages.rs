@@ -1,3 +1,5 @@
+pub mod edit_event;
 pub mod event;
 pub mod login;
 pub mod main;
+pub mod notifications;
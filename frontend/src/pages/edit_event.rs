@@ -0,0 +1,204 @@
+use crate::app::Context;
+use crate::molecules::event_publication_form;
+use crate::molecules::login_bar;
+use crate::orders::perform_cmd;
+use crate::orders::IMyOrders;
+use common::api::BackendApi;
+use common::{Event, Id};
+use seed::{prelude::*, *};
+use std::rc::Rc;
+use std::str::FromStr;
+
+fn id_from_url(url: &mut Url) -> Result<Id, String> {
+    Id::from_str(
+        url.next_path_part()
+            .ok_or("expected an ID in the URL".to_owned())?,
+    )
+    .map_err(|err| err.to_string())
+}
+
+fn request_event(id: Id, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.get_event(id).await {
+            Ok(event) => Msg::Private(PrivateMsg::OnGetEventResponse(Box::new(event))),
+            Err(error) => Msg::Private(PrivateMsg::Error(error.message)),
+        }
+    });
+}
+
+pub fn init(url: &mut Url, context: &Context, orders: &mut impl IMyOrders<Msg>) -> Model {
+    match id_from_url(url) {
+        Ok(id) => {
+            request_event(id, orders, context.backend.clone());
+            Model {
+                state: State::Loading,
+            }
+        }
+        Err(err) => Model {
+            state: State::Failed(err),
+        },
+    }
+}
+
+type ErrorMessage = String;
+
+pub struct Model {
+    pub state: State,
+}
+
+pub enum State {
+    Loading,
+    Loaded(Box<Loaded>),
+    Failed(ErrorMessage),
+}
+
+pub struct Loaded {
+    event_publication_form: event_publication_form::Model,
+    login_bar: login_bar::Model,
+}
+
+impl Loaded {
+    fn new(event: Event, context: &Context) -> Loaded {
+        Loaded {
+            event_publication_form: event_publication_form::init_for_edit(event),
+            login_bar: login_bar::init(context.username.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    Public(PublicMsg),
+    Private(PrivateMsg),
+}
+
+#[derive(Clone, Debug)]
+pub enum PublicMsg {
+    /// The event was saved, the app should go back to the page of the event.
+    EventUpdated(Id),
+}
+
+#[derive(Clone, Debug)]
+pub enum PrivateMsg {
+    OnGetEventResponse(Box<Event>),
+    Error(String),
+    EventPublicationForm(event_publication_form::Msg),
+    LoginBar(login_bar::Msg),
+}
+
+pub fn update(
+    msg: PrivateMsg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match msg {
+        PrivateMsg::OnGetEventResponse(event) => {
+            on_get_event_response_msg(*event, model, context, orders)
+        }
+        PrivateMsg::Error(err) => model.state = State::Failed(err),
+        PrivateMsg::EventPublicationForm(msg) => {
+            event_publication_form_msg(msg, model, context, orders)
+        }
+        PrivateMsg::LoginBar(msg) => login_bar_msg(msg, model, context, orders),
+    }
+}
+
+fn on_get_event_response_msg(
+    event: Event,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &model.state {
+        State::Loading => {
+            model.state = if event.organizer.is_some() && event.organizer == context.username {
+                login_bar::request_unread_count(
+                    context,
+                    &mut orders.proxy(|msg| Msg::Private(PrivateMsg::LoginBar(msg))),
+                );
+                State::Loaded(Box::new(Loaded::new(event, context)))
+            } else {
+                State::Failed("only the organizer can edit this event".to_owned())
+            }
+        }
+        State::Loaded(_) => error!("received an event while loaded"),
+        State::Failed(_) => { /* nothing to do */ }
+    }
+}
+
+fn event_publication_form_msg(
+    msg: event_publication_form::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received an event publication form msg while loading"),
+        State::Loaded(loaded) => match msg {
+            event_publication_form::Msg::Public(msg) => match msg {
+                event_publication_form::PublicMsg::EventPublished => {
+                    error!("received an event published msg while editing an event")
+                }
+                event_publication_form::PublicMsg::EventUpdated(event) => {
+                    notify_event_updated(event.id, orders)
+                }
+            },
+            event_publication_form::Msg::Private(msg) => event_publication_form::update(
+                msg,
+                &mut loaded.event_publication_form,
+                context,
+                &mut orders.proxy(|msg| Msg::Private(PrivateMsg::EventPublicationForm(msg))),
+            ),
+        },
+        State::Failed(_) => error!("received an event publication form msg while failed"),
+    }
+}
+
+fn login_bar_msg(
+    msg: login_bar::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a login bar msg while loading"),
+        State::Loaded(loaded) => match msg {
+            login_bar::Msg::Public(msg) => match msg {
+                login_bar::PublicMsg::SignedOut => {
+                    model.state = State::Failed("only the organizer can edit this event".to_owned())
+                }
+            },
+            login_bar::Msg::Private(msg) => login_bar::update(
+                msg,
+                &mut loaded.login_bar,
+                context,
+                &mut orders.proxy(|msg| Msg::Private(PrivateMsg::LoginBar(msg))),
+            ),
+        },
+        State::Failed(_) => error!("received a login bar msg while failed"),
+    }
+}
+
+fn notify_event_updated(id: Id, orders: &mut impl IMyOrders<Msg>) {
+    perform_cmd(
+        orders,
+        async move { Msg::Public(PublicMsg::EventUpdated(id)) },
+    );
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    div![
+        h1!(a![attrs![At::Href => "/"], "join my ride"]),
+        match &model.state {
+            State::Loading => div!["loading..."],
+            State::Loaded(loaded) => div![
+                login_bar::view(&loaded.login_bar)
+                    .map_msg(|msg| Msg::Private(PrivateMsg::LoginBar(msg))),
+                event_publication_form::view(&loaded.event_publication_form)
+                    .map_msg(|msg| Msg::Private(PrivateMsg::EventPublicationForm(msg))),
+            ],
+            State::Failed(err) => div![err],
+        }
+    ]
+}
@@ -1,11 +1,12 @@
-use crate::app::Context;
-use crate::atoms::button;
+use crate::app::{self, Context};
+use crate::atoms::{button, input};
+use crate::molecules::comment_thread;
 use crate::molecules::event_details;
 use crate::molecules::login_bar;
 use crate::orders::perform_cmd;
 use crate::orders::IMyOrders;
 use common::api::BackendApi;
-use common::{Event, Id};
+use common::{ApiError, Event, EventStatus, EventUpdate, Id, Participation, Route};
 use seed::{prelude::*, *};
 use std::rc::Rc;
 use std::str::FromStr;
@@ -23,8 +24,17 @@ pub fn request_event(id: Id, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn B
     // log!("get event {}", id);
     perform_cmd(orders, async move {
         match backend.get_event(id).await {
-            Ok(event) => Msg::OnGetEventResponse(event),
-            Err(error) => Msg::Error(error),
+            Ok(event) => Msg::OnGetEventResponse(Box::new(event)),
+            Err(error) => Msg::Error(error.message),
+        }
+    });
+}
+
+pub fn request_route(id: Id, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.get_route(id).await {
+            Ok(route) => Msg::OnGetRouteResponse(route),
+            Err(error) => Msg::ActionFailed(error),
         }
     });
 }
@@ -35,10 +45,36 @@ pub fn join_event(id: Id, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn Back
     orders.perform_cmd(async move {
         match backend.join_event(id).await {
             Ok(_) => match backend.get_event(id).await {
-                Ok(event) => Msg::OnGetEventResponse(event),
-                Err(error) => Msg::Error(error),
+                Ok(event) => Msg::OnGetEventResponse(Box::new(event)),
+                Err(error) => Msg::Error(error.message),
+            },
+            Err(error) => Msg::ActionFailed(error),
+        }
+    });
+}
+
+pub fn leave_event(id: Id, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    orders.perform_cmd(async move {
+        match backend.leave_event(id).await {
+            Ok(_) => match backend.get_event(id).await {
+                Ok(event) => Msg::OnGetEventResponse(Box::new(event)),
+                Err(error) => Msg::Error(error.message),
             },
-            Err(error) => Msg::Error(error),
+            Err(error) => Msg::ActionFailed(error),
+        }
+    });
+}
+
+pub fn cancel_event(
+    id: Id,
+    reason: String,
+    orders: &mut impl IMyOrders<Msg>,
+    backend: Rc<dyn BackendApi>,
+) {
+    orders.perform_cmd(async move {
+        match backend.cancel_event(id, reason).await {
+            Ok(event) => Msg::OnGetEventResponse(Box::new(event)),
+            Err(error) => Msg::ActionFailed(error),
         }
     });
 }
@@ -65,7 +101,7 @@ pub struct Model {
 
 pub enum State {
     Loading,
-    Loaded(Loaded),
+    Loaded(Box<Loaded>),
     Failed(ErrorMessage),
 }
 
@@ -73,26 +109,88 @@ pub struct Loaded {
     event: Event,
     event_details: event_details::Model,
     join_button: button::Model,
+    leave_button: button::Model,
+    cancellation_reason: input::Model,
+    cancel_button: button::Model,
     login_bar: login_bar::Model,
+    comment_thread: comment_thread::Model,
+    /// Why the last join, leave or cancel request failed.
+    error: Option<String>,
+    /// Logged-in user, copied from the context to decide whether to show the join or leave button.
+    username: Option<String>,
 }
 
 impl Loaded {
     fn new(event: Event, context: &Context) -> Loaded {
+        let join_label = if event.is_full() {
+            "join the waitlist"
+        } else {
+            "join"
+        };
+        let leave_label = match context
+            .username
+            .as_ref()
+            .and_then(|username| event.participation(username))
+        {
+            Some(Participation::Waitlisted { .. }) => "leave the waitlist",
+            _ => "leave",
+        };
+        let comment_thread = comment_thread::init(event.id);
         Loaded {
             event: event.clone(),
-            event_details: event_details::init(event),
-            join_button: button::init("join".into()),
+            event_details: event_details::init(event, context.username.clone()),
+            join_button: button::init(join_label.into()),
+            leave_button: button::init(leave_label.into()),
+            cancellation_reason: input::init("reason".into()),
+            cancel_button: button::init("cancel the event".into()),
             login_bar: login_bar::init(context.username.clone()),
+            comment_thread,
+            error: None,
+            username: context.username.clone(),
+        }
+    }
+
+    /// Show the new state of the event, keeping the route, the comments and what the user typed.
+    fn refresh(&mut self, event: Event, context: &Context) {
+        let route = self.event_details.route.take();
+        let refreshed = Loaded::new(event, context);
+        self.event = refreshed.event;
+        self.event_details = refreshed.event_details;
+        self.event_details.route = route;
+        self.join_button = refreshed.join_button;
+        self.leave_button = refreshed.leave_button;
+    }
+
+    fn is_organizer(&self) -> bool {
+        self.username.is_some() && self.event.organizer == self.username
+    }
+
+    fn can_be_cancelled(&self) -> bool {
+        self.is_organizer() && self.event.status.can_become(EventStatus::Cancelled)
+    }
+
+    /// Whether the logged-in user is going or waitlisted.
+    fn is_participant(&self) -> bool {
+        match &self.username {
+            Some(username) => self.event.participation(username).is_some(),
+            None => false,
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum Msg {
-    OnGetEventResponse(Event),
+    OnGetEventResponse(Box<Event>),
+    OnGetRouteResponse(Route),
     Error(String),
     JoinButton(button::Msg),
+    LeaveButton(button::Msg),
+    CancellationReason(input::Msg),
+    CancelButton(button::Msg),
+    ActionFailed(ApiError),
     LoginBar(login_bar::Msg),
+    CommentThread(comment_thread::Msg),
+    EventUpdated(Box<EventUpdate>),
 }
 
 pub fn update(
@@ -102,10 +200,17 @@ pub fn update(
     orders: &mut impl IMyOrders<Msg>,
 ) {
     match msg {
-        Msg::OnGetEventResponse(event) => on_get_event_response_msg(event, model, context, orders),
+        Msg::OnGetEventResponse(event) => on_get_event_response_msg(*event, model, context, orders),
+        Msg::OnGetRouteResponse(route) => on_get_route_response_msg(route, model, context, orders),
         Msg::Error(err) => model.state = State::Failed(err),
         Msg::JoinButton(msg) => join_button_msg(msg, model, context, orders),
+        Msg::LeaveButton(msg) => leave_button_msg(msg, model, context, orders),
+        Msg::CancellationReason(msg) => cancellation_reason_msg(msg, model, context, orders),
+        Msg::CancelButton(msg) => cancel_button_msg(msg, model, context, orders),
+        Msg::ActionFailed(error) => action_failed_msg(error, model, context, orders),
         Msg::LoginBar(msg) => login_bar_msg(msg, model, context, orders),
+        Msg::CommentThread(msg) => comment_thread_msg(msg, model, context, orders),
+        Msg::EventUpdated(update) => event_updated_msg(*update, model, context, orders),
     }
 }
 
@@ -113,11 +218,79 @@ fn on_get_event_response_msg(
     event: Event,
     model: &mut Model,
     context: &mut Context,
-    _: &mut impl IMyOrders<Msg>,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    // the points of the route are only requested again if the route changed
+    let route = match &mut model.state {
+        State::Loaded(loaded) if loaded.event.route == event.route => {
+            loaded.event_details.route.take()
+        }
+        _ => None,
+    };
+    // the comments are only requested once, the thread is kept when the event is refreshed
+    let comment_thread = match &mut model.state {
+        State::Loaded(loaded) => Some(std::mem::replace(
+            &mut loaded.comment_thread,
+            comment_thread::init(event.id),
+        )),
+        _ => None,
+    };
+    match &model.state {
+        State::Loading | State::Loaded(_) => {
+            if route.is_none() && event.route.is_some() {
+                request_route(event.id, orders, context.backend.clone());
+            }
+            let mut loaded = Loaded::new(event, context);
+            loaded.event_details.route = route;
+            match comment_thread {
+                Some(comment_thread) => loaded.comment_thread = comment_thread,
+                None => {
+                    comment_thread::request_comments(
+                        loaded.event.id,
+                        None,
+                        &mut orders.proxy(Msg::CommentThread),
+                        context.backend.clone(),
+                    );
+                    login_bar::request_unread_count(context, &mut orders.proxy(Msg::LoginBar));
+                }
+            }
+            model.state = State::Loaded(Box::new(loaded));
+        }
+        State::Failed(_) => { /* nothing to do */ }
+    }
+}
+
+fn event_updated_msg(
+    update: EventUpdate,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loaded(loaded) if loaded.event.id == update.event().id => {
+            let event = update.into_event();
+            if event.route != loaded.event.route {
+                loaded.event_details.route = None;
+                if event.route.is_some() {
+                    request_route(event.id, orders, context.backend.clone());
+                }
+            }
+            loaded.refresh(event, context);
+        }
+        // another event changed, or the event is not displayed yet
+        _ => {}
+    }
+}
+
+fn on_get_route_response_msg(
+    route: Route,
+    model: &mut Model,
+    _context: &mut Context,
+    _orders: &mut impl IMyOrders<Msg>,
 ) {
     match &mut model.state {
-        State::Loading => model.state = State::Loaded(Loaded::new(event, context)),
-        State::Loaded(_loaded) => model.state = State::Loaded(Loaded::new(event, context)),
+        State::Loading => error!("received a route while loading the event"),
+        State::Loaded(loaded) => loaded.event_details.route = Some(route),
         State::Failed(_) => { /* nothing to do */ }
     }
 }
@@ -137,6 +310,71 @@ fn join_button_msg(
     }
 }
 
+fn leave_button_msg(
+    msg: button::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a leave button msg while loading"),
+        State::Loaded(loaded) => match msg {
+            button::Msg::Click => leave_event(loaded.event.id, orders, context.backend.clone()),
+        },
+        State::Failed(_) => error!("received a leave button msg while failed"),
+    }
+}
+
+fn action_failed_msg(
+    error: ApiError,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    if error.requires_login() {
+        app::redirect_to_login(context, orders);
+    }
+    match &mut model.state {
+        State::Loaded(loaded) => loaded.error = Some(error.message),
+        _ => model.state = State::Failed(error.message),
+    }
+}
+
+fn cancellation_reason_msg(
+    msg: input::Msg,
+    model: &mut Model,
+    _context: &mut Context,
+    _orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a cancellation reason msg while loading"),
+        State::Loaded(loaded) => {
+            loaded.cancellation_reason = input::update(&loaded.cancellation_reason, &msg)
+        }
+        State::Failed(_) => error!("received a cancellation reason msg while failed"),
+    }
+}
+
+fn cancel_button_msg(
+    msg: button::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a cancel button msg while loading"),
+        State::Loaded(loaded) => match msg {
+            button::Msg::Click => cancel_event(
+                loaded.event.id,
+                loaded.cancellation_reason.value.clone(),
+                orders,
+                context.backend.clone(),
+            ),
+        },
+        State::Failed(_) => error!("received a cancel button msg while failed"),
+    }
+}
+
 fn login_bar_msg(
     msg: login_bar::Msg,
     model: &mut Model,
@@ -147,7 +385,7 @@ fn login_bar_msg(
         State::Loading => error!("received a login bar msg while loading"),
         State::Loaded(loaded) => match msg {
             login_bar::Msg::Public(msg) => match msg {
-                login_bar::PublicMsg::SignedOut => { /* nothing to do */ }
+                login_bar::PublicMsg::SignedOut => loaded.username = context.username.clone(),
             },
             login_bar::Msg::Private(msg) => login_bar::update(
                 msg,
@@ -160,6 +398,24 @@ fn login_bar_msg(
     }
 }
 
+fn comment_thread_msg(
+    msg: comment_thread::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a comment thread msg while loading"),
+        State::Loaded(loaded) => comment_thread::update(
+            msg,
+            &mut loaded.comment_thread,
+            context,
+            &mut orders.proxy(Msg::CommentThread),
+        ),
+        State::Failed(_) => error!("received a comment thread msg while failed"),
+    }
+}
+
 pub fn view(model: &Model) -> Node<Msg> {
     div![
         h1!(a![attrs![At::Href => "/"], "join my ride"]),
@@ -171,11 +427,42 @@ pub fn view(model: &Model) -> Node<Msg> {
                     // TODO: remove this map_msg since events_list does not have any
                     Msg::Error("unexpected msg from event details".to_owned())
                 }),
-                button::view(
-                    &loaded.join_button,
-                    !loaded.event.participants.contains("valid_user")
+                loaded.is_organizer().then(|| a![
+                    attrs![At::Href => format!("/event/{}/edit", loaded.event.id)],
+                    "edit"
+                ]),
+                // the calendar file of a draft is only served to its organizer, which a link
+                // cannot authenticate
+                (loaded.event.start.is_some() && loaded.event.status != EventStatus::Draft).then(
+                    || a![
+                        attrs![At::Href => format!("/api/event/{}/ics", loaded.event.id)],
+                        "add to calendar"
+                    ]
+                ),
+                if loaded.is_participant() {
+                    button::view(&loaded.leave_button, true).map_msg(Msg::LeaveButton)
+                } else {
+                    button::view(
+                        &loaded.join_button,
+                        loaded.username.is_some() && loaded.event.status.can_be_joined(),
+                    )
+                    .map_msg(Msg::JoinButton)
+                },
+                loaded.can_be_cancelled().then(|| div![
+                    input::view(&loaded.cancellation_reason).map_msg(Msg::CancellationReason),
+                    button::view(
+                        &loaded.cancel_button,
+                        !loaded.cancellation_reason.value.trim().is_empty()
+                    )
+                    .map_msg(Msg::CancelButton)
+                ]),
+                loaded.error.as_ref().map(|error| div![error]),
+                comment_thread::view(
+                    &loaded.comment_thread,
+                    loaded.username.as_deref(),
+                    loaded.is_organizer()
                 )
-                .map_msg(Msg::JoinButton)
+                .map_msg(Msg::CommentThread)
             ],
             State::Failed(err) => div![err],
         }
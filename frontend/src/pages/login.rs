@@ -2,7 +2,9 @@ use crate::{
     app::Context,
     orders::{perform_cmd, IMyOrders},
 };
+use common::{api::BackendApi, Credentials};
 use seed::{prelude::*, *};
+use std::rc::Rc;
 
 use crate::atoms::{button, input};
 
@@ -14,13 +16,10 @@ pub fn init(
 ) -> Model {
     let stage = match &context.username {
         Some(_) => Stage::SignedIn,
-        None => Stage::SignedOut(SignedOut {
-            username_input: input::init("username".into()),
-            login_button: button::init("login".into()),
-        }),
+        None => Stage::SignedOut(Box::new(SignedOut::new(None))),
     };
     Model {
-        stage: stage,
+        stage,
         url: target_url,
     }
 }
@@ -31,14 +30,40 @@ pub struct Model {
 }
 
 pub enum Stage {
-    SignedOut(SignedOut),
+    SignedOut(Box<SignedOut>),
     LoggingIn,
     SignedIn,
 }
 
 pub struct SignedOut {
     username_input: input::Model,
+    password_input: input::Model,
     login_button: button::Model,
+    signup_button: button::Model,
+    error: Option<String>,
+}
+
+impl SignedOut {
+    fn new(error: Option<String>) -> SignedOut {
+        SignedOut {
+            username_input: input::init("username".into()),
+            password_input: input::init_with_type("password".into(), "password"),
+            login_button: button::init("login".into()),
+            signup_button: button::init("sign up".into()),
+            error,
+        }
+    }
+
+    fn is_filled(&self) -> bool {
+        !self.username_input.value.is_empty() && !self.password_input.value.is_empty()
+    }
+
+    fn credentials(&self) -> Credentials {
+        Credentials {
+            username: self.username_input.value.clone(),
+            password: self.password_input.value.clone(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -55,8 +80,11 @@ pub enum PublicMsg {
 #[derive(Clone, Debug)]
 pub enum PrivateMsg {
     UsernameInput(input::Msg),
+    PasswordInput(input::Msg),
     LoginButton(button::Msg),
+    SignupButton(button::Msg),
     LoggedIn(String),
+    LoginFailed(String),
 }
 
 pub fn update(
@@ -67,8 +95,13 @@ pub fn update(
 ) {
     match msg {
         PrivateMsg::UsernameInput(msg) => username_input_msg(msg, model, context, orders),
+        PrivateMsg::PasswordInput(msg) => password_input_msg(msg, model, context, orders),
         PrivateMsg::LoginButton(msg) => login_button_msg(msg, model, context, orders),
+        PrivateMsg::SignupButton(msg) => signup_button_msg(msg, model, context, orders),
         PrivateMsg::LoggedIn(username) => logged_in_msg(username, model, context, orders),
+        PrivateMsg::LoginFailed(error) => {
+            model.stage = Stage::SignedOut(Box::new(SignedOut::new(Some(error))))
+        }
     }
 }
 
@@ -87,19 +120,34 @@ fn username_input_msg(
     }
 }
 
+fn password_input_msg(
+    msg: input::Msg,
+    model: &mut Model,
+    _context: &mut Context,
+    _orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.stage {
+        Stage::SignedOut(model) => match msg {
+            input::Msg::ValueChange(value) => model.password_input.value = value,
+        },
+        Stage::LoggingIn => error!("received password input msg while logging in"),
+        Stage::SignedIn => error!("received password input msg while signed in"),
+    }
+}
+
 fn login_button_msg(
     msg: button::Msg,
     model: &mut Model,
-    _context: &mut Context,
+    context: &mut Context,
     orders: &mut impl IMyOrders<Msg>,
 ) {
     match &mut model.stage {
         Stage::SignedOut(signed_out) => match msg {
             button::Msg::Click => {
-                if signed_out.username_input.value.is_empty() {
-                    error!("received login button msg but username input is empty")
+                if !signed_out.is_filled() {
+                    error!("received login button msg but the form is not filled")
                 } else {
-                    login(signed_out.username_input.value.clone(), orders);
+                    login(signed_out.credentials(), orders, context.backend.clone());
                     model.stage = Stage::LoggingIn;
                 }
             }
@@ -109,6 +157,28 @@ fn login_button_msg(
     }
 }
 
+fn signup_button_msg(
+    msg: button::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.stage {
+        Stage::SignedOut(signed_out) => match msg {
+            button::Msg::Click => {
+                if !signed_out.is_filled() {
+                    error!("received signup button msg but the form is not filled")
+                } else {
+                    signup(signed_out.credentials(), orders, context.backend.clone());
+                    model.stage = Stage::LoggingIn;
+                }
+            }
+        },
+        Stage::LoggingIn => error!("received signup button msg while logging in"),
+        Stage::SignedIn => error!("received signup button msg while signed in"),
+    }
+}
+
 fn logged_in_msg(
     username: String,
     model: &mut Model,
@@ -130,12 +200,16 @@ pub fn view(model: &Model) -> Node<Msg> {
                     input::view(&signed_out.username_input)
                         .map_msg(PrivateMsg::UsernameInput)
                         .map_msg(Msg::Private),
-                    button::view(
-                        &signed_out.login_button,
-                        !signed_out.username_input.value.is_empty()
-                    )
-                    .map_msg(PrivateMsg::LoginButton)
-                    .map_msg(Msg::Private)
+                    input::view(&signed_out.password_input)
+                        .map_msg(PrivateMsg::PasswordInput)
+                        .map_msg(Msg::Private),
+                    button::view(&signed_out.login_button, signed_out.is_filled())
+                        .map_msg(PrivateMsg::LoginButton)
+                        .map_msg(Msg::Private),
+                    button::view(&signed_out.signup_button, signed_out.is_filled())
+                        .map_msg(PrivateMsg::SignupButton)
+                        .map_msg(Msg::Private),
+                    signed_out.error.as_ref().map(|error| div![error]),
                 ]
             }
             Stage::LoggingIn => {
@@ -148,10 +222,25 @@ pub fn view(model: &Model) -> Node<Msg> {
     ]
 }
 
-fn login(username: String, orders: &mut impl IMyOrders<Msg>) {
-    perform_cmd(orders, async {
-        // TODO: login
-        Msg::Private(PrivateMsg::LoggedIn(username))
+fn login(credentials: Credentials, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.login(credentials).await {
+            Ok(session) => Msg::Private(PrivateMsg::LoggedIn(session.username)),
+            Err(error) => Msg::Private(PrivateMsg::LoginFailed(error.message)),
+        }
+    });
+}
+
+fn signup(credentials: Credentials, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        let login = match backend.signup(credentials.clone()).await {
+            Ok(()) => backend.login(credentials).await,
+            Err(error) => Err(error),
+        };
+        match login {
+            Ok(session) => Msg::Private(PrivateMsg::LoggedIn(session.username)),
+            Err(error) => Msg::Private(PrivateMsg::LoginFailed(error.message)),
+        }
     });
 }
 
@@ -1,17 +1,28 @@
 use std::rc::Rc;
 
 use crate::app::Context;
+use crate::atoms::button;
 use crate::molecules::event_publication_form;
 use crate::molecules::events_list;
 use crate::molecules::login_bar;
 use crate::orders::perform_cmd;
 use crate::orders::IMyOrders;
 use common::api::BackendApi;
-use common::Event;
+use common::{Event, EventQuery, EventUpdate, Page};
 use seed::{prelude::*, *};
 
+/// Number of events displayed at once.
+const PAGE_SIZE: usize = 10;
+
+fn first_page() -> EventQuery {
+    EventQuery {
+        limit: Some(PAGE_SIZE),
+        ..EventQuery::default()
+    }
+}
+
 pub fn init(_: &mut Url, context: &Context, orders: &mut impl IMyOrders<Msg>) -> Model {
-    request_events(orders, context.backend.clone());
+    request_events(first_page(), orders, context.backend.clone());
     Model {
         state: State::Loading,
     }
@@ -25,7 +36,7 @@ pub struct Model {
 
 pub enum State {
     Loading,
-    Loaded(Loaded),
+    Loaded(Box<Loaded>),
     Failed(ErrorMessage),
 }
 
@@ -33,24 +44,39 @@ pub struct Loaded {
     pub event_list: events_list::Model,
     pub event_publication_form: event_publication_form::Model,
     pub login_bar: login_bar::Model,
+    /// Query of the displayed page.
+    query: EventQuery,
+    /// Cursors of the pages before the displayed one, the first page having no cursor.
+    previous_cursors: Vec<Option<String>>,
+    next_cursor: Option<String>,
+    previous_button: button::Model,
+    next_button: button::Model,
 }
 
 impl Loaded {
-    fn new(events: Vec<Event>, context: &Context) -> Loaded {
+    fn new(page: Page<Event>, context: &Context) -> Loaded {
         Loaded {
-            event_list: events_list::init(events),
+            event_list: events_list::init(page.items),
             event_publication_form: event_publication_form::init(),
             login_bar: login_bar::init(context.username.clone()),
+            query: first_page(),
+            previous_cursors: vec![],
+            next_cursor: page.next_cursor,
+            previous_button: button::init("previous".into()),
+            next_button: button::init("next".into()),
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum Msg {
-    OnGetEventsResponse(Vec<Event>),
+    OnGetEventsResponse(Page<Event>),
+    PreviousButton(button::Msg),
+    NextButton(button::Msg),
     EventPublication(event_publication_form::Msg),
     Error(String),
     LoginBar(login_bar::Msg),
+    EventUpdated(Box<EventUpdate>),
 }
 
 pub fn update(
@@ -60,28 +86,104 @@ pub fn update(
     orders: &mut impl IMyOrders<Msg>,
 ) {
     match msg {
-        Msg::OnGetEventsResponse(events) => {
-            on_get_events_response_msg(events, model, context, orders)
-        }
+        Msg::OnGetEventsResponse(page) => on_get_events_response_msg(page, model, context, orders),
+        Msg::PreviousButton(msg) => previous_button_msg(msg, model, context, orders),
+        Msg::NextButton(msg) => next_button_msg(msg, model, context, orders),
         Msg::EventPublication(msg) => event_publication_form_msg(msg, model, context, orders),
         Msg::Error(err) => model.state = State::Failed(err),
         Msg::LoginBar(msg) => login_bar_msg(msg, model, context, orders),
+        Msg::EventUpdated(update) => event_updated_msg(*update, model, context, orders),
     }
 }
 
 fn on_get_events_response_msg(
-    events: Vec<Event>,
+    page: Page<Event>,
     model: &mut Model,
     context: &mut Context,
-    _: &mut impl IMyOrders<Msg>,
+    orders: &mut impl IMyOrders<Msg>,
 ) {
     match &mut model.state {
-        State::Loading => model.state = State::Loaded(Loaded::new(events, context)),
-        State::Loaded(loaded) => loaded.event_list = events_list::init(events),
+        State::Loading => {
+            model.state = State::Loaded(Box::new(Loaded::new(page, context)));
+            login_bar::request_unread_count(context, &mut orders.proxy(Msg::LoginBar));
+        }
+        State::Loaded(loaded) => {
+            loaded.event_list = events_list::init(page.items);
+            loaded.next_cursor = page.next_cursor;
+        }
         State::Failed(_) => { /* nothing to do */ }
     }
 }
 
+fn event_updated_msg(
+    update: EventUpdate,
+    model: &mut Model,
+    _context: &mut Context,
+    _orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loaded(loaded) => match update {
+            // the events are listed in publication order, a new event comes after the last one
+            EventUpdate::Created { event } => {
+                if loaded.next_cursor.is_none() {
+                    let events = &mut loaded.event_list.events;
+                    if events.len() < PAGE_SIZE {
+                        events.push(event);
+                    } else {
+                        loaded.next_cursor = events.last().map(|last| last.id.to_string());
+                    }
+                }
+            }
+            update => events_list::update_event(&mut loaded.event_list, update.into_event()),
+        },
+        // the events are listed as they are once loaded
+        State::Loading | State::Failed(_) => {}
+    }
+}
+
+fn previous_button_msg(
+    msg: button::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a previous button msg while loading"),
+        State::Loaded(loaded) => match msg {
+            button::Msg::Click => match loaded.previous_cursors.pop() {
+                Some(cursor) => {
+                    loaded.query.cursor = cursor;
+                    request_events(loaded.query.clone(), orders, context.backend.clone());
+                }
+                None => error!("received a previous button msg on the first page"),
+            },
+        },
+        State::Failed(_) => error!("received a previous button msg while failed"),
+    }
+}
+
+fn next_button_msg(
+    msg: button::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a next button msg while loading"),
+        State::Loaded(loaded) => match msg {
+            button::Msg::Click => match loaded.next_cursor.take() {
+                Some(cursor) => {
+                    let current_cursor = loaded.query.cursor.replace(cursor);
+                    loaded.previous_cursors.push(current_cursor);
+                    request_events(loaded.query.clone(), orders, context.backend.clone());
+                }
+                None => error!("received a next button msg on the last page"),
+            },
+        },
+        State::Failed(_) => error!("received a next button msg while failed"),
+    }
+}
+
 fn event_publication_form_msg(
     msg: event_publication_form::Msg,
     model: &mut Model,
@@ -93,9 +195,14 @@ fn event_publication_form_msg(
         State::Loaded(loaded) => match msg {
             event_publication_form::Msg::Public(msg) => match msg {
                 event_publication_form::PublicMsg::EventPublished => {
-                    request_events(orders, context.backend.clone());
+                    loaded.query = first_page();
+                    loaded.previous_cursors.clear();
+                    request_events(loaded.query.clone(), orders, context.backend.clone());
                     loaded.event_publication_form = event_publication_form::init();
                 }
+                event_publication_form::PublicMsg::EventUpdated(_) => {
+                    error!("received an event updated msg from the publication form")
+                }
             },
             event_publication_form::Msg::Private(msg) => event_publication_form::update(
                 msg,
@@ -143,6 +250,22 @@ pub fn view(model: &Model) -> Node<Msg> {
                         // TODO: remove this map_msg since events_list does not have any
                         Msg::Error("unexpected msg from events list".to_owned())
                     }),
+                    div![
+                        button::view(
+                            &loaded_state.previous_button,
+                            !loaded_state.previous_cursors.is_empty()
+                        )
+                        .map_msg(Msg::PreviousButton),
+                        button::view(
+                            &loaded_state.next_button,
+                            loaded_state.next_cursor.is_some()
+                        )
+                        .map_msg(Msg::NextButton),
+                    ],
+                    a![
+                        attrs![At::Href => "/api/events/atom", At::Type => "application/atom+xml"],
+                        "follow new rides in a feed reader"
+                    ],
                     event_publication_form::view(&loaded_state.event_publication_form)
                         .map_msg(Msg::EventPublication),
                 ]
@@ -152,13 +275,17 @@ pub fn view(model: &Model) -> Node<Msg> {
     ]
 }
 
-pub fn request_events(orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+pub fn request_events(
+    query: EventQuery,
+    orders: &mut impl IMyOrders<Msg>,
+    backend: Rc<dyn BackendApi>,
+) {
     // TODO: refactor this to use a logging service
-    // log!("get all events");
+    // log!("get events");
     perform_cmd(orders, async move {
-        match backend.get_events().await {
-            Ok(events) => Msg::OnGetEventsResponse(events),
-            Err(error) => Msg::Error(error),
+        match backend.get_events(query).await {
+            Ok(page) => Msg::OnGetEventsResponse(page),
+            Err(error) => Msg::Error(error.message),
         }
     });
 }
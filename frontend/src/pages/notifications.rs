@@ -0,0 +1,222 @@
+use crate::app::{self, Context};
+use crate::atoms::button;
+use crate::molecules::event_details::local_time;
+use crate::molecules::login_bar;
+use crate::orders::perform_cmd;
+use crate::orders::IMyOrders;
+use common::api::BackendApi;
+use common::{ApiError, Id, Notification};
+use seed::{prelude::*, *};
+use std::rc::Rc;
+
+pub fn init(_: &mut Url, context: &Context, orders: &mut impl IMyOrders<Msg>) -> Model {
+    request_notifications(orders, context.backend.clone());
+    Model {
+        state: State::Loading,
+    }
+}
+
+type ErrorMessage = String;
+
+pub struct Model {
+    pub state: State,
+}
+
+pub enum State {
+    Loading,
+    Loaded(Box<Loaded>),
+    Failed(ErrorMessage),
+}
+
+pub struct Loaded {
+    /// Notifications of the logged-in user, newest first.
+    notifications: Vec<Notification>,
+    login_bar: login_bar::Model,
+    mark_read_button: button::Model,
+    mark_all_read_button: button::Model,
+    /// Why the last request failed.
+    error: Option<String>,
+}
+
+impl Loaded {
+    fn new(notifications: Vec<Notification>, context: &Context) -> Loaded {
+        let mut loaded = Loaded {
+            notifications,
+            login_bar: login_bar::init(context.username.clone()),
+            mark_read_button: button::init("mark read".into()),
+            mark_all_read_button: button::init("mark all read".into()),
+            error: None,
+        };
+        loaded.update_unread_count();
+        loaded
+    }
+
+    fn unread(&self) -> usize {
+        self.notifications
+            .iter()
+            .filter(|notification| !notification.read)
+            .count()
+    }
+
+    fn update_unread_count(&mut self) {
+        let unread = self.unread();
+        login_bar::set_unread(&mut self.login_bar, unread);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Msg {
+    OnGetNotifications(Vec<Notification>),
+    MarkReadButton(Id),
+    OnMarkedRead(Id),
+    MarkAllReadButton(button::Msg),
+    OnMarkedAllRead,
+    RequestFailed(ApiError),
+    LoginBar(login_bar::Msg),
+}
+
+fn request_notifications(orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.get_notifications().await {
+            Ok(notifications) => Msg::OnGetNotifications(notifications),
+            Err(error) => Msg::RequestFailed(error),
+        }
+    });
+}
+
+fn mark_read(id: Id, orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.mark_notification_read(id).await {
+            Ok(()) => Msg::OnMarkedRead(id),
+            Err(error) => Msg::RequestFailed(error),
+        }
+    });
+}
+
+fn mark_all_read(orders: &mut impl IMyOrders<Msg>, backend: Rc<dyn BackendApi>) {
+    perform_cmd(orders, async move {
+        match backend.mark_all_notifications_read().await {
+            Ok(()) => Msg::OnMarkedAllRead,
+            Err(error) => Msg::RequestFailed(error),
+        }
+    });
+}
+
+pub fn update(
+    msg: Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match msg {
+        Msg::OnGetNotifications(notifications) => {
+            model.state = State::Loaded(Box::new(Loaded::new(notifications, context)))
+        }
+        Msg::MarkReadButton(id) => mark_read(id, orders, context.backend.clone()),
+        Msg::OnMarkedRead(id) => {
+            if let State::Loaded(loaded) = &mut model.state {
+                if let Some(notification) = loaded
+                    .notifications
+                    .iter_mut()
+                    .find(|notification| notification.id == id)
+                {
+                    notification.read = true;
+                }
+                loaded.update_unread_count();
+            }
+        }
+        Msg::MarkAllReadButton(button::Msg::Click) => {
+            mark_all_read(orders, context.backend.clone())
+        }
+        Msg::OnMarkedAllRead => {
+            if let State::Loaded(loaded) = &mut model.state {
+                for notification in &mut loaded.notifications {
+                    notification.read = true;
+                }
+                loaded.update_unread_count();
+            }
+        }
+        Msg::RequestFailed(error) => {
+            if error.requires_login() {
+                app::redirect_to_login(context, orders);
+            }
+            match &mut model.state {
+                State::Loaded(loaded) => loaded.error = Some(error.message),
+                State::Loading | State::Failed(_) => model.state = State::Failed(error.message),
+            }
+        }
+        Msg::LoginBar(msg) => login_bar_msg(msg, model, context, orders),
+    }
+}
+
+fn login_bar_msg(
+    msg: login_bar::Msg,
+    model: &mut Model,
+    context: &mut Context,
+    orders: &mut impl IMyOrders<Msg>,
+) {
+    match &mut model.state {
+        State::Loading => error!("received a login bar msg while loading"),
+        State::Loaded(loaded) => match msg {
+            login_bar::Msg::Public(msg) => match msg {
+                login_bar::PublicMsg::SignedOut => {
+                    model.state = State::Failed("log in to read your notifications".to_owned())
+                }
+            },
+            login_bar::Msg::Private(msg) => login_bar::update(
+                msg,
+                &mut loaded.login_bar,
+                context,
+                &mut orders.proxy(Msg::LoginBar),
+            ),
+        },
+        State::Failed(_) => error!("received a login bar msg while failed"),
+    }
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    div![
+        h1!(a![attrs![At::Href => "/"], "join my ride"]),
+        match &model.state {
+            State::Loading => div!["loading..."],
+            State::Loaded(loaded) => div![
+                login_bar::view(&loaded.login_bar).map_msg(Msg::LoginBar),
+                h2!("notifications"),
+                if loaded.notifications.is_empty() {
+                    vec![div!["no notifications"]]
+                } else {
+                    loaded
+                        .notifications
+                        .iter()
+                        .map(|notification| view_notification(loaded, notification))
+                        .collect()
+                },
+                button::view(&loaded.mark_all_read_button, loaded.unread() > 0)
+                    .map_msg(Msg::MarkAllReadButton),
+                loaded.error.as_ref().map(|error| div![error])
+            ],
+            State::Failed(error) => div![error],
+        }
+    ]
+}
+
+fn view_notification(loaded: &Loaded, notification: &Notification) -> Node<Msg> {
+    let id = notification.id;
+    let text = match notification.event_id {
+        Some(event_id) => a![
+            attrs![At::Href => format!("/event/{}", event_id)],
+            &notification.text
+        ],
+        None => span![&notification.text],
+    };
+    div![
+        div![local_time(&notification.created_at)],
+        if notification.read {
+            text
+        } else {
+            strong![text]
+        },
+        (!notification.read).then(|| button::view(&loaded.mark_read_button, true)
+            .map_msg(move |_| Msg::MarkReadButton(id)))
+    ]
+}
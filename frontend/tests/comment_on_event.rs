@@ -0,0 +1,163 @@
+use chrono::Utc;
+use common::api::MockBackendApi;
+use common::{Comment, Event, Id, Page};
+use frontend::app::{self, Msg};
+use frontend::molecules::comment_thread;
+use frontend::orders::{MyOrders, OrdersImplementation, OrdersMock};
+use frontend::pages::event;
+use seed::Url;
+use std::rc::Rc;
+
+mod html_query;
+use html_query::assert_contains_text;
+
+fn comment(event: &Event, author: &str, text: &str) -> Comment {
+    Comment {
+        id: Id::new_v4(),
+        event_id: event.id,
+        author: author.into(),
+        text: text.into(),
+        posted_at: Utc::now(),
+        edited_at: None,
+    }
+}
+
+#[test]
+fn event_page_displays_the_comments_a_page_at_a_time() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given an event with a long thread of comments
+    let mut event = Event::new("event name".into());
+    event.organizer = Some("organizer".into());
+    let first_page = Page {
+        items: vec![comment(&event, "rider", "Is the route paved?")],
+        next_cursor: Some("cursor".into()),
+    };
+    let mut last_page = Page {
+        items: vec![comment(
+            &event,
+            "organizer",
+            "Mostly, a few gravel sections.",
+        )],
+        next_cursor: None,
+    };
+    last_page.items[0].edited_at = Some(Utc::now());
+
+    // expect the front-end to request the event and the first page of comments
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    let first_page_backend_mock = first_page.clone();
+    backend
+        .expect_get_comments()
+        .withf(|_, cursor| cursor.is_none())
+        .returning(move |_, _| Ok(first_page_backend_mock.clone()));
+    backend.expect_get_notifications().returning(|| Ok(vec![]));
+
+    // given a user opens the event page
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+    app_.context.username = Some("rider".into());
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+
+    // when the backend responds with the first page of comments
+    app::testable_update(
+        app::Msg::Event(event::Msg::CommentThread(
+            comment_thread::Msg::OnGetComments(first_page),
+        )),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page displays the comments, offers to edit the user's comment and to load more
+    let view = app::view(&app_);
+    assert_contains_text(&view, "Is the route paved?");
+    assert_contains_text(&view, "edit");
+    assert_contains_text(&view, "more comments");
+
+    // when the user loads the next page
+    app::testable_update(
+        app::Msg::Event(event::Msg::CommentThread(
+            comment_thread::Msg::OnGetComments(last_page),
+        )),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page displays the comments of both pages
+    let view = app::view(&app_);
+    assert_contains_text(&view, "Is the route paved?");
+    assert_contains_text(&view, "Mostly, a few gravel sections.");
+    assert_contains_text(&view, "(edited)");
+}
+
+#[test]
+fn organizer_can_delete_comments() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given an event with a comment
+    let mut event = Event::new("event name".into());
+    event.organizer = Some("organizer".into());
+    let spam = comment(&event, "spammer", "buy cheap bikes");
+
+    // expect the front-end to request the event and its comments
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    let comments = Page {
+        items: vec![spam.clone()],
+        next_cursor: None,
+    };
+    let comments_backend_mock = comments.clone();
+    backend
+        .expect_get_comments()
+        .returning(move |_, _| Ok(comments_backend_mock.clone()));
+    backend.expect_get_notifications().returning(|| Ok(vec![]));
+
+    // expect the front-end to delete the comment once
+    let spam_id = spam.id;
+    backend
+        .expect_delete_comment()
+        .withf(move |id| *id == spam_id)
+        .times(1)
+        .returning(|_| Ok(()));
+
+    // given the organizer opens the event page
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+    app_.context.username = Some("organizer".into());
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+    app::testable_update(
+        app::Msg::Event(event::Msg::CommentThread(
+            comment_thread::Msg::OnGetComments(comments),
+        )),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page offers the organizer to delete the comment
+    let view = app::view(&app_);
+    assert_contains_text(&view, "buy cheap bikes");
+    assert_contains_text(&view, "delete");
+
+    // when the organizer deletes it
+    app::testable_update(
+        app::Msg::Event(event::Msg::CommentThread(
+            comment_thread::Msg::DeleteButton(spam.id),
+        )),
+        &mut app_,
+        &mut orders,
+    );
+}
@@ -18,10 +18,13 @@ fn main_page_requests_all_events_and_displays_them() {
 
     // expect the front-end to request all events
     let mut backend = MockBackendApi::new();
-    let events_backend_mock = vec![event_1.clone(), event_2.clone()];
+    let events_backend_mock = common::Page {
+        items: vec![event_1.clone(), event_2.clone()],
+        next_cursor: None,
+    };
     backend
         .expect_get_events()
-        .returning(move || Ok(events_backend_mock.clone())); // TODO: why do I need to move AND copy events_backend_mock?
+        .returning(move |_| Ok(events_backend_mock.clone())); // TODO: why do I need to move AND copy events_backend_mock?
 
     // given a new app is being initialized
     let mut app_ = app::testable_init(Url::new(), &mut orders, Rc::new(backend));
@@ -29,10 +32,10 @@ fn main_page_requests_all_events_and_displays_them() {
     // when the backend responds with events
     // assert!(matches!(orders.mock().unwrap().messages().last(), Some(app::Msg::Main(main::Msg::OnGetEventsResponse(..))))); // TODO: uncomment and fix
     app::testable_update(
-        app::Msg::Main(main::Msg::OnGetEventsResponse(vec![
-            event_1.clone(),
-            event_2.clone(),
-        ])),
+        app::Msg::Main(main::Msg::OnGetEventsResponse(common::Page {
+            items: vec![event_1.clone(), event_2.clone()],
+            next_cursor: None,
+        })),
         &mut app_,
         &mut orders,
     );
@@ -49,4 +52,7 @@ fn main_page_requests_all_events_and_displays_them() {
     let view = app::view(&app_);
     assert_contains_text(&view, &event_1.name);
     assert_contains_text(&view, &event_2.name);
+
+    // and then the page offers to follow the new events in a feed reader
+    assert_contains_text(&view, "follow new rides in a feed reader");
 }
@@ -0,0 +1,208 @@
+use chrono::{DateTime, Duration};
+use common::api::MockBackendApi;
+use common::{
+    Difficulty, Discipline, Event, Frequency, Id, MeetingPoint, Pace, Page, Recurrence, Route,
+    RoutePoint, Series,
+};
+use frontend::app::{self, Msg};
+use frontend::molecules::event_details::local_time;
+use frontend::orders::{MyOrders, OrdersImplementation, OrdersMock};
+use frontend::pages::event;
+use seed::Url;
+use std::rc::Rc;
+
+mod html_query;
+use html_query::assert_contains_text;
+
+#[test]
+fn event_page_displays_the_schedule_in_local_time() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given an event with a start and an end in the timezone of the organizer
+    let mut event = Event::new("event name".into());
+    let start = DateTime::parse_from_rfc3339("2030-06-01T09:00:00+02:00").unwrap();
+    event.start = Some(start);
+    event.end = Some(start + Duration::hours(3));
+
+    // expect the front-end to request the event and its comments
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    backend.expect_get_comments().returning(|_, _| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+
+    // given a user opens the event page
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+
+    // when the backend responds with the event
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page displays the start and the end in the timezone of the user
+    let view = app::view(&app_);
+    assert_contains_text(&view, &format!("starts {}", local_time(&start)));
+    assert_contains_text(&view, &format!("ends {}", local_time(&event.end.unwrap())));
+
+    // then the page offers to add the event to a calendar
+    assert_contains_text(&view, "add to calendar");
+}
+
+#[test]
+fn event_page_displays_the_meeting_point() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given an event with a meeting point
+    let mut event = Event::new("event name".into());
+    event.meeting_point = Some(MeetingPoint {
+        address: "Place de la Bastille".into(),
+        latitude: 48.8532,
+        longitude: 2.3691,
+    });
+
+    // expect the front-end to request the event and its comments
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    backend.expect_get_comments().returning(|_, _| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+
+    // given a user opens the event page
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+
+    // when the backend responds with the event
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page displays the address of the meeting point
+    let view = app::view(&app_);
+    assert_contains_text(&view, "Place de la Bastille");
+}
+
+#[test]
+fn event_page_displays_the_classification_and_the_recurrence_of_the_ride() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given a classified weekly ride
+    let mut event = Event::new("event name".into());
+    event.discipline = Some(Discipline::Gravel);
+    event.pace = Some(Pace::Moderate);
+    event.difficulty = Some(Difficulty::Easy);
+    event.no_drop = true;
+    event.series = Some(Series {
+        id: Id::new_v4(),
+        recurrence: Recurrence::new(Frequency::Weekly),
+    });
+
+    // expect the front-end to request the event and its comments
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    backend.expect_get_comments().returning(|_, _| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+
+    // given a user opens the event page
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+
+    // when the backend responds with the event
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page displays the discipline, the pace, the difficulty, the no-drop policy and
+    // the recurrence
+    let view = app::view(&app_);
+    assert_contains_text(&view, "repeats every week");
+    assert_contains_text(
+        &view,
+        "gravel, pace: moderate (20 to 25 km/h), difficulty: easy, no-drop",
+    );
+}
+
+#[test]
+fn event_page_draws_the_route() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given an event with a route going north, climbing from 100 m to 150 m
+    let point = |latitude, elevation| RoutePoint {
+        latitude,
+        longitude: 2.3,
+        elevation: Some(elevation),
+    };
+    let route = Route {
+        segments: vec![vec![
+            point(48.8, 100.0),
+            point(48.85, 150.0),
+            point(48.9, 130.0),
+        ]],
+    };
+    let mut event = Event::new("event name".into());
+    event.route = route.summary();
+
+    // expect the front-end to request the event and its route
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    backend.expect_get_comments().returning(|_, _| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+    let route_backend_mock = route.clone();
+    backend
+        .expect_get_route()
+        .times(1)
+        .returning(move |_| Ok(route_backend_mock.clone()));
+
+    // given a user opens the event page
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+
+    // when the backend responds with the event and its route
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetRouteResponse(route)),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page displays the summary and the elevation profile of the route
+    let view = app::view(&app_);
+    assert_contains_text(&view, "route: 11.1 km, +50 m, -20 m");
+    assert_contains_text(&view, "elevation between 100 m and 150 m over 11.1 km");
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Duration};
+use common::api::MockBackendApi;
+use common::{Event, EventPatch};
+use frontend::app::{self, Msg};
+use frontend::atoms::{button, input};
+use frontend::molecules::event_publication_form;
+use frontend::orders::{MyOrders, OrdersImplementation, OrdersMock};
+use frontend::pages::edit_event;
+use seed::Url;
+use std::rc::Rc;
+
+mod html_query;
+use html_query::assert_contains_text;
+
+fn form_msg(msg: event_publication_form::PrivateMsg) -> Msg {
+    Msg::EditEvent(edit_event::Msg::Private(
+        edit_event::PrivateMsg::EventPublicationForm(event_publication_form::Msg::Private(msg)),
+    ))
+}
+
+#[test]
+fn edit_page_sends_the_cleared_fields_to_the_backend() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given an event with an end and a capacity, organized by the user
+    let mut event = Event::new("event name".into());
+    let start = DateTime::parse_from_rfc3339("2030-06-01T09:00:00+02:00").unwrap();
+    event.start = Some(start);
+    event.end = Some(start + Duration::hours(3));
+    event.capacity = Some(10);
+    event.organizer = Some("organizer".into());
+
+    // expect the front-end to request the event and the notifications of the user, then to send
+    // a patch clearing only the end and the capacity
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    backend.expect_get_notifications().returning(|| Ok(vec![]));
+    let mut updated_event = event.clone();
+    updated_event.end = None;
+    updated_event.capacity = None;
+    let id = event.id;
+    backend
+        .expect_update_event()
+        .withf(move |patch_id, patch| {
+            *patch_id == id
+                && *patch
+                    == EventPatch {
+                        end: Some(None),
+                        capacity: Some(None),
+                        ..EventPatch::default()
+                    }
+        })
+        .times(1)
+        .returning(move |_, _| Ok(updated_event.clone()));
+
+    // given the organizer opens the edit page of the event
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string(), "edit".to_owned()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+    app_.context.username = Some("organizer".into());
+
+    // when the backend responds with the event
+    app::testable_update(
+        Msg::EditEvent(edit_event::Msg::Private(
+            edit_event::PrivateMsg::OnGetEventResponse(Box::new(event.clone())),
+        )),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page shows the form to edit the event
+    let view = app::view(&app_);
+    assert_contains_text(&view, "edit the event");
+
+    // when the organizer empties the duration and the capacity and saves the event
+    for msg in [
+        event_publication_form::PrivateMsg::Duration(input::Msg::ValueChange("".into())),
+        event_publication_form::PrivateMsg::Capacity(input::Msg::ValueChange("".into())),
+        event_publication_form::PrivateMsg::PublishButton(button::Msg::Click),
+    ] {
+        app::testable_update(form_msg(msg), &mut app_, &mut orders);
+    }
+
+    // then the backend is asked to clear them, which the mock checks when dropped
+}
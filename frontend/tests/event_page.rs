@@ -0,0 +1,53 @@
+use common::api::MockBackendApi;
+use common::{Event, Page};
+use frontend::app::{self, Msg};
+use frontend::orders::{MyOrders, OrdersImplementation, OrdersMock};
+use frontend::pages::event;
+use seed::virtual_dom::{Node, Tag};
+use seed::Url;
+use std::rc::Rc;
+
+/// Open the page of `event` as `username` and respond with the event.
+pub fn open_event_page(event: &Event, username: &str) -> app::Model {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // expect the front-end to request the event, its comments and the notifications of the user
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    backend.expect_get_comments().returning(|_, _| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+    backend.expect_get_notifications().returning(|| Ok(vec![]));
+
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+    app_.context.username = Some(username.into());
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+    app_
+}
+
+/// Labels of the buttons of an html node tree, in the order they appear
+pub fn button_labels(html: &Node<Msg>) -> Vec<String> {
+    match html {
+        Node::Element(el) if el.tag == Tag::Button => {
+            vec![el
+                .children
+                .iter()
+                .filter_map(|child| child.text())
+                .map(|text| text.text.to_string())
+                .collect()]
+        }
+        Node::Element(el) => el.children.iter().flat_map(button_labels).collect(),
+        _ => vec![],
+    }
+}
@@ -0,0 +1,136 @@
+use common::api::MockBackendApi;
+use common::{ApiError, ErrorCode, Event, EventUpdate, Page};
+use frontend::app::{self, Msg};
+use frontend::orders::{MyOrders, OrdersImplementation, OrdersMock};
+use frontend::pages::{event, main};
+use seed::Url;
+use std::rc::Rc;
+
+mod html_query;
+use html_query::assert_contains_text;
+
+#[test]
+fn main_page_displays_the_events_published_and_changed_by_others() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given a user displays the list of events
+    let event_1 = Event::new("event 1 name".into());
+    let mut backend = MockBackendApi::new();
+    let events_backend_mock = Page {
+        items: vec![event_1.clone()],
+        next_cursor: None,
+    };
+    backend
+        .expect_get_events()
+        .returning(move |_| Ok(events_backend_mock.clone()));
+    let mut app_ = app::testable_init(Url::new(), &mut orders, Rc::new(backend));
+    app::testable_update(
+        app::Msg::Main(main::Msg::OnGetEventsResponse(Page {
+            items: vec![event_1.clone()],
+            next_cursor: None,
+        })),
+        &mut app_,
+        &mut orders,
+    );
+
+    // when another user publishes an event
+    let event_2 = Event::new("event 2 name".into());
+    app::testable_update(
+        app::Msg::EventUpdated(Box::new(EventUpdate::Created {
+            event: event_2.clone(),
+        })),
+        &mut app_,
+        &mut orders,
+    );
+
+    // and when the organizer of the first event renames it
+    let mut renamed = event_1.clone();
+    renamed.name = "renamed event".into();
+    app::testable_update(
+        app::Msg::EventUpdated(Box::new(EventUpdate::Updated { event: renamed })),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the list displays the new event and the new name of the first one
+    let view = app::view(&app_);
+    assert_contains_text(&view, "event 2 name");
+    assert_contains_text(&view, "renamed event");
+}
+
+#[test]
+fn event_page_displays_the_users_joining_the_event() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given a user displays an event
+    let mut event = Event::new("event name".into());
+    event.capacity = Some(2);
+    let mut backend = MockBackendApi::new();
+    let event_backend_mock = event.clone();
+    backend
+        .expect_get_event()
+        .returning(move |_| Ok(event_backend_mock.clone()));
+    backend.expect_get_comments().returning(|_, _| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+    let url = Url::new().set_path(&["event".to_owned(), event.id.to_string()]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+    app::testable_update(
+        app::Msg::Event(event::Msg::OnGetEventResponse(Box::new(event.clone()))),
+        &mut app_,
+        &mut orders,
+    );
+
+    // when another user joins the event
+    let mut joined = event.clone();
+    joined.join("rider");
+    app::testable_update(
+        app::Msg::EventUpdated(Box::new(EventUpdate::Joined {
+            event: joined,
+            username: "rider".into(),
+        })),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page displays the new participant
+    let view = app::view(&app_);
+    assert_contains_text(&view, "rider");
+    assert_contains_text(&view, "participants: 1/2");
+}
+
+#[test]
+fn live_updates_are_followed_again_after_a_failure() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given the connection to the stream of updates keeps failing
+    let mut backend = MockBackendApi::new();
+    backend.expect_get_events().returning(|_| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+    backend
+        .expect_next_event_update()
+        .times(7)
+        .returning(|| Err(ApiError::new(ErrorCode::Network, "connection lost")));
+    let mut app_ = app::testable_init(Url::new(), &mut orders, Rc::new(backend));
+
+    // when following the events live fails, and again after each new attempt
+    let mut failure = app::Msg::LiveUpdatesFailed(ApiError::new(ErrorCode::Network, "offline"));
+    for _ in 0..7 {
+        app::testable_update(failure, &mut app_, &mut orders);
+        failure = orders.mock().unwrap().messages().last().unwrap().clone();
+        assert!(matches!(failure, app::Msg::LiveUpdatesFailed(_)));
+    }
+
+    // then the front-end tries again, waiting twice longer each time up to a minute
+    assert_eq!(
+        orders.mock().unwrap().delays(),
+        &vec![1000, 2000, 4000, 8000, 16000, 32000, 60000]
+    );
+}
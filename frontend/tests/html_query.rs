@@ -12,7 +12,7 @@ pub fn assert_contains_text(html: &Node<Msg>, text: &str) {
     assert!(
         matches!(get_element_by_contents(html, text), Some(..)),
         "the view does not contain a node with the text 'event 1 name':\n{}",
-        highlight_html_syntax(&indent(&html))
+        highlight_html_syntax(&indent(html))
     )
 }
 
@@ -45,7 +45,7 @@ fn get_element_by_contents<'a>(node: &'a Node<Msg>, contents: &str) -> Option<&'
 }
 
 fn indent(node: &Node<Msg>) -> String {
-    IndentedHtml { node: node }.to_string()
+    IndentedHtml { node }.to_string()
 }
 
 fn highlight_html_syntax(html: &str) -> String {
@@ -97,7 +97,7 @@ impl<'a> IndentedHtml<'a> {
                 if el.children.len() > 1 {
                     for child in &el.children {
                         IndentedHtml::write_node(child, f, indentation + 1)?;
-                        write!(f, "\n")?;
+                        writeln!(f)?;
                     }
                     write!(f, "{}</{}>", "  ".repeat(indentation), tag)?;
                 } else {
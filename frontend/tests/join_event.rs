@@ -0,0 +1,42 @@
+use common::Event;
+use frontend::app;
+
+mod event_page;
+use event_page::{button_labels, open_event_page};
+
+mod html_query;
+use html_query::assert_contains_text;
+
+#[test]
+fn event_page_offers_to_join_the_waitlist_of_a_full_event() {
+    // given a full event
+    let mut event = Event::new("event name".into());
+    event.capacity = Some(1);
+    event.join("participant");
+
+    // when another user opens the event page
+    let app_ = open_event_page(&event, "user");
+
+    // then the page offers the user to join the waitlist
+    let view = app::view(&app_);
+    assert_contains_text(&view, "participants: 1/1");
+    assert!(button_labels(&view).contains(&"join the waitlist".to_owned()));
+}
+
+#[test]
+fn event_page_displays_the_position_in_the_waitlist() {
+    // given a full event with two waitlisted users
+    let mut event = Event::new("event name".into());
+    event.capacity = Some(1);
+    event.join("participant");
+    event.join("first");
+    event.join("second");
+
+    // when the second waitlisted user opens the event page
+    let app_ = open_event_page(&event, "second");
+
+    // then the page displays their position and offers them to leave the waitlist
+    let view = app::view(&app_);
+    assert_contains_text(&view, "you are waitlisted at position 2");
+    assert_contains_text(&view, "leave the waitlist");
+}
@@ -0,0 +1,35 @@
+use common::Event;
+use frontend::app;
+
+mod event_page;
+use event_page::{button_labels, open_event_page};
+
+#[test]
+fn event_page_offers_participants_to_leave_the_event() {
+    // given an event joined by a user
+    let mut event = Event::new("event name".into());
+    event.participants.insert("participant".into());
+
+    // when the participant opens the event page
+    let app_ = open_event_page(&event, "participant");
+
+    // then the page offers the user to leave the event rather than to join it
+    let labels = button_labels(&app::view(&app_));
+    assert!(labels.contains(&"leave".to_owned()), "{:?}", labels);
+    assert!(!labels.contains(&"join".to_owned()), "{:?}", labels);
+}
+
+#[test]
+fn event_page_offers_other_users_to_join_the_event() {
+    // given an event joined by a user
+    let mut event = Event::new("event name".into());
+    event.participants.insert("participant".into());
+
+    // when another user opens the event page
+    let app_ = open_event_page(&event, "user");
+
+    // then the page offers the user to join the event rather than to leave it
+    let labels = button_labels(&app::view(&app_));
+    assert!(labels.contains(&"join".to_owned()), "{:?}", labels);
+    assert!(!labels.contains(&"leave".to_owned()), "{:?}", labels);
+}
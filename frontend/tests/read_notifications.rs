@@ -0,0 +1,143 @@
+use chrono::Utc;
+use common::api::MockBackendApi;
+use common::{Id, Notification, Page};
+use frontend::app::{self, Msg};
+use frontend::atoms::button;
+use frontend::molecules::login_bar;
+use frontend::orders::{MyOrders, OrdersImplementation, OrdersMock};
+use frontend::pages::{main, notifications};
+use seed::Url;
+use std::rc::Rc;
+
+mod html_query;
+use html_query::assert_contains_text;
+
+fn notification(text: &str, read: bool) -> Notification {
+    Notification {
+        id: Id::new_v4(),
+        text: text.into(),
+        event_id: Some(Id::new_v4()),
+        created_at: Utc::now(),
+        read,
+    }
+}
+
+#[test]
+fn login_bar_counts_the_unread_notifications() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given a user with 2 unread notifications
+    let inbox = vec![
+        notification("rider joined sunday ride", false),
+        notification("sunday ride changed", false),
+        notification("rider joined saturday ride", true),
+    ];
+
+    // expect the front-end to request the events and the notifications
+    let mut backend = MockBackendApi::new();
+    backend.expect_get_events().returning(|_| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+    let inbox_backend_mock = inbox.clone();
+    backend
+        .expect_get_notifications()
+        .returning(move || Ok(inbox_backend_mock.clone()));
+
+    // given the user opens the main page
+    let mut app_ = app::testable_init(Url::new(), &mut orders, Rc::new(backend));
+    app_.context.username = Some("organizer".into());
+    app::testable_update(
+        app::Msg::Main(main::Msg::OnGetEventsResponse(Page {
+            items: vec![],
+            next_cursor: None,
+        })),
+        &mut app_,
+        &mut orders,
+    );
+
+    // when the backend responds with the notifications
+    app::testable_update(
+        app::Msg::Main(main::Msg::LoginBar(login_bar::Msg::Private(
+            login_bar::PrivateMsg::OnGetNotifications(inbox),
+        ))),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the bell shows the number of unread notifications
+    let view = app::view(&app_);
+    assert_contains_text(&view, "🔔 2");
+}
+
+#[test]
+fn notifications_page_lists_the_notifications_and_marks_them_read() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // given a user with 2 unread notifications
+    let joined = notification("rider joined sunday ride", false);
+    let changed = notification("sunday ride changed", false);
+    let inbox = vec![joined.clone(), changed];
+
+    // expect the front-end to request the notifications, to mark one read and then all
+    let mut backend = MockBackendApi::new();
+    let inbox_backend_mock = inbox.clone();
+    backend
+        .expect_get_notifications()
+        .returning(move || Ok(inbox_backend_mock.clone()));
+    let joined_id = joined.id;
+    backend
+        .expect_mark_notification_read()
+        .withf(move |id| *id == joined_id)
+        .times(1)
+        .returning(|_| Ok(()));
+    backend
+        .expect_mark_all_notifications_read()
+        .times(1)
+        .returning(|| Ok(()));
+
+    // given the user opens the notifications page
+    let url = Url::new().set_path(["notifications"]);
+    let mut app_ = app::testable_init(url, &mut orders, Rc::new(backend));
+    app_.context.username = Some("organizer".into());
+
+    // when the backend responds with the notifications
+    app::testable_update(
+        app::Msg::Notifications(notifications::Msg::OnGetNotifications(inbox)),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the page lists them and offers to mark them read
+    let view = app::view(&app_);
+    assert_contains_text(&view, "rider joined sunday ride");
+    assert_contains_text(&view, "sunday ride changed");
+    assert_contains_text(&view, "mark read");
+    assert_contains_text(&view, "mark all read");
+    assert_contains_text(&view, "🔔 2");
+
+    // when the user marks the first one read
+    app::testable_update(
+        app::Msg::Notifications(notifications::Msg::MarkReadButton(joined.id)),
+        &mut app_,
+        &mut orders,
+    );
+    app::testable_update(
+        app::Msg::Notifications(notifications::Msg::OnMarkedRead(joined.id)),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then one notification is left unread
+    let view = app::view(&app_);
+    assert_contains_text(&view, "🔔 1");
+
+    // when the user marks all of them read
+    app::testable_update(
+        app::Msg::Notifications(notifications::Msg::MarkAllReadButton(button::Msg::Click)),
+        &mut app_,
+        &mut orders,
+    );
+}
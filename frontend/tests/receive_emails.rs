@@ -0,0 +1,74 @@
+use common::api::MockBackendApi;
+use common::Page;
+use frontend::app::{self, Msg};
+use frontend::atoms::{button, input};
+use frontend::molecules::login_bar;
+use frontend::orders::{MyOrders, OrdersImplementation, OrdersMock};
+use frontend::pages::main;
+use seed::Url;
+use std::rc::Rc;
+
+mod html_query;
+use html_query::assert_contains_text;
+
+fn login_bar_msg(msg: login_bar::PrivateMsg) -> Msg {
+    Msg::Main(main::Msg::LoginBar(login_bar::Msg::Private(msg)))
+}
+
+#[test]
+fn login_bar_sets_the_email_address_of_the_user() {
+    let mut orders = MyOrders::new(OrdersImplementation::<Msg, Msg>::Mock(OrdersMock::new()));
+
+    // expect the front-end to request the events and the notifications, then to send the email
+    // address of the user
+    let mut backend = MockBackendApi::new();
+    backend.expect_get_events().returning(|_| {
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    });
+    backend.expect_get_notifications().returning(|| Ok(vec![]));
+    backend
+        .expect_set_email()
+        .withf(|email| email == "rider@example.com")
+        .times(1)
+        .returning(|_| Ok(()));
+
+    // given a logged-in user on the main page
+    let mut app_ = app::testable_init(Url::new(), &mut orders, Rc::new(backend));
+    app_.context.username = Some("rider".into());
+    app::testable_update(
+        Msg::Main(main::Msg::OnGetEventsResponse(Page {
+            items: vec![],
+            next_cursor: None,
+        })),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the login bar offers to email the user
+    let view = app::view(&app_);
+    assert_contains_text(&view, "email me");
+
+    // when the user enters their email address and submits it
+    for msg in [
+        login_bar::PrivateMsg::EmailInput(input::Msg::ValueChange(" rider@example.com ".into())),
+        login_bar::PrivateMsg::EmailButton(button::Msg::Click),
+    ] {
+        app::testable_update(login_bar_msg(msg), &mut app_, &mut orders);
+    }
+
+    // when the backend accepts the address
+    app::testable_update(
+        login_bar_msg(login_bar::PrivateMsg::OnEmailSet(
+            "rider@example.com".into(),
+        )),
+        &mut app_,
+        &mut orders,
+    );
+
+    // then the login bar tells where the emails are sent
+    let view = app::view(&app_);
+    assert_contains_text(&view, "changes emailed to rider@example.com");
+}